@@ -0,0 +1,217 @@
+//! Measures the building blocks of a render: intersections, BVH construction and textures.
+//!
+//! Intersections and lookups are timed over batches of rays or points, so the throughput
+//! reported by criterion is in rays (or lookups) per second.
+//! Run with `cargo bench --bench primitives`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use rust_tracing::hitables::*;
+use rust_tracing::materials::{Lambertian, Material};
+use rust_tracing::samplers::Pcg32;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const SEED: u64 = 0xbe9c;
+/// Rays traced, or points looked up, per iteration
+const BATCH: usize = 1024;
+/// Sphere counts of the BVH benchmarks
+const SPHERE_COUNTS: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian {
+        albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+    })
+}
+
+/// Rays from random points of a sphere of the given radius around the origin,
+/// aimed at random points of the unit cube: most of them hit an object at the origin.
+fn rays(rng: &mut Pcg32, radius: f64) -> Vec<Ray> {
+    (0..BATCH)
+        .map(|_| {
+            let origin = unit_vector(Vec3::random_range(rng, -1.0, 1.0)) * radius;
+            let target = Vec3::random_range(rng, -1.0, 1.0);
+            Ray::new(origin, target - origin, 0.0)
+        })
+        .collect()
+}
+
+/// Small spheres scattered in a cube, about as dense whatever their count
+fn random_spheres(rng: &mut Pcg32, count: usize) -> HitableList {
+    let mut list = HitableList::new(material());
+    let side = (count as f64).cbrt() * 2.0;
+    for _ in 0..count {
+        list.push(Arc::new(Sphere {
+            center: Vec3::random_range(rng, -side, side),
+            radius: 0.5,
+            material: material(),
+        }));
+    }
+    list
+}
+
+fn intersect(c: &mut Criterion) {
+    let mut rng = Pcg32::new(SEED);
+    let rays = rays(&mut rng, 10.0);
+
+    let mut group = c.benchmark_group("intersect");
+    group.throughput(Throughput::Elements(BATCH as u64));
+
+    let sphere = Sphere {
+        center: Vec3::default(),
+        radius: 1.0,
+        material: material(),
+    };
+    group.bench_function("Sphere", |b| {
+        b.iter(|| {
+            for ray in &rays {
+                black_box(sphere.hit(ray, 0.001, f64::INFINITY));
+            }
+        })
+    });
+
+    let aabb = Aabb {
+        min: Vec3::new(-1.0, -1.0, -1.0),
+        max: Vec3::new(1.0, 1.0, 1.0),
+    };
+    group.bench_function("Aabb", |b| {
+        b.iter(|| {
+            for ray in &rays {
+                black_box(aabb.hit(ray, 0.001, f64::INFINITY));
+            }
+        })
+    });
+
+    let rect = XYRect {
+        material: material(),
+        x0: -1.0,
+        x1: 1.0,
+        y0: -1.0,
+        y1: 1.0,
+        k: 0.0,
+    };
+    group.bench_function("XYRect", |b| {
+        b.iter(|| {
+            for ray in &rays {
+                black_box(rect.hit(ray, 0.001, f64::INFINITY));
+            }
+        })
+    });
+
+    for &count in &SPHERE_COUNTS {
+        let spheres = random_spheres(&mut rng, count);
+        let bvh = BVHNode::new(&spheres, 0.0, 1.0);
+        let side = (count as f64).cbrt() * 4.0;
+        let rays = self::rays(&mut rng, side);
+        group.bench_with_input(BenchmarkId::new("BVHNode", count), &rays, |b, rays| {
+            b.iter(|| {
+                for ray in rays {
+                    black_box(bvh.hit(ray, 0.001, f64::INFINITY));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bvh_build(c: &mut Criterion) {
+    let mut rng = Pcg32::new(SEED);
+
+    let mut group = c.benchmark_group("BVHNode::new");
+    group.sample_size(10);
+    for &count in &SPHERE_COUNTS {
+        let spheres = random_spheres(&mut rng, count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &spheres,
+            |b, spheres| b.iter(|| BVHNode::new(spheres, 0.0, 1.0)),
+        );
+    }
+    group.finish();
+}
+
+fn noise(c: &mut Criterion) {
+    let mut rng = Pcg32::new(SEED);
+    let points: Vec<Vec3> = (0..BATCH)
+        .map(|_| Vec3::random_range(&mut rng, -10.0, 10.0))
+        .collect();
+    let perlin = Perlin::new(256);
+
+    let mut group = c.benchmark_group("Perlin");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.bench_function("noise", |b| {
+        b.iter(|| {
+            for p in &points {
+                black_box(perlin.noise(p));
+            }
+        })
+    });
+    group.bench_function("turbulence", |b| {
+        b.iter(|| {
+            for p in &points {
+                black_box(perlin.turbulence(p, 7));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn textures(c: &mut Criterion) {
+    let mut rng = Pcg32::new(SEED);
+    let lookups: Vec<(f64, f64, Vec3)> = (0..BATCH)
+        .map(|_| {
+            let p = Vec3::random_range(&mut rng, -10.0, 10.0);
+            let uv = Vec3::random(&mut rng);
+            (uv.x, uv.y, p)
+        })
+        .collect();
+
+    let earth = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/images/earthmap.jpg");
+    let mut texture_manager = ResourceManager::new();
+    let white = TextureConfig {
+        kind: TextureKind::Constant(Color::new(255, 255, 255)),
+    };
+    let black = TextureConfig {
+        kind: TextureKind::Constant(Color::new(0, 0, 0)),
+    };
+    let textures = [
+        ("SolidColor", white),
+        (
+            "CheckerTexture",
+            TextureConfig {
+                kind: TextureKind::Checker(&white, &black),
+            },
+        ),
+        (
+            "ImageTexture",
+            TextureConfig {
+                kind: TextureKind::FromFile(&earth),
+            },
+        ),
+        (
+            "PerlinTexture",
+            TextureConfig {
+                kind: TextureKind::Perlin(256, 4),
+            },
+        ),
+    ];
+
+    let mut group = c.benchmark_group("Texture::value");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    for (name, config) in &textures {
+        let texture = texture_manager.get_resource(config);
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                for (u, v, p) in &lookups {
+                    black_box(texture.value(*u, *v, p));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, intersect, bvh_build, noise, textures);
+criterion_main!(benches);
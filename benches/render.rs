@@ -0,0 +1,87 @@
+//! Renders the built-in scenes at a small resolution, BVH build included.
+//!
+//! The throughput reported by criterion is in camera rays per second, the number to track
+//! over time. Renders run on a single thread so the numbers don't depend on the machine's
+//! core count.
+//! Run with `cargo bench --bench render`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use rust_tracing::cameras::ThinLensCamera;
+use rust_tracing::integrators::PathTracer;
+use rust_tracing::render::*;
+use rust_tracing::samplers::SamplerKind;
+use rust_tracing::scenes::{preset, Preset};
+use rust_tracing::scheduler::TileOrder;
+use rust_tracing::structs::*;
+
+use std::sync::Arc;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 36;
+const SAMPLES: u32 = 4;
+const SEED: u64 = 0;
+/// Built-in scenes rendered, by number and name
+const SCENES: [(u32, &str); 5] = [
+    (0, "random_scene"),
+    (2, "two_perlin_spheres"),
+    (3, "earth"),
+    (4, "simple_light"),
+    (5, "cornell_box"),
+];
+
+fn render_scene(preset: &Preset) {
+    let camera = Arc::new(ThinLensCamera::new_look_at(
+        preset.camera.lookfrom,
+        preset.camera.lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        preset.camera.vfov,
+        f64::from(WIDTH) / f64::from(HEIGHT),
+        preset.camera.aperture,
+        preset.camera.focus_dist,
+        0.0,
+        1.0,
+    ));
+    let film = Film::new(WIDTH, HEIGHT, Filter::Box { radius: 0.5 }, None);
+    let progress = Progress {
+        time_budget: None,
+        samples_per_pass: SAMPLES,
+        thread_count: 1,
+        tile_size: 16,
+        tile_order: TileOrder::Scanline,
+        snapshot: None,
+        checkpoint: None,
+    };
+    render(
+        &film,
+        Sampling::Uniform(SAMPLES),
+        &progress,
+        PathSettings::new(preset.max_depth),
+        ColorMode::Rgb,
+        &PathTracer,
+        &preset.world,
+        camera,
+        (0.0, 1.0),
+        SamplerKind::Independent,
+        SEED,
+        &AovLayout::new(&[], 0),
+        None,
+    )
+    .expect("the render was interrupted");
+}
+
+fn render_scenes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(u64::from(WIDTH * HEIGHT * SAMPLES)));
+    for &(scene, name) in &SCENES {
+        let preset = preset(scene, SEED);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &preset, |b, preset| {
+            b.iter(|| render_scene(preset))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render_scenes);
+criterion_main!(benches);
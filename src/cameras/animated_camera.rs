@@ -0,0 +1,141 @@
+use super::{shutter_time, Camera, CameraAnimation, CameraSettings};
+use crate::samplers::{Sampler, CAMERA_DIMENSION};
+use crate::structs::Ray;
+
+use std::sync::Arc;
+
+/// Intervals of the shutter between two cameras built from the animation
+const CAMERA_STEPS: usize = 8;
+
+/// Camera following its animation while the shutter is open, so its motion blurs the frame.
+/// Cameras are built at regular times of the shutter interval, a ray at a time between two
+/// of them is interpolated from the rays both trace with the same numbers.
+pub struct AnimatedCamera {
+    /// Times and the cameras placed by the animation at them
+    steps: Vec<(f64, Arc<dyn Camera>)>,
+    time0: f64,
+    time1: f64,
+}
+
+impl AnimatedCamera {
+    /// build makes the camera of some settings whose rays leave at the given time
+    pub fn new<F>(animation: &CameraAnimation, shutter: (f64, f64), build: F) -> AnimatedCamera
+    where
+        F: Fn(&CameraSettings, f64) -> Arc<dyn Camera>,
+    {
+        let (time0, time1) = shutter;
+        let steps = if time1 > time0 { CAMERA_STEPS } else { 0 };
+        AnimatedCamera {
+            steps: (0..=steps)
+                .map(|i| {
+                    let time = time0 + (time1 - time0) * i as f64 / steps.max(1) as f64;
+                    (time, build(&animation.at(time), time))
+                })
+                .collect(),
+            time0,
+            time1,
+        }
+    }
+
+    /// Time of a ray, the step before it and how far it is toward the next one
+    fn step(&self, sampler: &mut dyn Sampler) -> (f64, usize, f64) {
+        // The shutter time follows the 2D lens sample
+        sampler.set_dimension(CAMERA_DIMENSION + 2);
+        let time = shutter_time(self.time0, self.time1, sampler);
+        if self.steps.len() < 2 {
+            return (time, 0, 0.0);
+        }
+        let position = (time - self.time0) / (self.time1 - self.time0) * CAMERA_STEPS as f64;
+        let i = (position as usize).min(CAMERA_STEPS - 1);
+        (time, i, position - i as f64)
+    }
+}
+
+impl Camera for AnimatedCamera {
+    /// Ray of the camera where the animation has it at the time of the ray
+    fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
+        let (time, i, s) = self.step(sampler);
+        sampler.set_dimension(CAMERA_DIMENSION);
+        let ray = self.steps[i].1.get_ray(u, v, sampler);
+        match self.steps.get(i + 1) {
+            Some((_, next)) => {
+                sampler.set_dimension(CAMERA_DIMENSION);
+                lerp(&ray, &next.get_ray(u, v, sampler), s, time)
+            }
+            None => Ray::new(ray.origin(), ray.direction(), time),
+        }
+    }
+
+    fn sample_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<(Ray, f64)> {
+        let (time, i, s) = self.step(sampler);
+        sampler.set_dimension(CAMERA_DIMENSION);
+        let (ray, weight) = self.steps[i].1.sample_ray(u, v, sampler)?;
+        match self.steps.get(i + 1) {
+            Some((_, next)) => {
+                sampler.set_dimension(CAMERA_DIMENSION);
+                let (next_ray, next_weight) = next.sample_ray(u, v, sampler)?;
+                Some((
+                    lerp(&ray, &next_ray, s, time),
+                    weight + (next_weight - weight) * s,
+                ))
+            }
+            None => Some((Ray::new(ray.origin(), ray.direction(), time), weight)),
+        }
+    }
+}
+
+/// Ray between a and b at s, leaving at time
+fn lerp(a: &Ray, b: &Ray, s: f64, time: f64) -> Ray {
+    Ray::new(
+        a.origin() + (b.origin() - a.origin()) * s,
+        a.direction() + (b.direction() - a.direction()) * s,
+        time,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cameras::ThinLensCamera;
+    use crate::samplers::IndependentSampler;
+    use crate::structs::{Interpolation, Track, Vec3};
+
+    #[test]
+    fn camera_moves_during_the_shutter() {
+        // Pinhole sliding along x, one unit per unit of time
+        let mut animation = CameraAnimation::new(CameraSettings {
+            lookfrom: Vec3::new(0.0, 0.0, 10.0),
+            lookat: Vec3::default(),
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_dist: 10.0,
+        });
+        animation.lookfrom = Track::new(Interpolation::Linear, 0.0, Vec3::new(0.0, 0.0, 10.0))
+            .key(1.0, Vec3::new(1.0, 0.0, 10.0));
+        let camera = AnimatedCamera::new(&animation, (0.0, 1.0), |settings, time| {
+            Arc::new(ThinLensCamera::new_look_at(
+                settings.lookfrom,
+                settings.lookat,
+                Vec3::new(0.0, 1.0, 0.0),
+                settings.vfov,
+                1.0,
+                settings.aperture,
+                settings.focus_dist,
+                time,
+                time,
+            ))
+        });
+
+        let mut sampler = IndependentSampler::new(0);
+        let (mut first, mut last) = (f64::MAX, f64::MIN);
+        for index in 0..64 {
+            sampler.start_pixel_sample(0, 0, index);
+            let (ray, weight) = camera.sample_ray(0.5, 0.5, &mut sampler).unwrap();
+            assert_eq!(weight, 1.0);
+            assert!((ray.origin().x - ray.time()).abs() < 1e-9);
+            first = first.min(ray.time());
+            last = last.max(ray.time());
+        }
+        assert!(first < 0.25 && last > 0.75);
+    }
+}
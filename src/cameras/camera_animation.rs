@@ -0,0 +1,45 @@
+use crate::structs::{Track, Vec3};
+
+/// Placement and lens settings of a camera at one point in time
+#[derive(Debug, Copy, Clone)]
+pub struct CameraSettings {
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    /// Vertical field of view, in degrees
+    pub vfov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+}
+
+/// Keyframed camera settings
+#[derive(Debug, Clone)]
+pub struct CameraAnimation {
+    pub lookfrom: Track<Vec3>,
+    pub lookat: Track<Vec3>,
+    pub vfov: Track<f64>,
+    pub aperture: Track<f64>,
+    pub focus_dist: Track<f64>,
+}
+
+impl CameraAnimation {
+    /// Camera holding still until tracks are replaced
+    pub fn new(settings: CameraSettings) -> CameraAnimation {
+        CameraAnimation {
+            lookfrom: Track::constant(settings.lookfrom),
+            lookat: Track::constant(settings.lookat),
+            vfov: Track::constant(settings.vfov),
+            aperture: Track::constant(settings.aperture),
+            focus_dist: Track::constant(settings.focus_dist),
+        }
+    }
+
+    pub fn at(&self, time: f64) -> CameraSettings {
+        CameraSettings {
+            lookfrom: self.lookfrom.at(time),
+            lookat: self.lookat.at(time),
+            vfov: self.vfov.at(time),
+            aperture: self.aperture.at(time),
+            focus_dist: self.focus_dist.at(time),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{shutter_time, Camera};
+use crate::samplers::Sampler;
+use crate::structs::{cross, unit_vector, Ray, Vec3};
+
+use std::f64::consts::PI;
+
+/// 360° camera producing a latitude-longitude panorama.
+/// The image center looks toward lookat.
+pub struct EquirectangularCamera {
+    pub origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    /// Signed distance of the eye from the origin, for omni-directional stereo
+    eye_offset: f64,
+    // shutter open/close times
+    time0: f64,
+    time1: f64,
+}
+
+impl EquirectangularCamera {
+    pub fn new_look_at(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        t0: f64,
+        t1: f64,
+    ) -> EquirectangularCamera {
+        let w = unit_vector(lookfrom - lookat);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+
+        EquirectangularCamera {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            eye_offset: 0.0,
+            time0: t0,
+            time1: t1,
+        }
+    }
+
+    /// Move the eye sideways of the viewing direction, on a circle of radius |offset|.
+    /// Negative offsets give the left eye, positive ones the right eye.
+    pub fn with_eye_offset(mut self, offset: f64) -> EquirectangularCamera {
+        self.eye_offset = offset;
+        self
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let longitude = (s - 0.5) * 2.0 * PI;
+        let latitude = (t - 0.5) * PI;
+
+        let direction = latitude.cos() * (longitude.sin() * self.u - longitude.cos() * self.w)
+            + latitude.sin() * self.v;
+        let right = longitude.cos() * self.u + longitude.sin() * self.w;
+
+        Ray::new(
+            self.origin + self.eye_offset * right,
+            direction,
+            shutter_time(self.time0, self.time1, sampler),
+        )
+    }
+}
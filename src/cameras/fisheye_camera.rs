@@ -0,0 +1,88 @@
+use super::{shutter_time, Camera};
+use crate::samplers::Sampler;
+use crate::structs::{cross, unit_vector, Ray, Vec3};
+
+/// How the angle from the optical axis maps to the distance from the image center
+#[derive(Debug, Copy, Clone)]
+pub enum FisheyeMapping {
+    /// r = f θ, angles are preserved along the radius
+    Equidistant,
+    /// r = 2 f sin(θ/2), areas are preserved
+    Equisolid,
+}
+
+/// Full frame fisheye camera, the field of view covers the image diagonal
+pub struct FisheyeCamera {
+    pub origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    aspect: f64,
+    half_fov: f64,
+    mapping: FisheyeMapping,
+    // shutter open/close times
+    time0: f64,
+    time1: f64,
+}
+
+impl FisheyeCamera {
+    /// fov is the diagonal field of view in degrees, up to 360
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_look_at(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        fov: f64,
+        aspect: f64,
+        mapping: FisheyeMapping,
+        t0: f64,
+        t1: f64,
+    ) -> FisheyeCamera {
+        let w = unit_vector(lookfrom - lookat);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+
+        FisheyeCamera {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            aspect,
+            half_fov: fov.min(360.0).to_radians() / 2.0,
+            mapping,
+            time0: t0,
+            time1: t1,
+        }
+    }
+
+    /// Angle from the optical axis of a point at a relative distance
+    /// (0 in the center, 1 in a corner) from the image center
+    fn angle(&self, r: f64) -> f64 {
+        match self.mapping {
+            FisheyeMapping::Equidistant => r * self.half_fov,
+            FisheyeMapping::Equisolid => {
+                let max = (self.half_fov / 2.0).sin();
+                2.0 * (r * max).min(1.0).asin()
+            }
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let x = (s - 0.5) * self.aspect;
+        let y = t - 0.5;
+        let r = (x * x + y * y).sqrt();
+        let r_max = 0.5 * (self.aspect * self.aspect + 1.0).sqrt();
+
+        let theta = self.angle(r / r_max);
+        let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (0.0, 0.0) };
+        let direction = theta.sin() * (cos_phi * self.u + sin_phi * self.v) - theta.cos() * self.w;
+
+        Ray::new(
+            self.origin,
+            direction,
+            shutter_time(self.time0, self.time1, sampler),
+        )
+    }
+}
@@ -2,9 +2,39 @@ mod pinhole_camera;
 pub use pinhole_camera::*;
 mod thin_lens_camera;
 pub use thin_lens_camera::*;
+mod orthographic_camera;
+pub use orthographic_camera::*;
+mod fisheye_camera;
+pub use fisheye_camera::*;
+mod equirectangular_camera;
+pub use equirectangular_camera::*;
+mod stereo_camera;
+pub use stereo_camera::*;
+mod realistic_camera;
+pub use realistic_camera::*;
+mod camera_animation;
+pub use camera_animation::*;
+mod animated_camera;
+pub use animated_camera::*;
 
+use crate::samplers::Sampler;
 use crate::structs::Ray;
 
 pub trait Camera: Send + Sync {
-    fn get_ray(&self, u: f64, v: f64) -> Ray;
+    fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray;
+
+    /// Ray with the fraction of light the camera lets through along it,
+    /// None when the ray is blocked inside the camera
+    fn sample_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<(Ray, f64)> {
+        Some((self.get_ray(u, v, sampler), 1.0))
+    }
+}
+
+/// Random time between shutter opening and closing
+fn shutter_time(time0: f64, time1: f64, sampler: &mut dyn Sampler) -> f64 {
+    if time1 > time0 {
+        time0 + (time1 - time0) * sampler.get_1d()
+    } else {
+        time0
+    }
 }
@@ -0,0 +1,55 @@
+use super::{shutter_time, Camera};
+use crate::samplers::Sampler;
+use crate::structs::{cross, unit_vector, Ray, Vec3};
+
+/// Parallel projection, no perspective, for technical drawings
+pub struct OrthographicCamera {
+    pub origin: Vec3,
+    lower_left_corner: Vec3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    direction: Vec3,
+    // shutter open/close times
+    time0: f64,
+    time1: f64,
+}
+
+impl OrthographicCamera {
+    /// view_height is the height of the visible area in world units
+    pub fn new_look_at(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        view_height: f64,
+        aspect: f64,
+        t0: f64,
+        t1: f64,
+    ) -> OrthographicCamera {
+        let half_height = view_height / 2.0;
+        let half_width = aspect * half_height;
+
+        let w = unit_vector(lookfrom - lookat);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+
+        OrthographicCamera {
+            origin: lookfrom,
+            lower_left_corner: lookfrom - half_width * u - half_height * v,
+            horizontal: 2.0 * half_width * u,
+            vertical: 2.0 * half_height * v,
+            direction: -w,
+            time0: t0,
+            time1: t1,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
+        Ray::new(
+            self.lower_left_corner + u * self.horizontal + v * self.vertical,
+            self.direction,
+            shutter_time(self.time0, self.time1, sampler),
+        )
+    }
+}
@@ -1,8 +1,7 @@
-use super::Camera;
+use super::{shutter_time, Camera};
+use crate::samplers::Sampler;
 use crate::structs::{cross, unit_vector, Ray, Vec3};
 
-use rand::Rng;
-
 pub struct PinholeCamera {
     pub origin: Vec3,
     lower_left_corner: Vec3,
@@ -55,8 +54,10 @@ impl PinholeCamera {
             time1: t1,
         }
     }
+}
 
-    pub fn default() -> PinholeCamera {
+impl Default for PinholeCamera {
+    fn default() -> PinholeCamera {
         PinholeCamera {
             origin: Vec3::default(),
             lower_left_corner: Vec3::new(-2.0, -1.0, -1.0),
@@ -69,11 +70,11 @@ impl PinholeCamera {
 }
 
 impl Camera for PinholeCamera {
-    fn get_ray(&self, u: f64, v: f64) -> Ray {
+    fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
         Ray::new(
             self.origin,
             self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin,
-            rand::thread_rng().gen_range(self.time0, self.time1), // TODO pre-gen for better performances
+            shutter_time(self.time0, self.time1, sampler),
         )
     }
 }
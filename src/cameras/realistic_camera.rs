@@ -0,0 +1,611 @@
+use super::{shutter_time, Camera};
+use crate::samplers::Sampler;
+use crate::structs::{cross, dot, unit_vector, Ray, Vec3};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Number of film rings with a precomputed exit pupil
+const PUPIL_RINGS: usize = 32;
+/// Film positions and rear element grid resolution used to bound each ring's exit pupil
+const PUPIL_FILM_SAMPLES: usize = 8;
+const PUPIL_GRID: usize = 48;
+/// Rays get_ray samples before giving up on a film point the lens blocks
+const MAX_RAY_ATTEMPTS: usize = 64;
+/// Rear element to film distances are rounded to this, in millimeters, so cameras focused
+/// a hair apart share their exit pupils
+const FOCUS_STEP: f64 = 1e-3;
+
+/// One spherical interface of a lens, in millimeters.
+/// A zero radius marks the aperture stop.
+#[derive(Debug, Copy, Clone)]
+pub struct LensElement {
+    pub radius: f64,
+    /// Distance to the next interface toward the film
+    pub thickness: f64,
+    /// Index of refraction of the medium behind the interface, toward the film
+    pub ior: f64,
+    pub aperture_radius: f64,
+}
+
+/// Bits of the stop diameter, blades, bits of the film diagonal and focus step
+type PupilKey = (u64, u32, u64, i64);
+
+/// Lens prescription, listed from the scene side to the film side
+#[derive(Debug)]
+pub struct LensSystem {
+    pub elements: Vec<LensElement>,
+    /// Exit pupils bounded so far
+    pupils: Mutex<HashMap<PupilKey, Arc<ExitPupils>>>,
+}
+
+impl LensSystem {
+    /// Read a prescription with one interface per line:
+    /// radius, thickness, index of refraction and aperture diameter, in millimeters.
+    /// Lines starting with # are comments.
+    pub fn from_file(path: &Path) -> Result<LensSystem, Box<dyn Error>> {
+        let mut elements = Vec::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.len() != 4 {
+                return Err(format!("line {}: expected 4 values", number + 1).into());
+            }
+            elements.push(LensElement {
+                radius: values[0],
+                thickness: values[1],
+                ior: if values[2] == 0.0 { 1.0 } else { values[2] },
+                aperture_radius: values[3] / 2.0,
+            });
+        }
+        if elements.is_empty() {
+            return Err("lens prescription is empty".into());
+        }
+        Ok(LensSystem {
+            elements,
+            pupils: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// Rectangle on the rear element plane
+#[derive(Debug, Copy, Clone)]
+struct Bounds {
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Bounds {
+    fn area(&self) -> f64 {
+        (self.max.0 - self.min.0).max(0.0) * (self.max.1 - self.min.1).max(0.0)
+    }
+}
+
+/// Exit pupils of the film rings for a setting of the lens
+#[derive(Debug)]
+struct ExitPupils {
+    rings: Vec<Bounds>,
+    center_area: f64,
+}
+
+/// Camera tracing rays from the film through every element of a real lens.
+/// Vignetting, distortion and aberrations come from the prescription.
+pub struct RealisticCamera {
+    pub origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    elements: Vec<LensElement>,
+    // film extent in millimeters
+    film_width: f64,
+    film_height: f64,
+    /// Number of aperture blades, 0 for a circular aperture
+    blades: u32,
+    /// Size of one world unit in millimeters
+    unit: f64,
+    exit_pupils: Arc<ExitPupils>,
+    // shutter open/close times
+    time0: f64,
+    time1: f64,
+}
+
+impl RealisticCamera {
+    /// The lens focuses on objects focus_dist world units away from the film.
+    /// film_diagonal is in millimeters, 43.27 for a full frame sensor.
+    /// aperture is the stop diameter in millimeters, None keeps the prescription's.
+    /// The exit pupils are kept by the lens for the next cameras with the same settings.
+    /// Fails when the lens can't focus that close, or blocks the rays it is focused with.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_look_at(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        up: Vec3,
+        lens: &LensSystem,
+        film_diagonal: f64,
+        aspect: f64,
+        aperture: Option<f64>,
+        blades: u32,
+        focus_dist: f64,
+        unit: f64,
+        t0: f64,
+        t1: f64,
+    ) -> Result<RealisticCamera, Box<dyn Error>> {
+        let w = unit_vector(lookfrom - lookat);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+
+        let mut elements = lens.elements.clone();
+        if let Some(diameter) = aperture {
+            for element in elements.iter_mut().filter(|e| e.radius == 0.0) {
+                element.aperture_radius = diameter / 2.0;
+            }
+        }
+
+        let film_height = film_diagonal / (aspect * aspect + 1.0).sqrt();
+        let mut camera = RealisticCamera {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            elements,
+            film_width: aspect * film_height,
+            film_height,
+            blades,
+            unit,
+            exit_pupils: Arc::new(ExitPupils {
+                rings: Vec::new(),
+                center_area: 0.0,
+            }),
+            time0: t0,
+            time1: t1,
+        };
+
+        let step = (camera.focus(focus_dist * unit)? / FOCUS_STEP).round();
+        camera.elements.last_mut().unwrap().thickness = step * FOCUS_STEP;
+        let key = (
+            aperture.unwrap_or(-1.0).to_bits(),
+            blades,
+            film_diagonal.to_bits(),
+            step as i64,
+        );
+        // Bounded without holding the lock, another thread may do the same meanwhile
+        let cached = lens.pupils.lock().unwrap().get(&key).cloned();
+        camera.exit_pupils = cached.unwrap_or_else(|| {
+            let pupils = Arc::new(camera.exit_pupils(film_diagonal));
+            lens.pupils.lock().unwrap().insert(key, pupils.clone());
+            pupils
+        });
+        Ok(camera)
+    }
+
+    /// Exit pupils of the rings of a film of the given diagonal
+    fn exit_pupils(&self, film_diagonal: f64) -> ExitPupils {
+        let r = film_diagonal / 2.0 / PUPIL_RINGS as f64;
+        ExitPupils {
+            rings: (0..PUPIL_RINGS)
+                .map(|i| self.bound_exit_pupil(i as f64 * r, (i + 1) as f64 * r))
+                .collect(),
+            center_area: self.pupil_area(self.bound_exit_pupil(0.0, 0.0)),
+        }
+    }
+
+    fn rear_z(&self) -> f64 {
+        -self.elements.last().unwrap().thickness
+    }
+
+    fn front_z(&self) -> f64 {
+        -self.elements.iter().map(|e| e.thickness).sum::<f64>()
+    }
+
+    fn is_inside_aperture(&self, element: &LensElement, x: f64, y: f64) -> bool {
+        let r = (x * x + y * y).sqrt();
+        if r > element.aperture_radius {
+            return false;
+        }
+        if element.radius != 0.0 || self.blades < 3 {
+            return true;
+        }
+        // regular polygon inscribed in the stop
+        let sector = 2.0 * PI / f64::from(self.blades);
+        let angle = y.atan2(x).rem_euclid(sector) - sector / 2.0;
+        r * angle.cos() <= element.aperture_radius * (sector / 2.0).cos()
+    }
+
+    /// Follow a ray through the interfaces, in lens space where the film
+    /// is at z = 0 and the scene toward -z.
+    /// Returns the ray leaving the lens, None when it is blocked.
+    fn trace(&self, origin: Vec3, direction: Vec3, from_film: bool) -> Option<(Vec3, Vec3)> {
+        let (mut o, mut d) = (origin, unit_vector(direction));
+        let count = self.elements.len();
+        let mut element_z = if from_film { 0.0 } else { self.front_z() };
+        for step in 0..count {
+            let i = if from_film { count - 1 - step } else { step };
+            let element = &self.elements[i];
+            if from_film {
+                element_z -= element.thickness;
+            }
+
+            let (t, normal) = if element.radius == 0.0 {
+                ((element_z - o.z) / d.z, None)
+            } else {
+                let (t, n) = intersect_sphere(o, d, element_z + element.radius, element.radius)?;
+                (t, Some(n))
+            };
+            if t <= 0.0 {
+                return None;
+            }
+            o += t * d;
+            if !self.is_inside_aperture(element, o.x, o.y) {
+                return None;
+            }
+
+            if let Some(n) = normal {
+                let behind = element.ior;
+                let before = if i == 0 {
+                    1.0
+                } else {
+                    self.elements[i - 1].ior
+                };
+                let eta = if from_film {
+                    behind / before
+                } else {
+                    before / behind
+                };
+                d = refract(d, n, eta)?;
+            }
+            if !from_film {
+                element_z += element.thickness;
+            }
+        }
+        Some((o, d))
+    }
+
+    /// Image side principal plane and focal point of a ray parallel to the axis at height x
+    fn cardinal_points(x: f64, out_origin: Vec3, out_direction: Vec3) -> (f64, f64) {
+        let t_focus = -out_origin.x / out_direction.x;
+        let t_principal = (x - out_origin.x) / out_direction.x;
+        (
+            out_origin.z + t_principal * out_direction.z,
+            out_origin.z + t_focus * out_direction.z,
+        )
+    }
+
+    /// Rear element to film distance bringing objects focus_dist millimeters
+    /// from the film into focus, using a thick lens approximation
+    fn focus(&self, focus_dist: f64) -> Result<f64, Box<dyn Error>> {
+        let x = 0.001
+            * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+
+        let from_scene = Vec3::new(x, 0.0, self.front_z() - 1.0);
+        let (o, d) = self
+            .trace(from_scene, Vec3::new(0.0, 0.0, 1.0), false)
+            .ok_or("paraxial ray blocked by the lens")?;
+        let (image_principal, image_focal) = Self::cardinal_points(x, o, d);
+
+        let from_film = Vec3::new(x, 0.0, self.rear_z() + 1.0);
+        let (o, d) = self
+            .trace(from_film, Vec3::new(0.0, 0.0, -1.0), true)
+            .ok_or("paraxial ray blocked by the lens")?;
+        let (object_principal, _) = Self::cardinal_points(x, o, d);
+
+        // 1/s + 1/s' = 1/f with the lens moved delta toward the scene
+        let f = image_focal - image_principal;
+        let a = focus_dist + object_principal;
+        let b = image_principal;
+        let discriminant = (a - b) * (a - b - 4.0 * f);
+        // No image forms of objects closer than about four focal lengths from the film
+        if discriminant < 0.0 {
+            return Err(format!("the lens can't focus {} mm from the film", focus_dist).into());
+        }
+        let delta = 0.5 * ((a + b) - discriminant.sqrt());
+        Ok(self.rear_z().abs() + delta)
+    }
+
+    /// Region of the rear element through which light reaches film points
+    /// between the two distances from the center, along the x axis
+    fn bound_exit_pupil(&self, r0: f64, r1: f64) -> Bounds {
+        let rear = self.elements.last().unwrap().aperture_radius * 1.5;
+        let cell = 2.0 * rear / PUPIL_GRID as f64;
+        let mut bounds = Bounds {
+            min: (f64::MAX, f64::MAX),
+            max: (f64::MIN, f64::MIN),
+        };
+        for f in 0..PUPIL_FILM_SAMPLES {
+            let x = r0 + (r1 - r0) * (f as f64 + 0.5) / PUPIL_FILM_SAMPLES as f64;
+            let film = Vec3::new(x, 0.0, 0.0);
+            for i in 0..PUPIL_GRID {
+                for j in 0..PUPIL_GRID {
+                    let px = -rear + (i as f64 + 0.5) * cell;
+                    let py = -rear + (j as f64 + 0.5) * cell;
+                    let target = Vec3::new(px, py, self.rear_z());
+                    if self.trace(film, target - film, true).is_some() {
+                        bounds.min = (bounds.min.0.min(px), bounds.min.1.min(py));
+                        bounds.max = (bounds.max.0.max(px), bounds.max.1.max(py));
+                    }
+                }
+            }
+        }
+        // the grid may miss thin slivers at the edges
+        Bounds {
+            min: (bounds.min.0 - cell, bounds.min.1 - cell),
+            max: (bounds.max.0 + cell, bounds.max.1 + cell),
+        }
+    }
+
+    /// Point (s, t) of the film, the lens flips the image so does the film
+    fn film_point(&self, s: f64, t: f64) -> Vec3 {
+        Vec3::new(
+            (0.5 - s) * self.film_width,
+            (0.5 - t) * self.film_height,
+            0.0,
+        )
+    }
+
+    /// Area of the rear element region actually letting light through,
+    /// estimated on a grid from the film center
+    fn pupil_area(&self, bounds: Bounds) -> f64 {
+        let film = Vec3::default();
+        let steps = PUPIL_GRID * 2;
+        let mut passed = 0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let px =
+                    bounds.min.0 + (bounds.max.0 - bounds.min.0) * (i as f64 + 0.5) / steps as f64;
+                let py =
+                    bounds.min.1 + (bounds.max.1 - bounds.min.1) * (j as f64 + 0.5) / steps as f64;
+                let target = Vec3::new(px, py, self.rear_z());
+                if self.trace(film, target - film, true).is_some() {
+                    passed += 1;
+                }
+            }
+        }
+        bounds.area() * passed as f64 / (steps * steps) as f64
+    }
+}
+
+fn intersect_sphere(o: Vec3, d: Vec3, center_z: f64, radius: f64) -> Option<(f64, Vec3)> {
+    let oc = o - Vec3::new(0.0, 0.0, center_z);
+    let b = dot(oc, d);
+    let c = oc.squared_length() - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    // the interface is the half of the sphere facing the film for concave surfaces
+    let closer = (d.z > 0.0) != (radius < 0.0);
+    let t = if closer { -b - root } else { -b + root };
+    let mut n = unit_vector(oc + t * d);
+    if dot(n, d) > 0.0 {
+        n = -n;
+    }
+    Some((t, n))
+}
+
+/// Refraction of the unit direction d through a surface whose normal faces d,
+/// eta is the ratio of the incident index over the transmitted one
+fn refract(d: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = -dot(d, n);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+    if sin2_t > 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(eta * d + (eta * cos_i - cos_t) * n)
+}
+
+impl Camera for RealisticCamera {
+    /// Retries until a ray makes it through the lens, this loses vignetting:
+    /// prefer sample_ray.
+    /// Film points the lens blocks, after MAX_RAY_ATTEMPTS tries, get the ray
+    /// a pinhole at the center of the rear element would let through.
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        for _ in 0..MAX_RAY_ATTEMPTS {
+            if let Some((ray, _)) = self.sample_ray(s, t, sampler) {
+                return ray;
+            }
+        }
+        let d = Vec3::new(0.0, 0.0, self.rear_z()) - self.film_point(s, t);
+        Ray::new(
+            self.origin,
+            d.x * self.u + d.y * self.v + d.z * self.w,
+            shutter_time(self.time0, self.time1, sampler),
+        )
+    }
+
+    fn sample_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Option<(Ray, f64)> {
+        let film = self.film_point(s, t);
+        let r = (film.x * film.x + film.y * film.y).sqrt();
+        let half_diagonal =
+            0.5 * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+        let ring = ((r / half_diagonal * PUPIL_RINGS as f64) as usize).min(PUPIL_RINGS - 1);
+        let bounds = self.exit_pupils.rings[ring];
+        if bounds.area() == 0.0 {
+            return None;
+        }
+
+        let (x, y) = sampler.get_2d();
+        let px = bounds.min.0 + (bounds.max.0 - bounds.min.0) * x;
+        let py = bounds.min.1 + (bounds.max.1 - bounds.min.1) * y;
+        // the pupil was bounded along x, rotate it toward the film point
+        let (sin, cos) = if r > 0.0 {
+            (film.y / r, film.x / r)
+        } else {
+            (0.0, 1.0)
+        };
+        let target = Vec3::new(cos * px - sin * py, sin * px + cos * py, self.rear_z());
+
+        let direction = unit_vector(target - film);
+        let (o, d) = self.trace(film, direction, true)?;
+
+        let cos_theta = direction.z.abs();
+        let weight = cos_theta.powi(4) * bounds.area() / self.exit_pupils.center_area;
+
+        let origin = self.origin + (o.x * self.u + o.y * self.v + o.z * self.w) / self.unit;
+        let direction = d.x * self.u + d.y * self.v + d.z * self.w;
+        Some((
+            Ray::new(
+                origin,
+                direction,
+                shutter_time(self.time0, self.time1, sampler),
+            ),
+            weight,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samplers::IndependentSampler;
+
+    /// Double Gauss lens stopped down until the exit pupil grid misses the stop
+    fn pinhole_lens() -> RealisticCamera {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/lenses/dgauss.50mm.dat");
+        let lens = LensSystem::from_file(&path).unwrap();
+        RealisticCamera::new_look_at(
+            Vec3::new(0.0, 0.0, 10.0),
+            Vec3::default(),
+            Vec3::new(0.0, 1.0, 0.0),
+            &lens,
+            43.27,
+            1.5,
+            Some(0.1),
+            0,
+            10.0,
+            1000.0,
+            0.0,
+            1.0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn blocked_lens_gives_up() {
+        let camera = pinhole_lens();
+        let mut sampler = IndependentSampler::new(0);
+        for &(s, t) in &[(0.5, 0.5), (0.1, 0.9), (0.0, 0.0)] {
+            assert!(camera.sample_ray(s, t, &mut sampler).is_none());
+            let ray = camera.get_ray(s, t, &mut sampler);
+            let direction = ray.direction();
+            assert!(direction.length() > 0.0 && direction.length().is_finite());
+            // Toward the scene, in front of the camera
+            assert!(dot(direction, camera.w) < 0.0);
+        }
+    }
+
+    /// Width in millimeters of the spot a point on the axis, distance world units away from
+    /// the film, makes on the film
+    fn spot_size(camera: &RealisticCamera, distance: f64) -> f64 {
+        let point = Vec3::new(0.0, 0.0, -distance * camera.unit);
+        let radius = camera.elements[0].aperture_radius;
+        let (mut min, mut max) = (f64::MAX, f64::MIN);
+        for i in 0..=40 {
+            for j in 0..=40 {
+                let (x, y) = (f64::from(i) / 20.0 - 1.0, f64::from(j) / 20.0 - 1.0);
+                let front = Vec3::new(x * radius, y * radius, camera.front_z());
+                if let Some((o, d)) = camera.trace(point, front - point, false) {
+                    let x = o.x - o.z / d.z * d.x;
+                    min = min.min(x);
+                    max = max.max(x);
+                }
+            }
+        }
+        assert!(max >= min, "no ray made it through the lens");
+        max - min
+    }
+
+    #[test]
+    fn focus_distance_is_sharp() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/lenses/dgauss.50mm.dat");
+        let lens = LensSystem::from_file(&path).unwrap();
+        for &focus_dist in &[1.0, 3.0, 10.0] {
+            // Stopped down to f/12, spherical aberration blurs the full aperture
+            let camera = RealisticCamera::new_look_at(
+                Vec3::new(0.0, 0.0, focus_dist),
+                Vec3::default(),
+                Vec3::new(0.0, 1.0, 0.0),
+                &lens,
+                43.27,
+                1.5,
+                Some(4.0),
+                0,
+                focus_dist,
+                1000.0,
+                0.0,
+                1.0,
+            )
+            .unwrap();
+            let sharp = spot_size(&camera, focus_dist);
+            assert!(sharp < 0.005, "{} mm spot at {}", sharp, focus_dist);
+            assert!(spot_size(&camera, focus_dist * 2.0) > 5.0 * sharp);
+            assert!(spot_size(&camera, focus_dist / 2.0) > 5.0 * sharp);
+        }
+    }
+
+    #[test]
+    fn cameras_share_exit_pupils() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/lenses/dgauss.50mm.dat");
+        let lens = LensSystem::from_file(&path).unwrap();
+        let camera = |lookfrom: Vec3, stop: f64| {
+            RealisticCamera::new_look_at(
+                lookfrom,
+                Vec3::default(),
+                Vec3::new(0.0, 1.0, 0.0),
+                &lens,
+                43.27,
+                1.5,
+                Some(stop),
+                0,
+                lookfrom.length(),
+                1000.0,
+                0.0,
+                1.0,
+            )
+            .unwrap()
+        };
+        let first = camera(Vec3::new(0.0, 0.0, 5.0), 8.0);
+        // Moved a hundredth of a millimeter, the focus doesn't change by a micrometer
+        let moved = camera(Vec3::new(3.0, 0.0, 4.000_01), 8.0);
+        let stopped_down = camera(Vec3::new(0.0, 0.0, 5.0), 4.0);
+        assert!(Arc::ptr_eq(&first.exit_pupils, &moved.exit_pupils));
+        assert!(!Arc::ptr_eq(&first.exit_pupils, &stopped_down.exit_pupils));
+    }
+
+    #[test]
+    fn unfocusable_lens_fails() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/lenses/dgauss.50mm.dat");
+        let lens = LensSystem::from_file(&path).unwrap();
+        let camera = |stop: f64, focus_dist: f64| {
+            RealisticCamera::new_look_at(
+                Vec3::new(0.0, 0.0, focus_dist),
+                Vec3::default(),
+                Vec3::new(0.0, 1.0, 0.0),
+                &lens,
+                43.27,
+                1.5,
+                Some(stop),
+                0,
+                focus_dist,
+                1000.0,
+                0.0,
+                1.0,
+            )
+        };
+        assert!(camera(8.0, 1.0).is_ok());
+        // Inside the focal length of the 50 mm lens
+        assert!(camera(8.0, 0.03).is_err());
+        // A closed stop blocks the paraxial rays
+        assert!(camera(0.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+use super::Camera;
+use crate::samplers::Sampler;
+use crate::structs::{cross, unit_vector, Ray, Vec3};
+
+use std::sync::Arc;
+
+/// Pair of cameras rendered over/under in the same image:
+/// the left eye on the top half, the right eye on the bottom half.
+/// Each eye gets half the image height, its aspect ratio must account for it.
+pub struct StereoCamera {
+    pub left: Arc<dyn Camera>,
+    pub right: Arc<dyn Camera>,
+}
+
+impl StereoCamera {
+    pub fn new(left: Arc<dyn Camera>, right: Arc<dyn Camera>) -> StereoCamera {
+        StereoCamera { left, right }
+    }
+}
+
+/// Positions and targets of the left and right eyes of a parallel stereo rig
+/// separated by the interpupillary distance
+pub fn stereo_eyes(
+    lookfrom: Vec3,
+    lookat: Vec3,
+    up: Vec3,
+    interpupillary_distance: f64,
+) -> [(Vec3, Vec3); 2] {
+    let w = unit_vector(lookfrom - lookat);
+    let offset = interpupillary_distance / 2.0 * unit_vector(cross(up, w));
+    [
+        (lookfrom - offset, lookat - offset),
+        (lookfrom + offset, lookat + offset),
+    ]
+}
+
+impl Camera for StereoCamera {
+    fn get_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
+        // v goes from the bottom (0) to the top (1) of the image
+        if v >= 0.5 {
+            self.left.get_ray(u, (v - 0.5) * 2.0, sampler)
+        } else {
+            self.right.get_ray(u, v * 2.0, sampler)
+        }
+    }
+
+    fn sample_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<(Ray, f64)> {
+        if v >= 0.5 {
+            self.left.sample_ray(u, (v - 0.5) * 2.0, sampler)
+        } else {
+            self.right.sample_ray(u, v * 2.0, sampler)
+        }
+    }
+}
@@ -1,9 +1,8 @@
-use super::Camera;
+use super::{shutter_time, Camera};
 
+use crate::samplers::Sampler;
 use crate::structs::{cross, random_in_unit_disk, unit_vector, Ray, Vec3};
 
-use rand::Rng;
-
 pub struct ThinLensCamera {
     pub origin: Vec3,
     lower_left_corner: Vec3,
@@ -11,7 +10,6 @@ pub struct ThinLensCamera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
     lens_radius: f64,
     // shutter open/close times
     time0: f64,
@@ -19,6 +17,7 @@ pub struct ThinLensCamera {
 }
 
 impl ThinLensCamera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new_look_at(
         lookfrom: Vec3,
         lookat: Vec3,
@@ -48,7 +47,6 @@ impl ThinLensCamera {
             vertical: 2.0 * half_height * focus_dist * v,
             u,
             v,
-            w,
             lens_radius: aperture / 2.0,
             time0: t0,
             time1: t1,
@@ -57,13 +55,13 @@ impl ThinLensCamera {
 }
 
 impl Camera for ThinLensCamera {
-    fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * random_in_unit_disk(sampler);
         let offset = self.u * rd.x + self.v * rd.y;
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-            rand::thread_rng().gen_range(self.time0, self.time1), // TODO pre-gen for better performances
+            shutter_time(self.time0, self.time1, sampler),
         )
     }
 }
@@ -38,7 +38,7 @@ impl Hitable for XYRect {
         Some(rec)
     }
 
-    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
         // The bounding-box must have non-zero width in each dimension,
         // so pad the Z dimension a small amount
         let padding = 0.001;
@@ -84,7 +84,7 @@ impl Hitable for XZRect {
         Some(rec)
     }
 
-    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
         // The bounding-box must have non-zero width in each dimension,
         // so pad the Z dimension a small amount
         let padding = 0.001;
@@ -130,7 +130,7 @@ impl Hitable for YZRect {
         Some(rec)
     }
 
-    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
         // The bounding-box must have non-zero width in each dimension,
         // so pad the Z dimension a small amount
         let padding = 0.001;
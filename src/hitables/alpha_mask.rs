@@ -0,0 +1,125 @@
+use super::{Aabb, HitRecord, Hitable};
+use crate::structs::{Ray, Texture, Vec3};
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Offset applied after a discarded hit so the next query
+/// does not return the same intersection again
+const SKIP_EPSILON: f64 = 1e-7;
+/// Same offset relative to the distance of the hit, far hits need more than SKIP_EPSILON
+/// to move the next query past them
+const SKIP_RELATIVE_EPSILON: f64 = 1e-9;
+
+/// How the opacity read from the mask decides if a hit is kept
+#[derive(Debug, Copy, Clone)]
+pub enum AlphaMode {
+    /// Hits where the opacity is below the threshold are discarded
+    Threshold(f64),
+    /// Hits are kept with a probability equal to the opacity
+    Stochastic,
+}
+
+/// Cut out parts of any hitable with an opacity texture.
+/// Discarded hits are skipped and the intersection continues behind them,
+/// so every ray (camera, scattered or shadow) sees the same cutout.
+/// Inside a BVH a leaf whose hits are all cut out reports no hit,
+/// and `BVHNode::hit` goes on with the other children.
+pub struct AlphaMask {
+    pub object: Arc<dyn Hitable>,
+    pub opacity: Arc<dyn Texture>,
+    pub mode: AlphaMode,
+}
+
+impl AlphaMask {
+    pub fn new(object: Arc<dyn Hitable>, opacity: Arc<dyn Texture>, mode: AlphaMode) -> AlphaMask {
+        AlphaMask {
+            object,
+            opacity,
+            mode,
+        }
+    }
+
+    /// Return the opacity between 0 and 1 at the hit point, the mean of the texture channels
+    pub fn opacity(&self, rec: &HitRecord) -> f64 {
+        let value = self.opacity.value(rec.u, rec.v, &rec.p);
+        ((value.x + value.y + value.z) / 3.0).clamp(0.0, 1.0)
+    }
+
+    fn is_opaque(&self, ray: &Ray, rec: &HitRecord) -> bool {
+        let alpha = self.opacity(rec);
+        match self.mode {
+            AlphaMode::Threshold(threshold) => alpha >= threshold,
+            AlphaMode::Stochastic => hash_to_unit(ray, rec.t) < alpha,
+        }
+    }
+}
+
+/// Hash the ray and the hit distance to a number in [0,1).
+/// Using a hash rather than a random generator keeps the decision
+/// identical if the same ray is traced twice.
+fn hash_to_unit(ray: &Ray, t: f64) -> f64 {
+    let mut hasher = DefaultHasher::new();
+    let hash_vec = |v: Vec3, hasher: &mut DefaultHasher| {
+        v.x.to_bits().hash(hasher);
+        v.y.to_bits().hash(hasher);
+        v.z.to_bits().hash(hasher);
+    };
+    hash_vec(ray.origin(), &mut hasher);
+    hash_vec(ray.direction(), &mut hasher);
+    t.to_bits().hash(&mut hasher);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl Hitable for AlphaMask {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut t_min = t_min;
+        loop {
+            let rec = self.object.hit(ray, t_min, t_max)?;
+            if self.is_opaque(ray, &rec) {
+                return Some(rec);
+            }
+            // Transparent here, look for the next intersection behind it
+            t_min = rec.t + SKIP_EPSILON.max(rec.t.abs() * SKIP_RELATIVE_EPSILON);
+        }
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.object.bounding_box(t0, t1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hitables::XYRect;
+    use crate::materials::Lambertian;
+    use crate::structs::SolidColor;
+
+    /// Quad of the xy plane at z = k masked by a constant opacity
+    fn masked_quad(k: f64, opacity: f64) -> AlphaMask {
+        let quad = XYRect {
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+            }),
+            x0: -1.0,
+            x1: 1.0,
+            y0: -1.0,
+            y1: 1.0,
+            k,
+        };
+        let opacity = Arc::new(SolidColor::new(Vec3::new(opacity, opacity, opacity)));
+        AlphaMask::new(Arc::new(quad), opacity, AlphaMode::Threshold(0.5))
+    }
+
+    #[test]
+    fn far_cutout_is_skipped() {
+        // rec.t + SKIP_EPSILON rounds back to rec.t this far away
+        let k = 1e12;
+        let ray = Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(masked_quad(k, 0.0).hit(&ray, 0.001, f64::MAX).is_none());
+        let rec = masked_quad(k, 1.0).hit(&ray, 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.t, k);
+    }
+}
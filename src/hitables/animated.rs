@@ -0,0 +1,220 @@
+use super::aabb::*;
+use super::hitable::*;
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Times per shutter interval at which the bounding box is evaluated,
+/// on top of the keyframes
+const BOUNDING_STEPS: usize = 16;
+/// Objects scaled down below this are too small to hit,
+/// rays divided by their scale would overflow
+const MIN_SCALE: f64 = 1e-12;
+
+/// Object moved by keyframed transforms, evaluated at the time of each ray.
+/// The object is scaled, then rotated around the Y axis, then translated.
+pub struct Animated {
+    pub object: Arc<dyn Hitable>,
+    pub translation: Track<Vec3>,
+    /// Rotation around the Y axis, in degrees
+    pub rotation: Track<f64>,
+    /// Uniform scale factor, the object vanishes at 0
+    pub scale: Track<f64>,
+}
+
+impl Animated {
+    /// Object standing still until tracks are set
+    pub fn new(object: Arc<dyn Hitable>) -> Animated {
+        Animated {
+            object,
+            translation: Track::constant(Vec3::default()),
+            rotation: Track::constant(0.0),
+            scale: Track::constant(1.0),
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Track<Vec3>) -> Animated {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Track<f64>) -> Animated {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Track<f64>) -> Animated {
+        self.scale = scale;
+        self
+    }
+
+    /// Translation, rotation sine and cosine, and scale at a given time
+    fn transform(&self, time: f64) -> (Vec3, f64, f64, f64) {
+        let (sin, cos) = self.rotation.at(time).to_radians().sin_cos();
+        (self.translation.at(time), sin, cos, self.scale.at(time))
+    }
+}
+
+fn rotate_y(v: Vec3, sin: f64, cos: f64) -> Vec3 {
+    Vec3::new(cos * v.x + sin * v.z, v.y, -sin * v.x + cos * v.z)
+}
+
+impl Hitable for Animated {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (translation, sin, cos, scale) = self.transform(ray.time());
+        if scale.abs() < MIN_SCALE {
+            return None;
+        }
+
+        // Scaling the direction too keeps the same t along both rays
+        let origin = rotate_y(ray.origin() - translation, -sin, cos) / scale;
+        let direction = rotate_y(ray.direction(), -sin, cos) / scale;
+        let mut local = Ray::new(origin, direction, ray.time());
+        if let Some(wavelength) = ray.wavelength() {
+            local = local.with_wavelength(wavelength);
+        }
+
+        let mut rec = self.object.hit(&local, t_min, t_max)?;
+        rec.p = rotate_y(rec.p, sin, cos) * scale + translation;
+        rec.normal = rotate_y(rec.normal, sin, cos);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let local = self.object.bounding_box(t0, t1)?;
+
+        let mut times: Vec<f64> = (0..=BOUNDING_STEPS)
+            .map(|i| t0 + (t1 - t0) * i as f64 / BOUNDING_STEPS as f64)
+            .chain(
+                self.translation
+                    .key_times()
+                    .chain(self.rotation.key_times())
+                    .chain(self.scale.key_times())
+                    .filter(|t| (t0..=t1).contains(t)),
+            )
+            .collect();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut output: Option<Aabb> = None;
+        let mut max_scale: f64 = 0.0;
+        for &time in &times {
+            let (translation, sin, cos, scale) = self.transform(time);
+            max_scale = max_scale.max(scale.abs());
+            for corner in 0..8 {
+                let p = Vec3::new(
+                    if corner & 1 == 0 {
+                        local.min.x
+                    } else {
+                        local.max.x
+                    },
+                    if corner & 2 == 0 {
+                        local.min.y
+                    } else {
+                        local.max.y
+                    },
+                    if corner & 4 == 0 {
+                        local.min.z
+                    } else {
+                        local.max.z
+                    },
+                );
+                let p = rotate_y(p, sin, cos) * scale + translation;
+                let point = Aabb { min: p, max: p };
+                output = Some(match output {
+                    Some(b) => surrounding_box(&b, &point),
+                    None => point,
+                });
+            }
+        }
+        // Between two times the corners travel on arcs around the axis,
+        // which bulge out of the hull of their ends by the arc sag
+        let step = times
+            .windows(2)
+            .map(|w| {
+                (self.rotation.at(w[1]) - self.rotation.at(w[0]))
+                    .abs()
+                    .to_radians()
+            })
+            .fold(0.0, f64::max);
+        // Farthest a corner of the local box gets from the axis
+        let x = local.min.x.abs().max(local.max.x.abs());
+        let z = local.min.z.abs().max(local.max.z.abs());
+        let radius = x.hypot(z) * max_scale;
+        // Past half a turn, any point of the circle is within its diameter
+        let sag = if step < PI {
+            radius * (1.0 - (step / 2.0).cos())
+        } else {
+            2.0 * radius
+        };
+        output.map(|b| Aabb {
+            min: b.min - Vec3::new(sag, 0.0, sag),
+            max: b.max + Vec3::new(sag, 0.0, sag),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hitables::Sphere;
+    use crate::materials::Lambertian;
+    use crate::structs::{Interpolation, SolidColor};
+
+    #[test]
+    fn zero_scale_vanishes() {
+        let sphere = Arc::new(Sphere {
+            center: Vec3::default(),
+            radius: 1.0,
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+            }),
+        });
+        let animated = Animated::new(sphere)
+            .with_scale(Track::new(Interpolation::Linear, 0.0, 0.0).key(1.0, 1.0));
+        let ray = |time| Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), time);
+        assert!(animated.hit(&ray(0.0), 0.001, f64::MAX).is_none());
+        let rec = animated.hit(&ray(1.0), 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+
+        let bounds = animated.bounding_box(0.0, 1.0).unwrap();
+        for v in &[bounds.min, bounds.max] {
+            assert!(v.x.is_finite() && v.y.is_finite() && v.z.is_finite());
+        }
+    }
+
+    #[test]
+    fn spinning_stays_in_bounds() {
+        // Several turns in one shutter, with steps that skip the extremes
+        let sphere = Arc::new(Sphere {
+            center: Vec3::new(10.0, 0.0, 0.0),
+            radius: 0.1,
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(SolidColor::new(Vec3::new(0.1, 0.1, 0.1))),
+            }),
+        });
+        let animated = Animated::new(sphere)
+            .with_rotation(Track::new(Interpolation::Linear, 0.0, 0.0).key(1.0, 1000.0));
+        let bounds = animated.bounding_box(0.0, 1.0).unwrap();
+        for i in 0..=10000 {
+            let (_, sin, cos, _) = animated.transform(f64::from(i) / 10000.0);
+            let center = rotate_y(Vec3::new(10.0, 0.0, 0.0), sin, cos);
+            for p in &[
+                center - Vec3::new(0.1, 0.1, 0.1),
+                center + Vec3::new(0.1, 0.1, 0.1),
+            ] {
+                assert!(
+                    bounds.min.x <= p.x && p.x <= bounds.max.x,
+                    "{} at {}",
+                    p.x,
+                    i
+                );
+                assert!(
+                    bounds.min.z <= p.z && p.z <= bounds.max.z,
+                    "{} at {}",
+                    p.z,
+                    i
+                );
+            }
+        }
+    }
+}
@@ -1,28 +1,35 @@
 use super::{surrounding_box, Aabb, HitRecord, Hitable, HitableList, Ray};
+use crate::samplers::Pcg32;
+use crate::structs::{count, count_many, Counter};
 
 use std::cmp::Ordering;
 use std::sync::Arc;
 
-use rand::prelude::*;
+use rand::Rng;
+
+/// Trees are built the same from one run to the other
+const BVH_SEED: u64 = 0xb1d;
 
 #[derive(Clone)]
 pub struct BVHNode {
     left: Arc<dyn Hitable>,
     right: Arc<dyn Hitable>,
     bb: Aabb,
+    /// The children are objects of the list rather than other nodes
+    leaf: bool,
 }
 
 impl BVHNode {
     /// Construct a BVH from a list of hitables objects
     pub fn new(list: &HitableList, time0: f64, time1: f64) -> BVHNode {
-        BVHNode::from(&list.list[..], time0, time1)
+        BVHNode::from(&list.list[..], time0, time1, &mut Pcg32::new(BVH_SEED))
     }
 
     /// Construct a BVH based on a slice of hitables
     /// Use a randomly choosen axis to cut
     /// For other construction methods, see SAH ...
-    fn from(objects: &[Arc<dyn Hitable>], time0: f64, time1: f64) -> BVHNode {
-        let axis = thread_rng().gen_range(0, 2); // TODO pre-gen for better performances
+    fn from(objects: &[Arc<dyn Hitable>], time0: f64, time1: f64, rng: &mut Pcg32) -> BVHNode {
+        let axis = rng.gen_range(0, 2);
         let comparator = match axis {
             0 => box_x_compare,
             1 => box_y_compare,
@@ -42,17 +49,13 @@ impl BVHNode {
                 }
             }
             _ => {
-                let mut sorted = vec![];
-                sorted.reserve(object_span);
-                for i in objects {
-                    sorted.push(i.clone())
-                }
+                let mut sorted = objects.to_vec();
                 sorted.sort_by(comparator);
 
                 let mid = object_span / 2;
                 (
-                    Arc::new(BVHNode::from(&sorted[..mid], time0, time1)) as Arc<dyn Hitable>,
-                    Arc::new(BVHNode::from(&sorted[mid..], time0, time1)) as Arc<dyn Hitable>,
+                    Arc::new(BVHNode::from(&sorted[..mid], time0, time1, rng)) as Arc<dyn Hitable>,
+                    Arc::new(BVHNode::from(&sorted[mid..], time0, time1, rng)) as Arc<dyn Hitable>,
                 )
             }
         };
@@ -68,6 +71,7 @@ impl BVHNode {
             left,
             right,
             bb: surrounding_box(&box_left.unwrap(), &box_right.unwrap()),
+            leaf: object_span <= 2,
         }
     }
 }
@@ -99,9 +103,14 @@ fn box_z_compare(a: &Arc<dyn Hitable>, b: &Arc<dyn Hitable>) -> Ordering {
 
 impl Hitable for BVHNode {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        count(Counter::BvhNodes);
         if !self.bb.hit(ray, t_min, t_max) {
             return None;
         }
+        if self.leaf {
+            // Both children are always tested
+            count_many(Counter::PrimitiveTests, 2);
+        }
 
         // This combinator return right node hit when left node isn't hit
         // or left node hit if left node is hit but right node isn't
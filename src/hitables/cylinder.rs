@@ -0,0 +1,117 @@
+use super::hitable::*;
+use super::Aabb;
+use crate::materials::Material;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Closed cylinder aligned on the Y axis, capped at y0 and y1
+pub struct Cylinder {
+    pub center: Vec3,
+    pub radius: f64,
+    pub y0: f64,
+    pub y1: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    fn hit_side(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let ox = ray.origin().x - self.center.x;
+        let oz = ray.origin().z - self.center.z;
+        let dx = ray.direction().x;
+        let dz = ray.direction().z;
+
+        let a = dx * dx + dz * dz;
+        let half_b = ox * dx + oz * dz;
+        let c = ox * ox + oz * oz - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if a == 0.0 || discriminant <= 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        for temp in [(-half_b - root) / a, (-half_b + root) / a].iter() {
+            let temp = *temp;
+            if temp <= t_min || temp >= t_max {
+                continue;
+            }
+            let p = ray.point_at(temp);
+            if p.y < self.y0 || p.y > self.y1 {
+                continue;
+            }
+            let outward_normal = Vec3::new(
+                (p.x - self.center.x) / self.radius,
+                0.0,
+                (p.z - self.center.z) / self.radius,
+            );
+            let u = ((-outward_normal.z).atan2(outward_normal.x) + PI) / (2.0 * PI);
+            let v = (p.y - self.y0) / (self.y1 - self.y0);
+            return Some(HitRecord::new(
+                temp,
+                u,
+                v,
+                p,
+                ray,
+                outward_normal,
+                self.material.clone(),
+            ));
+        }
+        None
+    }
+
+    fn hit_cap(
+        &self,
+        ray: &Ray,
+        y: f64,
+        normal_y: f64,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord> {
+        let t = (y - ray.origin().y) / ray.direction().y;
+        if t.is_nan() || t <= t_min || t >= t_max {
+            return None;
+        }
+        let p = ray.point_at(t);
+        let x = (p.x - self.center.x) / self.radius;
+        let z = (p.z - self.center.z) / self.radius;
+        if x * x + z * z > 1.0 {
+            return None;
+        }
+        Some(HitRecord::new(
+            t,
+            (x + 1.0) / 2.0,
+            (z + 1.0) / 2.0,
+            p,
+            ray,
+            Vec3::new(0.0, normal_y, 0.0),
+            self.material.clone(),
+        ))
+    }
+}
+
+impl Hitable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Each test is bounded by the closest hit found so far,
+        // so the last found hit is the closest one
+        let side = self.hit_side(ray, t_min, t_max);
+        let t_max = side.as_ref().map_or(t_max, |rec| rec.t);
+        let bottom = self.hit_cap(ray, self.y0, -1.0, t_min, t_max);
+        let t_max = bottom.as_ref().map_or(t_max, |rec| rec.t);
+        let top = self.hit_cap(ray, self.y1, 1.0, t_min, t_max);
+        top.or(bottom).or(side)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(Aabb {
+            min: Vec3::new(
+                self.center.x - self.radius,
+                self.y0,
+                self.center.z - self.radius,
+            ),
+            max: Vec3::new(
+                self.center.x + self.radius,
+                self.y1,
+                self.center.z + self.radius,
+            ),
+        })
+    }
+}
@@ -11,6 +11,9 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub front_face: bool,
     pub material: Arc<dyn Material>,
+    /// Index of the object of the world that was hit, starting at 1.
+    /// Set by `Identified`, 0 for objects without an index.
+    pub object_id: u32,
 }
 
 impl HitRecord {
@@ -32,6 +35,7 @@ impl HitRecord {
             front_face,
             normal,
             material,
+            object_id: 0,
         }
     }
 }
@@ -40,7 +44,7 @@ impl HitRecord {
 fn compute_face_normal(ray: &Ray, outward_normal: &Vec3) -> (bool, Vec3) {
     let front_face = dot(ray.direction(), *outward_normal) < 0.0;
     let normal = if front_face {
-        outward_normal.clone()
+        *outward_normal
     } else {
         -outward_normal
     };
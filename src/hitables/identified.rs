@@ -0,0 +1,29 @@
+use super::{Aabb, HitRecord, Hitable};
+use crate::structs::Ray;
+
+use std::sync::Arc;
+
+/// Tag the hits of an object with an ID, written to the object ID AOV
+pub struct Identified {
+    pub object: Arc<dyn Hitable>,
+    pub id: u32,
+}
+
+impl Identified {
+    pub fn new(object: Arc<dyn Hitable>, id: u32) -> Identified {
+        Identified { object, id }
+    }
+}
+
+impl Hitable for Identified {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.object.hit(ray, t_min, t_max).map(|mut rec| {
+            rec.object_id = self.id;
+            rec
+        })
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.object.bounding_box(t0, t1)
+    }
+}
@@ -6,11 +6,20 @@ mod moving_sphere;
 pub use moving_sphere::*;
 mod aa_rect;
 pub use aa_rect::*;
+mod cylinder;
+pub use cylinder::*;
 mod aabb;
 pub use aabb::*;
 mod bvh;
 pub use bvh::*;
+mod alpha_mask;
+pub use alpha_mask::*;
+mod animated;
+pub use animated::*;
+mod identified;
+pub use identified::*;
 
+use crate::lights::Light;
 use crate::materials::Material;
 
 use std::sync::Arc;
@@ -18,6 +27,10 @@ use std::sync::Arc;
 pub struct HitableList {
     list: Vec<Arc<dyn Hitable>>,
     pub background: Arc<dyn Material>,
+    /// Lights without geometry, reached by shadow rays only
+    pub lights: Vec<Arc<dyn Light>>,
+    /// Background also sampled as a light, if any
+    pub environment: Option<Arc<dyn Light>>,
 }
 
 impl HitableList {
@@ -25,24 +38,105 @@ impl HitableList {
         HitableList {
             list: Vec::new(),
             background,
+            lights: Vec::new(),
+            environment: None,
         }
     }
 
+    /// Use a background that is also sampled as a light, an environment map or the sky
+    pub fn set_environment<E: Light + Material + 'static>(&mut self, environment: Arc<E>) {
+        self.background = environment.clone();
+        self.environment = Some(environment.clone());
+        self.lights.push(environment);
+    }
+
     pub fn push(&mut self, elem: Arc<dyn Hitable>) {
         self.list.push(elem);
     }
 
+    pub fn push_light(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    /// Description of the objects, lights and background of the list over the shutter interval,
+    /// changes when they move or change shape or material.
+    /// Each object is probed by a single ray aimed at the center of its bounding box,
+    /// the lights and the background are sampled from the center of the scene.
+    /// Changes the probes miss, like the material of a face the ray doesn't meet,
+    /// leave the description unchanged.
+    pub fn describe(&self, t0: f64, t1: f64) -> String {
+        let probe_direction = unit_vector(Vec3::new(1.0, 2.0, 3.0));
+        let mut description = format!("{} objects, {} lights", self.len(), self.lights.len());
+        for object in &self.list {
+            let bounds = match object.bounding_box(t0, t1) {
+                Some(bounds) => bounds,
+                None => {
+                    description += "; unbounded";
+                    continue;
+                }
+            };
+            let center = 0.5 * (bounds.min + bounds.max);
+            let distance = (bounds.max - bounds.min).length() + 1.0;
+            let probe = Ray::new(center + distance * probe_direction, -probe_direction, t0);
+            let surface = object.hit(&probe, 0.001, f64::MAX).map(|rec| {
+                (
+                    rec.t,
+                    rec.normal,
+                    rec.material.eval(&probe, &rec, &probe_direction),
+                    rec.material.emit(rec.u, rec.v, &rec.p),
+                )
+            });
+            description += &format!("; {:?}", (bounds.min, bounds.max, surface));
+        }
+
+        let center = self
+            .bounding_box(t0, t1)
+            .map_or(Vec3::default(), |bounds| 0.5 * (bounds.min + bounds.max));
+        for light in &self.lights {
+            let sample = light
+                .sample(&center, (0.5, 0.5))
+                .map(|sample| (sample.direction, sample.distance, sample.radiance));
+            description += &format!("; light {:?}", sample);
+        }
+        for direction in &[probe_direction, -probe_direction] {
+            let emitted = self.background.emit(0.0, 0.0, direction);
+            description += &format!("; background {:?}", emitted);
+        }
+        description
+    }
+
+    /// Copy of the list whose objects report their position in it as object ID
+    pub fn with_object_ids(&self) -> HitableList {
+        HitableList {
+            list: self
+                .list
+                .iter()
+                .enumerate()
+                .map(|(index, object)| {
+                    Arc::new(Identified::new(object.clone(), index as u32 + 1)) as Arc<dyn Hitable>
+                })
+                .collect(),
+            background: self.background.clone(),
+            lights: self.lights.clone(),
+            environment: self.environment.clone(),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.list.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
 }
 
 impl Hitable for HitableList {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let mut closest_so_far = t_max;
         let mut rec: Option<HitRecord> = None;
-        for i in 0..self.list.len() {
-            let temp_rec = self.list[i].hit(&ray, t_min, closest_so_far);
+        for hitable in &self.list {
+            let temp_rec = hitable.hit(ray, t_min, closest_so_far);
             if let Some(n) = temp_rec {
                 closest_so_far = n.t;
                 rec = Some(n);
@@ -75,3 +169,45 @@ impl Hitable for HitableList {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lights::PointLight;
+    use crate::materials::Lambertian;
+
+    /// A sphere of the given color and position, lit by a point light of the given intensity
+    fn world(center: Vec3, color: Vec3, intensity: f64) -> HitableList {
+        let mut world = HitableList::new(Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::default())),
+        }));
+        world.push(Arc::new(Sphere {
+            center,
+            radius: 1.0,
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(SolidColor::new(color)),
+            }),
+        }));
+        world.push_light(Arc::new(PointLight {
+            position: Vec3::new(0.0, 5.0, 0.0),
+            intensity: Vec3::new(intensity, intensity, intensity),
+        }));
+        world
+    }
+
+    #[test]
+    fn description_follows_the_scene_content() {
+        let (center, color) = (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.5, 0.5, 0.5));
+        let description = world(center, color, 10.0).describe(0.0, 1.0);
+        assert_eq!(description, world(center, color, 10.0).describe(0.0, 1.0));
+        assert_ne!(
+            description,
+            world(Vec3::new(0.0, 1.5, 0.0), color, 10.0).describe(0.0, 1.0)
+        );
+        assert_ne!(
+            description,
+            world(center, Vec3::new(0.5, 0.2, 0.2), 10.0).describe(0.0, 1.0)
+        );
+        assert_ne!(description, world(center, color, 20.0).describe(0.0, 1.0));
+    }
+}
@@ -0,0 +1,49 @@
+use super::{surface_aovs, Integrator};
+use crate::hitables::Hitable;
+use crate::materials::random_unit_vector;
+use crate::render::Scene;
+use crate::samplers::{scatter_dimension, Sampler};
+use crate::structs::{count, AovLayout, Counter, Ray, Vec3};
+
+/// White where a ray leaving the first hit in a cosine weighted direction
+/// travels distance without hitting anything, black elsewhere.
+/// Converges to the fraction of the surroundings left open, regardless of materials and lights.
+pub struct AmbientOcclusion {
+    /// Occluders farther away don't count, in world units
+    pub distance: f64,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3 {
+        let open = Vec3::new(1.0, 1.0, 1.0);
+        count(Counter::PathRays);
+        let rec = match scene.world.hit(ray, 0.001, f64::MAX) {
+            Some(rec) => rec,
+            None => return open,
+        };
+        if let Some((layout, channels)) = aovs {
+            surface_aovs(layout, channels, ray, &rec);
+        }
+
+        sampler.set_dimension(scatter_dimension(0));
+        let mut direction = rec.normal + random_unit_vector(sampler);
+        if direction.squared_length() < 1e-12 {
+            direction = rec.normal;
+        }
+        let probe = Ray::new(rec.p, direction, ray.time());
+        count(Counter::ShadowRays);
+        match scene
+            .world
+            .hit(&probe, 0.001, self.distance / direction.length())
+        {
+            Some(_) => Vec3::default(),
+            None => open,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::{surface_aovs, Integrator};
+use crate::hitables::Hitable;
+use crate::render::Scene;
+use crate::samplers::Sampler;
+use crate::structs::{count, AovLayout, Counter, Ray, Vec3};
+
+/// Property of the first hit shown by `DebugIntegrator`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DebugView {
+    /// Normal facing the camera, each coordinate mapped from [-1, 1] to [0, 1]
+    Normals,
+    /// Texture coordinates in the red and green channels
+    Uv,
+    /// Distance to the camera, black at the camera and white from the given distance on
+    Depth(f64),
+}
+
+/// Shows the geometry seen by the camera rays, to check the scenes and the cameras.
+/// Rays missing every object are black, or white in the depth view.
+pub struct DebugIntegrator {
+    pub view: DebugView,
+}
+
+impl Integrator for DebugIntegrator {
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        _sampler: &mut dyn Sampler,
+        aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3 {
+        count(Counter::PathRays);
+        let rec = match scene.world.hit(ray, 0.001, f64::MAX) {
+            Some(rec) => rec,
+            None => {
+                return match self.view {
+                    DebugView::Depth(_) => Vec3::new(1.0, 1.0, 1.0),
+                    _ => Vec3::default(),
+                };
+            }
+        };
+        if let Some((layout, channels)) = aovs {
+            surface_aovs(layout, channels, ray, &rec);
+        }
+
+        match self.view {
+            DebugView::Normals => (rec.normal + Vec3::new(1.0, 1.0, 1.0)) * 0.5,
+            DebugView::Uv => Vec3::new(rec.u, rec.v, 0.0),
+            DebugView::Depth(far) => {
+                let depth = (rec.t * ray.direction().length() / far).min(1.0);
+                Vec3::new(depth, depth, depth)
+            }
+        }
+    }
+}
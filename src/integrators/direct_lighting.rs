@@ -0,0 +1,62 @@
+use super::{surface_aovs, Integrator};
+use crate::hitables::Hitable;
+use crate::render::{pdf_of_scattered, Scene};
+use crate::samplers::{scatter_dimension, Sampler};
+use crate::structs::{count, Aov, AovLayout, Counter, Ray, Vec3};
+
+/// Light reaching the first hit straight from its source, without bounces in between.
+/// The lights are sampled, and a scattered ray picks up the emissive surfaces
+/// and the background it reaches.
+/// Mirrors and glass only show what they reflect or refract directly.
+pub struct DirectLighting;
+
+impl Integrator for DirectLighting {
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        mut aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3 {
+        count(Counter::PathRays);
+        let rec = match scene.world.hit(ray, 0.001, f64::MAX) {
+            Some(rec) => rec,
+            None => {
+                let emitted = scene.background(ray, None);
+                if let Some((layout, channels)) = aovs {
+                    layout.set_color(channels, Aov::Emission, emitted);
+                }
+                return emitted;
+            }
+        };
+
+        sampler.set_dimension(scatter_dimension(0));
+        let res = rec.material.scatter(ray, &rec, sampler);
+        let emitted = rec.material.emit(rec.u, rec.v, &rec.p);
+        if let Some((layout, channels)) = &mut aovs {
+            surface_aovs(layout, channels, ray, &rec);
+            layout.set_color(channels, Aov::Emission, emitted);
+        }
+        let mut direct = Vec3::default();
+        scene.sample_lights(ray, &rec, 0, sampler, |light, bsdf, radiance| {
+            direct += bsdf * radiance;
+            if let Some((layout, channels)) = &mut aovs {
+                layout.add_light(channels, light, bsdf * radiance);
+            }
+        });
+
+        if let Some((attenuation, scattered)) = res {
+            let pdf = pdf_of_scattered(ray, &rec, &scattered);
+            count(Counter::PathRays);
+            let reached = match scene.world.hit(&scattered, 0.001, f64::MAX) {
+                Some(hit) => hit.material.emit(hit.u, hit.v, &hit.p),
+                None => scene.background(&scattered, pdf),
+            };
+            direct += attenuation * reached;
+        }
+        if let Some((layout, channels)) = aovs {
+            layout.set_color(channels, Aov::Direct, direct);
+        }
+        emitted + direct
+    }
+}
@@ -0,0 +1,111 @@
+mod path_tracer;
+pub use path_tracer::*;
+mod simple_path_tracer;
+pub use simple_path_tracer::*;
+mod direct_lighting;
+pub use direct_lighting::*;
+mod ambient_occlusion;
+pub use ambient_occlusion::*;
+mod debug_integrator;
+pub use debug_integrator::*;
+
+use crate::hitables::HitRecord;
+use crate::render::Scene;
+use crate::samplers::Sampler;
+use crate::structs::{Aov, AovLayout, Ray, SampledSpectrum, SampledWavelengths, Vec3};
+
+/// Estimates the light arriving along camera rays.
+/// Implementations loop over the bounces of a path rather than recursing,
+/// deep paths don't grow the stack.
+pub trait Integrator: Send + Sync {
+    /// Light arriving along a camera ray.
+    /// Fills the channels of the selected AOVs at the first hit when given,
+    /// the lighting ones are left black by integrators not telling them apart.
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3;
+
+    /// Light arriving along a camera ray at the wavelengths of a spectral path.
+    /// Uplifts the RGB estimate by default, which ignores dispersion,
+    /// the binary only renders spectrally with the `PathTracer`.
+    fn spectral_li(
+        &self,
+        ray: &Ray,
+        wavelengths: &mut SampledWavelengths,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> SampledSpectrum {
+        SampledSpectrum::from_rgb(&self.li(ray, scene, sampler, aovs), wavelengths)
+    }
+}
+
+/// Integrator picked for a render
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntegratorKind {
+    /// Paths sampling lights at every bounce, see `PathTracer`
+    Path,
+    /// Paths following the scattered rays only, see `SimplePathTracer`
+    SimplePath,
+    /// Light reaching the first hit directly, see `DirectLighting`
+    DirectLighting,
+    /// Occlusion of the first hit within a distance, see `AmbientOcclusion`
+    AmbientOcclusion(f64),
+    /// Geometry of the first hit, see `DebugIntegrator`
+    Debug(DebugView),
+}
+
+/// Distance shown white by the depth view when none is given
+const DEFAULT_FAR: f64 = 100.0;
+
+impl IntegratorKind {
+    /// Names recognized by `from_name`
+    pub const NAMES: &'static str = "path, simple, direct, ao[:distance], normals, uv, depth[:far]";
+
+    /// Kind given on the command line, a name optionally followed by `:` and a distance.
+    /// None when the name is unknown or the distance can't be read.
+    pub fn from_name(spec: &str) -> Option<IntegratorKind> {
+        let (name, distance) = match spec.split_once(':') {
+            Some((name, distance)) => (name, Some(distance.parse::<f64>().ok()?)),
+            None => (spec, None),
+        };
+        let kind = match (name, distance) {
+            ("path", None) => IntegratorKind::Path,
+            ("simple", None) => IntegratorKind::SimplePath,
+            ("direct", None) => IntegratorKind::DirectLighting,
+            ("ao", distance) => IntegratorKind::AmbientOcclusion(distance.unwrap_or(f64::INFINITY)),
+            ("normals", None) => IntegratorKind::Debug(DebugView::Normals),
+            ("uv", None) => IntegratorKind::Debug(DebugView::Uv),
+            ("depth", far) => IntegratorKind::Debug(DebugView::Depth(far.unwrap_or(DEFAULT_FAR))),
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn create(self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Path => Box::new(PathTracer),
+            IntegratorKind::SimplePath => Box::new(SimplePathTracer),
+            IntegratorKind::DirectLighting => Box::new(DirectLighting),
+            IntegratorKind::AmbientOcclusion(distance) => Box::new(AmbientOcclusion { distance }),
+            IntegratorKind::Debug(view) => Box::new(DebugIntegrator { view }),
+        }
+    }
+}
+
+/// Geometric AOVs of the surface a camera ray hits first, the background leaves them at 0
+fn surface_aovs(layout: &AovLayout, channels: &mut [f64], ray: &Ray, rec: &HitRecord) {
+    layout.set(channels, Aov::Depth, &[rec.t * ray.direction().length()]);
+    layout.set(channels, Aov::Position, &[rec.p.x, rec.p.y, rec.p.z]);
+    layout.set(
+        channels,
+        Aov::Normal,
+        &[rec.normal.x, rec.normal.y, rec.normal.z],
+    );
+    layout.set(channels, Aov::Uv, &[rec.u, rec.v]);
+    layout.set_color(channels, Aov::Albedo, rec.material.albedo(rec));
+}
@@ -0,0 +1,183 @@
+use super::{surface_aovs, Integrator};
+use crate::hitables::Hitable;
+use crate::render::{pdf_of_scattered, BounceKind, Bounces, Scene};
+use crate::samplers::{scatter_dimension, Sampler};
+use crate::structs::{
+    count, luminance, Aov, AovLayout, Counter, Ray, SampledSpectrum, SampledWavelengths, Vec3,
+};
+
+/// Path tracer sampling the lights at every bounce, weighted against the scattered rays
+/// with multiple importance sampling.
+/// Paths follow the path settings of the scene: bounce limits, Russian roulette and clamping.
+/// Also tells apart the direct and indirect light, and the light of each source, in the AOVs.
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        mut aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3 {
+        let paths = &scene.paths;
+        let mut ray = *ray;
+        let mut radiance = Vec3::default();
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut bounces = Bounces::default();
+        let mut scattering_pdf = None;
+        // Light found at the first hit, the remainder is indirect
+        let mut first_found = Vec3::default();
+
+        while bounces.total() < paths.max_depth {
+            let bounce = bounces.total();
+            // Only the camera ray fills the AOVs
+            let mut vertex_aovs = if bounce == 0 { aovs.take() } else { None };
+
+            count(Counter::PathRays);
+            let rec = match scene.world.hit(&ray, 0.001, f64::MAX) {
+                Some(rec) => rec,
+                None => {
+                    let emitted = scene.background(&ray, scattering_pdf);
+                    if let Some((layout, channels)) = &mut vertex_aovs {
+                        layout.set_color(channels, Aov::Emission, emitted);
+                    }
+                    let found = throughput * emitted;
+                    let found = found * paths.clamp_factor(&bounces, luminance(&found));
+                    radiance += found;
+                    if bounce == 0 {
+                        first_found = found;
+                    }
+                    aovs = aovs.or(vertex_aovs);
+                    break;
+                }
+            };
+
+            sampler.set_dimension(scatter_dimension(bounce));
+            let res = rec.material.scatter(&ray, &rec, sampler);
+            let emitted = rec.material.emit(rec.u, rec.v, &rec.p);
+            if let Some((layout, channels)) = &mut vertex_aovs {
+                surface_aovs(layout, channels, &ray, &rec);
+                layout.set_color(channels, Aov::Emission, emitted);
+            }
+            let mut direct = Vec3::default();
+            scene.sample_lights(&ray, &rec, bounce, sampler, |light, bsdf, radiance| {
+                direct += bsdf * radiance;
+                if let Some((layout, channels)) = &mut vertex_aovs {
+                    layout.add_light(channels, light, bsdf * radiance);
+                }
+            });
+            if let Some((layout, channels)) = &mut vertex_aovs {
+                layout.set_color(channels, Aov::Direct, direct);
+            }
+            aovs = aovs.or(vertex_aovs);
+
+            let found = throughput * (emitted + direct);
+            let found = found * paths.clamp_factor(&bounces, luminance(&found));
+            radiance += found;
+            if bounce == 0 {
+                first_found = found;
+            }
+
+            let (attenuation, scattered) = match res {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            let pdf = pdf_of_scattered(&ray, &rec, &scattered);
+            bounces = bounces.after(BounceKind::of(&rec, &scattered, pdf));
+            throughput *= attenuation;
+            let max_throughput = throughput.x.max(throughput.y).max(throughput.z);
+            match paths.survival(&bounces, max_throughput, sampler) {
+                Some(probability) => throughput /= probability,
+                None => break,
+            }
+            ray = scattered;
+            scattering_pdf = pdf;
+        }
+
+        if let Some((layout, channels)) = aovs {
+            layout.set_color(channels, Aov::Indirect, radiance - first_found);
+        }
+        radiance
+    }
+
+    /// Same algorithm as `li`, RGB colors being uplifted to spectra on the way.
+    /// Only the geometric AOVs are filled, the lighting ones stay black.
+    fn spectral_li(
+        &self,
+        ray: &Ray,
+        wavelengths: &mut SampledWavelengths,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        mut aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> SampledSpectrum {
+        let paths = &scene.paths;
+        let mut ray = *ray;
+        let mut radiance = SampledSpectrum::default();
+        let mut throughput = SampledSpectrum::splat(1.0);
+        let mut bounces = Bounces::default();
+        let mut scattering_pdf = None;
+
+        // Light found by the path, kept under the clamp of the scene
+        let clamp = |bounces: &Bounces, wavelengths: &SampledWavelengths, found| {
+            if paths.clamp_indirect.is_none() {
+                return found;
+            }
+            let added = luminance(&wavelengths.to_rgb(&found));
+            found * SampledSpectrum::splat(paths.clamp_factor(bounces, added))
+        };
+
+        while bounces.total() < paths.max_depth {
+            let bounce = bounces.total();
+            count(Counter::PathRays);
+            let rec = match scene.world.hit(&ray, 0.001, f64::MAX) {
+                Some(rec) => rec,
+                None => {
+                    let emitted = scene.background(&ray, scattering_pdf);
+                    let found = throughput * SampledSpectrum::from_rgb(&emitted, wavelengths);
+                    radiance = radiance + clamp(&bounces, wavelengths, found);
+                    break;
+                }
+            };
+
+            sampler.set_dimension(scatter_dimension(bounce));
+            let res = rec.material.scatter(&ray, &rec, sampler);
+            let emitted = rec.material.emit(rec.u, rec.v, &rec.p);
+            let mut emitted = SampledSpectrum::from_rgb(&emitted, wavelengths);
+            if let Some((layout, channels)) = aovs.take() {
+                surface_aovs(layout, channels, &ray, &rec);
+            }
+            scene.sample_lights(&ray, &rec, bounce, sampler, |_, bsdf, radiance| {
+                emitted = emitted
+                    + SampledSpectrum::from_rgb(&bsdf, wavelengths)
+                        * SampledSpectrum::from_rgb(&radiance, wavelengths)
+            });
+            radiance = radiance + clamp(&bounces, wavelengths, throughput * emitted);
+
+            let (attenuation, scattered) = match res {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            // A dispersive interaction only follows the hero wavelength
+            let weight = if rec.material.is_dispersive() {
+                wavelengths.terminate_secondary()
+            } else {
+                SampledSpectrum::splat(1.0)
+            };
+            let scattered = scattered.with_wavelength(wavelengths.hero());
+            let pdf = pdf_of_scattered(&ray, &rec, &scattered);
+            bounces = bounces.after(BounceKind::of(&rec, &scattered, pdf));
+            throughput = throughput * SampledSpectrum::from_rgb(&attenuation, wavelengths) * weight;
+            let max_throughput = throughput.values.iter().cloned().fold(0.0, f64::max);
+            match paths.survival(&bounces, max_throughput, sampler) {
+                Some(probability) => {
+                    throughput = throughput * SampledSpectrum::splat(1.0 / probability)
+                }
+                None => break,
+            }
+            ray = scattered;
+            scattering_pdf = pdf;
+        }
+        radiance
+    }
+}
@@ -0,0 +1,59 @@
+use super::{surface_aovs, Integrator};
+use crate::hitables::Hitable;
+use crate::render::Scene;
+use crate::samplers::{scatter_dimension, Sampler};
+use crate::structs::{count, Aov, AovLayout, Counter, Ray, Vec3};
+
+/// Path tracer of the book: paths follow the rays scattered by the materials
+/// until they leave the scene or reach the maximum depth, nothing else.
+/// Lights without geometry are never reached, so they light nothing.
+/// Slow to converge but simple, a reference for the other integrators.
+pub struct SimplePathTracer;
+
+impl Integrator for SimplePathTracer {
+    fn li(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        mut aovs: Option<(&AovLayout, &mut [f64])>,
+    ) -> Vec3 {
+        let mut ray = *ray;
+        let mut radiance = Vec3::default();
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+
+        for bounce in 0..scene.paths.max_depth {
+            count(Counter::PathRays);
+            let rec = match scene.world.hit(&ray, 0.001, f64::MAX) {
+                Some(rec) => rec,
+                None => {
+                    // Nothing samples the background, it takes its full weight
+                    let emitted = scene.background(&ray, None);
+                    if let Some((layout, channels)) = aovs.take() {
+                        layout.set_color(channels, Aov::Emission, emitted);
+                    }
+                    radiance += throughput * emitted;
+                    break;
+                }
+            };
+
+            sampler.set_dimension(scatter_dimension(bounce));
+            let res = rec.material.scatter(&ray, &rec, sampler);
+            let emitted = rec.material.emit(rec.u, rec.v, &rec.p);
+            if let Some((layout, channels)) = aovs.take() {
+                surface_aovs(layout, channels, &ray, &rec);
+                layout.set_color(channels, Aov::Emission, emitted);
+            }
+            radiance += throughput * emitted;
+
+            match res {
+                Some((attenuation, scattered)) => {
+                    throughput *= attenuation;
+                    ray = scattered;
+                }
+                None => break,
+            }
+        }
+        radiance
+    }
+}
@@ -0,0 +1,10 @@
+pub mod cameras;
+pub mod hitables;
+pub mod integrators;
+pub mod lights;
+pub mod materials;
+pub mod render;
+pub mod samplers;
+pub mod scenes;
+pub mod scheduler;
+pub mod structs;
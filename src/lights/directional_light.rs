@@ -0,0 +1,64 @@
+use super::{Light, LightSample};
+use crate::structs::{cross, unit_vector, Vec3};
+
+use std::f64::consts::PI;
+
+/// Light coming from infinitely far away, like the sun.
+/// A non zero angular diameter gives soft shadows.
+pub struct DirectionalLight {
+    /// Unit vector toward the light
+    to_light: Vec3,
+    /// Irradiance received by a surface facing the light
+    pub irradiance: Vec3,
+    cos_half_angle: f64,
+}
+
+impl DirectionalLight {
+    /// direction: where the light travels to.
+    /// angular_diameter: apparent size of the light in degrees (about 0.53 for the sun).
+    pub fn new(direction: Vec3, irradiance: Vec3, angular_diameter: f64) -> DirectionalLight {
+        DirectionalLight {
+            to_light: -unit_vector(direction),
+            irradiance,
+            cos_half_angle: (angular_diameter / 2.0).to_radians().cos(),
+        }
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        -self.to_light
+    }
+}
+
+/// Uniform direction inside the cone around the unit vector axis
+pub(crate) fn sample_cone(axis: Vec3, cos_half_angle: f64, u: (f64, f64)) -> Vec3 {
+    let cos_theta = 1.0 - u.0 * (1.0 - cos_half_angle);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+
+    let w = axis;
+    let a = if w.x.abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let v = unit_vector(cross(w, a));
+    let u = cross(w, v);
+    sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * w
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Vec3, u: (f64, f64)) -> Option<LightSample> {
+        let direction = if self.cos_half_angle < 1.0 {
+            sample_cone(self.to_light, self.cos_half_angle, u)
+        } else {
+            self.to_light
+        };
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+            pdf: None,
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use super::{Light, LightSample};
+use crate::hitables::HitRecord;
+use crate::materials::Material;
+use crate::samplers::Sampler;
+use crate::structs::{luminance, unit_vector, Distribution2D, Ray, Vec3};
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// Light coming from an equirectangular (latitude-longitude) HDR image
+/// surrounding the scene.
+/// It is both the scene background, seen by rays leaving the scene,
+/// and a light importance sampled on its bright pixels.
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+    /// Rotation around the Y axis, as a fraction of a turn
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentLight {
+    /// Load an environment from a Radiance (.hdr) or OpenEXR (.exr) file.
+    /// rotation turns the environment around the Y axis (degrees),
+    /// intensity scales its radiance.
+    pub fn from_file(
+        path: &Path,
+        rotation: f64,
+        intensity: f64,
+    ) -> Result<EnvironmentLight, Box<dyn Error>> {
+        let (width, height, pixels) = match path.extension().and_then(|e| e.to_str()) {
+            Some("exr") => load_exr(path)?,
+            _ => load_hdr(path)?,
+        };
+        Ok(EnvironmentLight::new(
+            width, height, pixels, rotation, intensity,
+        ))
+    }
+
+    /// Create an environment from linear RGB pixels, stored row by row from the top
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<Vec3>,
+        rotation: f64,
+        intensity: f64,
+    ) -> EnvironmentLight {
+        assert_eq!(pixels.len(), width * height);
+
+        // Favour bright pixels, rows near the poles cover a smaller solid angle
+        let mut func = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let sin_theta = (PI * (row as f64 + 0.5) / height as f64).sin();
+            for pixel in &pixels[row * width..(row + 1) * width] {
+                func.push(luminance(pixel) * sin_theta);
+            }
+        }
+
+        EnvironmentLight {
+            width,
+            height,
+            distribution: Distribution2D::new(&func, width, height),
+            pixels,
+            rotation: rotation / 360.0,
+            intensity,
+        }
+    }
+
+    /// Image coordinates in [0,1)² seen in the given direction
+    fn direction_to_uv(&self, direction: &Vec3) -> (f64, f64) {
+        let d = unit_vector(*direction);
+        let phi = (-d.z).atan2(d.x) + PI;
+        let theta = d.y.clamp(-1.0, 1.0).acos();
+        let u = (phi / (2.0 * PI) + self.rotation).rem_euclid(1.0);
+        (u, theta / PI)
+    }
+
+    /// Direction seen at the given image coordinates, with the sine of its polar angle
+    fn uv_to_direction(&self, u: f64, v: f64) -> (Vec3, f64) {
+        let phi = 2.0 * PI * (u - self.rotation);
+        let theta = PI * v;
+        let sin_theta = theta.sin();
+        let direction = Vec3::new(-sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin());
+        (direction, sin_theta)
+    }
+
+    fn lookup(&self, u: f64, v: f64) -> Vec3 {
+        let col = ((u * self.width as f64) as usize).min(self.width - 1);
+        let row = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[row * self.width + col] * self.intensity
+    }
+
+    /// Radiance coming from the given direction
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = self.direction_to_uv(direction);
+        self.lookup(u, v)
+    }
+}
+
+fn load_hdr(path: &Path) -> Result<(usize, usize, Vec<Vec3>), Box<dyn Error>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let decoder = image::hdr::HdrDecoder::new(reader)?;
+    let metadata = decoder.metadata();
+    let pixels = decoder
+        .read_image_hdr()?
+        .iter()
+        .map(|p| Vec3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+        .collect();
+    Ok((metadata.width as usize, metadata.height as usize, pixels))
+}
+
+fn load_exr(path: &Path) -> Result<(usize, usize, Vec<Vec3>), Box<dyn Error>> {
+    let image = exr::prelude::read_first_rgba_layer_from_file(
+        path,
+        |resolution, _| {
+            (
+                resolution.width(),
+                vec![Vec3::default(); resolution.width() * resolution.height()],
+            )
+        },
+        |(width, pixels), position, (r, g, b, _a): (f32, f32, f32, f32)| {
+            pixels[position.y() * *width + position.x()] = Vec3::new(r as f64, g as f64, b as f64);
+        },
+    )?;
+    let (width, pixels) = image.layer_data.channel_data.pixels;
+    Ok((width, pixels.len() / width, pixels))
+}
+
+impl Material for EnvironmentLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emit(&self, _u: f64, _v: f64, direction: &Vec3) -> Vec3 {
+        self.radiance(direction)
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _p: &Vec3, u: (f64, f64)) -> Option<LightSample> {
+        let ((u, v), pdf_uv) = self.distribution.sample(u.0, u.1);
+        let (direction, sin_theta) = self.uv_to_direction(u, v);
+        if pdf_uv == 0.0 || sin_theta == 0.0 {
+            return None;
+        }
+
+        // Change of variables from the image to the sphere of directions
+        let pdf = pdf_uv / (2.0 * PI * PI * sin_theta);
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.lookup(u, v) / pdf,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn pdf(&self, _p: &Vec3, direction: &Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
@@ -0,0 +1,29 @@
+use crate::structs::Vec3;
+
+/// Light arriving on a point from a sampled direction
+pub struct LightSample {
+    /// Unit vector from the lit point toward the light
+    pub direction: Vec3,
+    /// Distance to the light, infinite for lights at infinity
+    pub distance: f64,
+    /// Incoming light, already divided by the density of the sampled direction
+    pub radiance: Vec3,
+    /// Solid angle density of the sampled direction when the light can also be
+    /// reached by scattered rays, to weight both strategies.
+    /// None for lights only reached by shadow rays.
+    pub pdf: Option<f64>,
+}
+
+/// Light sources not represented by a hitable.
+/// They are only reached by shadow rays, traced by the renderer toward a sample of the light.
+pub trait Light: Send + Sync {
+    /// Sample the light as seen from the point p.
+    /// u is a pair of random numbers in [0,1) used by lights with an extent.
+    fn sample(&self, p: &Vec3, u: (f64, f64)) -> Option<LightSample>;
+
+    /// Solid angle density with which sample would return direction from p.
+    /// Only needed by lights also reached by scattered rays.
+    fn pdf(&self, _p: &Vec3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,12 @@
+mod light;
+pub use light::*;
+mod point_light;
+pub use point_light::PointLight;
+mod spot_light;
+pub use spot_light::SpotLight;
+mod directional_light;
+pub use directional_light::DirectionalLight;
+mod environment_light;
+pub use environment_light::EnvironmentLight;
+mod physical_sky;
+pub use physical_sky::PhysicalSky;
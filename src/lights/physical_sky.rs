@@ -0,0 +1,293 @@
+use super::directional_light::sample_cone;
+use super::{DirectionalLight, Light, LightSample};
+use crate::hitables::HitRecord;
+use crate::materials::Material;
+use crate::samplers::Sampler;
+use crate::structs::{dot, unit_vector, xyz_to_linear_srgb, Ray, Vec3};
+
+use std::f64::consts::PI;
+
+/// Solar illuminance above the atmosphere, in kilolux
+const SOLAR_ILLUMINANCE: f64 = 127.0;
+/// Apparent diameter of the sun in degrees
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+
+/// Coefficients of the Perez sky luminance distribution
+#[derive(Debug, Copy, Clone)]
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    /// Relative value for a view zenith angle theta, at an angle gamma from the sun
+    fn eval(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Analytic daylight model from Preetham, Shirley and Smits,
+/// "A Practical Analytic Model for Daylight" (1999), with the disk of the sun.
+/// It is both the scene background and a light sampling the sun disk as a cone,
+/// so the sun lights the scene and shows in mirrors and glass.
+/// Radiance is in kcd/m², scaled by intensity.
+pub struct PhysicalSky {
+    to_sun: Vec3,
+    theta_sun: f64,
+    turbidity: f64,
+    pub intensity: f64,
+    /// Cosine of the angular radius of the sun disk
+    cos_sun_radius: f64,
+    /// Radiance of the sun disk, black below the horizon
+    sun_radiance: Vec3,
+    zenith: Vec3,
+    perez_y: Perez,
+    perez_x: Perez,
+    perez_yy: Perez,
+}
+
+impl PhysicalSky {
+    /// elevation: angle of the sun above the horizon (degrees).
+    /// azimuth: angle of the sun around the Y axis, from +X toward +Z (degrees).
+    /// turbidity: haziness of the atmosphere, from 2 (clear) to 10 (hazy).
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64, intensity: f64) -> PhysicalSky {
+        let elevation = elevation.to_radians();
+        let azimuth = azimuth.to_radians();
+        let to_sun = Vec3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.sin(),
+            elevation.cos() * azimuth.sin(),
+        );
+        let theta_sun = PI / 2.0 - elevation;
+        let t = turbidity;
+
+        // Zenith luminance (kcd/m²) and chromaticity
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let theta = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+        let turbidity_terms = [t * t, t, 1.0];
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let mut acc = 0.0;
+            for (i, row) in m.iter().enumerate() {
+                for (j, coefficient) in row.iter().enumerate() {
+                    acc += turbidity_terms[i] * coefficient * theta[j];
+                }
+            }
+            acc
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let mut sky = PhysicalSky {
+            to_sun,
+            theta_sun,
+            turbidity,
+            intensity,
+            cos_sun_radius: (SUN_ANGULAR_DIAMETER / 2.0).to_radians().cos(),
+            sun_radiance: Vec3::default(),
+            zenith: Vec3::new(zenith_luminance.max(0.0), zenith_x, zenith_y),
+            perez_y: Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            perez_x: Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            perez_yy: Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        };
+        if to_sun.y > 0.0 {
+            // Spread the illuminance over the disk
+            sky.sun_radiance =
+                sky.sun_transmittance() * SOLAR_ILLUMINANCE * intensity / sky.sun_solid_angle();
+        }
+        sky
+    }
+
+    /// Radiance of the sky (without the sun disk) in the given direction.
+    /// Below the horizon the sky is mirrored.
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let direction = unit_vector(*direction);
+        let cos_theta = direction.y.abs().max(0.01);
+        let gamma = dot(direction, self.to_sun).clamp(-1.0, 1.0).acos();
+
+        // Each value relative to the zenith one
+        let ratio = |perez: &Perez| perez.eval(cos_theta, gamma) / perez.eval(1.0, self.theta_sun);
+        let luminance = self.zenith.x * ratio(&self.perez_y);
+        let x = self.zenith.y * ratio(&self.perez_x);
+        let y = self.zenith.z * ratio(&self.perez_yy);
+        if y <= 0.0 {
+            return Vec3::default();
+        }
+
+        let xyz = Vec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = xyz_to_linear_srgb(&xyz) * self.intensity;
+        Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+
+    /// Transmittance of the atmosphere toward the sun on RGB channels,
+    /// from Rayleigh and aerosol (Ångström) optical depths
+    fn sun_transmittance(&self) -> Vec3 {
+        let theta = self.theta_sun.to_degrees().min(93.8);
+        // Kasten and Young relative air mass
+        let air_mass = 1.0 / (self.theta_sun.cos() + 0.15 * (93.885 - theta).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |lambda: f64| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        };
+        Vec3::new(
+            transmittance(0.65),
+            transmittance(0.55),
+            transmittance(0.45),
+        )
+    }
+
+    fn sun_solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - self.cos_sun_radius)
+    }
+
+    fn in_sun_disk(&self, direction: &Vec3) -> bool {
+        dot(unit_vector(*direction), self.to_sun) >= self.cos_sun_radius
+    }
+
+    /// Illuminance of the sun on a surface facing it, colored by the atmosphere
+    pub fn sun_irradiance(&self) -> Vec3 {
+        self.sun_radiance * self.sun_solid_angle()
+    }
+
+    /// Directional light matching the sun disk, for a sky used as a plain background.
+    /// A sky set as the environment already samples the sun, adding this light would
+    /// count it twice.
+    pub fn sun(&self) -> DirectionalLight {
+        DirectionalLight::new(-self.to_sun, self.sun_irradiance(), SUN_ANGULAR_DIAMETER)
+    }
+}
+
+impl Material for PhysicalSky {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emit(&self, _u: f64, _v: f64, direction: &Vec3) -> Vec3 {
+        if self.in_sun_disk(direction) {
+            self.radiance(direction) + self.sun_radiance
+        } else {
+            self.radiance(direction)
+        }
+    }
+}
+
+impl Light for PhysicalSky {
+    /// Samples the sun disk only, the sky is reached by scattered rays
+    fn sample(&self, _p: &Vec3, u: (f64, f64)) -> Option<LightSample> {
+        if self.sun_radiance == Vec3::default() {
+            return None;
+        }
+        let direction = sample_cone(self.to_sun, self.cos_sun_radius, u);
+        let pdf = 1.0 / self.sun_solid_angle();
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.emit(0.0, 0.0, &direction) / pdf,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn pdf(&self, _p: &Vec3, direction: &Vec3) -> f64 {
+        if self.sun_radiance != Vec3::default() && self.in_sun_disk(direction) {
+            1.0 / self.sun_solid_angle()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::luminance;
+
+    #[test]
+    fn sun_disk_is_seen_and_sampled() {
+        let sky = PhysicalSky::new(40.0, 30.0, 3.0, 1.0);
+        let to_sun = sky.to_sun;
+        // Far brighter than the sky right next to it
+        let beside = unit_vector(to_sun + Vec3::new(0.0, 0.02, 0.0));
+        assert!(
+            luminance(&sky.emit(0.0, 0.0, &to_sun)) > 1000.0 * luminance(&sky.radiance(&beside))
+        );
+        assert_eq!(sky.emit(0.0, 0.0, &beside), sky.radiance(&beside));
+        assert_eq!(Light::pdf(&sky, &Vec3::default(), &beside), 0.0);
+
+        // Samples of the cone stay in the disk, and bring the illuminance of the sun back
+        let mut irradiance = Vec3::default();
+        let n = 64;
+        for i in 0..n {
+            for j in 0..n {
+                let u = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                let sample = sky.sample(&Vec3::default(), u).unwrap();
+                assert!(sky.in_sun_disk(&sample.direction));
+                assert_eq!(
+                    sample.pdf,
+                    Some(Light::pdf(&sky, &Vec3::default(), &sample.direction))
+                );
+                irradiance += sample.radiance * dot(sample.direction, to_sun);
+            }
+        }
+        irradiance /= (n * n) as f64;
+        let expected = sky.sun_irradiance() + sky.radiance(&to_sun) * sky.sun_solid_angle();
+        for c in 0..3 {
+            assert!((irradiance[c] - expected[c]).abs() < 1e-3 * expected[c]);
+        }
+    }
+
+    #[test]
+    fn sun_light_matches_the_disk() {
+        let sky = PhysicalSky::new(40.0, 30.0, 3.0, 1.0);
+        let sun = sky.sun();
+        assert!((sun.direction() + sky.to_sun).length() < 1e-12);
+        assert_eq!(sun.irradiance, sky.sun_irradiance());
+        let sample = sun.sample(&Vec3::default(), (0.5, 0.5)).unwrap();
+        assert!(sky.in_sun_disk(&sample.direction));
+    }
+
+    #[test]
+    fn no_sun_at_night() {
+        let sky = PhysicalSky::new(-10.0, 30.0, 3.0, 1.0);
+        assert!(sky.sample(&Vec3::default(), (0.5, 0.5)).is_none());
+        assert_eq!(sky.sun_irradiance(), Vec3::default());
+        assert_eq!(sky.sun().irradiance, Vec3::default());
+    }
+}
@@ -0,0 +1,26 @@
+use super::{Light, LightSample};
+use crate::structs::Vec3;
+
+/// Light emitted equally in every direction from a single point
+pub struct PointLight {
+    pub position: Vec3,
+    /// Power per solid angle on RGB channels
+    pub intensity: Vec3,
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Vec3, _u: (f64, f64)) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / (distance * distance),
+            pdf: None,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use super::{Light, LightSample};
+use crate::structs::{dot, unit_vector, Vec3};
+
+/// Point light restricted to a cone
+pub struct SpotLight {
+    pub position: Vec3,
+    direction: Vec3,
+    pub intensity: Vec3,
+    cos_cone: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    /// cone_angle is the half angle (degrees) outside which nothing is lit,
+    /// falloff_angle the half angle where the intensity starts to decrease.
+    pub fn new(
+        position: Vec3,
+        target: Vec3,
+        intensity: Vec3,
+        cone_angle: f64,
+        falloff_angle: f64,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            direction: unit_vector(target - position),
+            intensity,
+            cos_cone: cone_angle.to_radians().cos(),
+            cos_falloff_start: falloff_angle.min(cone_angle).to_radians().cos(),
+        }
+    }
+
+    /// Smooth decrease of the intensity between the falloff start and the cone border
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta < self.cos_cone {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        let t = (cos_theta - self.cos_cone) / (self.cos_falloff_start - self.cos_cone);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Vec3, _u: (f64, f64)) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+
+        let falloff = self.falloff(dot(-direction, self.direction));
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * falloff / (distance * distance),
+            pdf: None,
+        })
+    }
+}
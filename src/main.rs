@@ -1,494 +1,330 @@
-mod cameras;
-mod hitables;
-mod materials;
-mod structs;
-
-use cameras::*;
-use hitables::*;
-use materials::*;
-
-use rand::Rng;
+use rust_tracing::cameras::*;
+use rust_tracing::hitables::*;
+use rust_tracing::integrators::*;
+use rust_tracing::render::*;
+use rust_tracing::samplers::*;
+use rust_tracing::scenes::*;
+use rust_tracing::scheduler::*;
+
+use std::fs;
 use std::ops::Div;
-use std::sync::mpsc;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::thread;
-use std::time::Instant;
-
-struct Sky {
-    pub color1: Vec3,
-    pub color2: Vec3,
-}
-
-impl Material for Sky {
-    fn scatter(&self, _ray: &Ray, _rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        None
+use std::time::{Duration, Instant};
+
+/// Checkpoint to continue from, None when there is none.
+/// Exits when it was written by a render with other settings.
+fn load_checkpoint(checkpointing: &Checkpointing, seed: u64) -> Option<Checkpoint> {
+    let path = std::path::Path::new(&checkpointing.path);
+    if !path.exists() {
+        return None;
     }
-
-    fn emit(&self, _u: f64, _v: f64, direction: &Vec3) -> Vec3 {
-        let unit_direction = unit_vector(*direction);
-        let t: f64 = 0.5 * (unit_direction.y + 1.0);
-        (1.0 - t) * self.color1 + t * self.color2
-    }
-}
-
-fn make_sky_material(color1: &Vec3, color2: &Vec3) -> Arc<dyn Material> {
-    Arc::new(Sky {
-        color1: color1.clone(),
-        color2: color2.clone(),
-    })
+    let checkpoint =
+        Checkpoint::resume(path, checkpointing.settings_hash, seed).unwrap_or_else(|error| {
+            eprintln!("cannot resume: {}", error);
+            process::exit(1);
+        });
+    eprintln!("Resuming from {}", path.display());
+    Some(checkpoint)
 }
 
-/// Generate a random scene with 484 little random spheres,
-/// 3 bigger spheres in center, and a spheric ground.
-fn random_scene(background: &Vec3) -> HitableList {
-    let mut rng = rand::thread_rng();
-    let background = make_sky_material(&Vec3::new(1.0, 1.0, 1.0), background);
-
-    let mut world = HitableList::new(background);
-
-    let mut texture_manager = ResourceManager::new();
-
-    let odd_color = TextureConfig {
-        kind: TextureKind::Constant(Color::new(51, 77, 26)),
+/// Path of a frame of an animation, its number goes before the extension
+fn frame_path(path: &str, frame: u32) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{:04}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}_{:04}", stem, frame),
     };
-    let even_color = TextureConfig {
-        kind: TextureKind::Constant(Color::new(230, 230, 230)),
-    };
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
-        material: Arc::new(Lambertian {
-            albedo: texture_manager.get_resource(&TextureConfig {
-                kind: TextureKind::Checker(&odd_color, &even_color),
-            }),
-        }),
-    }));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rng.gen::<f64>();
-            let center = Vec3::new(
-                f64::from(a) + 0.9 * rng.gen::<f64>(),
-                0.2,
-                f64::from(b) + 0.9 * rng.gen::<f64>(),
-            );
-            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    // Diffuse
-                    let albedo = rng.gen::<Vec3>() * rng.gen::<Vec3>();
-                    let texture = texture_manager.get_resource(&TextureConfig {
-                        kind: TextureKind::Constant(Color::new(
-                            (albedo.x * 255.99) as u8,
-                            (albedo.y * 255.99) as u8,
-                            (albedo.z * 255.99) as u8,
-                        )),
-                    });
-                    world.push(Arc::new(MovingSphere {
-                        center0: center,
-                        center1: center + Vec3::new(0.0, 0.5 * rng.gen::<f64>(), 0.0),
-                        time0: 0.0,
-                        time1: 1.0,
-                        radius: 0.2,
-                        material: Arc::new(Lambertian { albedo: texture }),
-                    }));
-                } else if choose_mat < 0.95 {
-                    // Metal
-                    let albedo = Vec3::random_range(0.5, 1.0);
-                    let fuzz = rng.gen_range(0.0, 0.5);
-                    world.push(Arc::new(Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Arc::new(Metal::new(albedo, fuzz)),
-                    }));
-                } else {
-                    // Glass
-                    world.push(Arc::new(Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Arc::new(Dielectric { ref_idx: 1.5 }),
-                    }));
-                }
-            }
-        }
-    }
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Arc::new(Dielectric { ref_idx: 1.5 }),
-    }));
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(-4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Arc::new(Lambertian {
-            albedo: Arc::new(SolidColor::new(Vec3::new(0.4, 0.2, 0.1))),
-        }),
-    }));
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
-    }));
-
-    world
+    path.with_file_name(name).to_string_lossy().into_owned()
 }
 
-fn two_spheres(background: &Vec3) -> HitableList {
-    let background = make_sky_material(&Vec3::new(1.0, 1.0, 1.0), background);
-
-    let mut world = HitableList::new(background);
-
-    let mut texture_manager = ResourceManager::new();
-
-    let odd_color = TextureConfig {
-        kind: TextureKind::Constant(Color::new(51, 77, 26)),
-    };
-    let even_color = TextureConfig {
-        kind: TextureKind::Constant(Color::new(230, 230, 230)),
-    };
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, -10.0, 0.0),
-        radius: 10.0,
-        material: Arc::new(Lambertian {
-            albedo: texture_manager.get_resource(&TextureConfig {
-                kind: TextureKind::Checker(&odd_color, &even_color),
-            }),
-        }),
-    }));
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, 10.0, 0.0),
-        radius: 10.0,
-        material: Arc::new(Lambertian {
-            albedo: texture_manager.get_resource(&TextureConfig {
-                kind: TextureKind::Checker(&odd_color, &even_color),
-            }),
-        }),
-    }));
-
-    world
+/// Camera model, picked with `--projection`
+#[derive(Debug, Copy, Clone)]
+enum Projection {
+    /// Perspective with depth of field
+    ThinLens,
+    /// Parallel rays, vfov is replaced by the view height in world units
+    Orthographic(f64),
+    /// Takes the diagonal field of view in degrees
+    Fisheye(FisheyeMapping, f64),
+    /// Full 360° panorama, usually rendered with a 2:1 aspect ratio
+    Equirectangular,
+    /// 50mm double gauss lens on a full frame film, focused on lookat.
+    /// Takes the stop diameter in millimeters and the number of aperture blades,
+    /// the scene is assumed to be in meters.
+    Lens(Option<f64>, u32),
 }
 
-fn two_perlin_spheres(background: &Vec3) -> HitableList {
-    let background = make_sky_material(&Vec3::new(1.0, 1.0, 1.0), background);
-
-    let mut world = HitableList::new(background);
-
-    let mut texture_manager = ResourceManager::new();
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
-        material: Arc::new(Lambertian {
-            albedo: texture_manager.get_resource(&TextureConfig {
-                kind: TextureKind::Perlin(256, 4),
-            }),
-        }),
-    }));
-
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, 2.0, 0.0),
-        radius: 2.0,
-        material: Arc::new(Lambertian {
-            albedo: texture_manager.get_resource(&TextureConfig {
-                kind: TextureKind::Perlin(256, 4),
-            }),
-        }),
-    }));
-
-    world
-}
-
-fn earth(background: &Vec3) -> HitableList {
-    let mut texture_manager = ResourceManager::new();
-    let earth_texture = texture_manager.get_resource(&TextureConfig {
-        kind: TextureKind::FromFile(std::path::Path::new("assets/images/earthmap.jpg")),
-    });
-    let earth_surface = Arc::new(Lambertian {
-        albedo: earth_texture,
-    });
-    let globe = Arc::new(Sphere {
-        center: Vec3::default(),
-        radius: 2.0,
-        material: earth_surface,
-    });
-
-    let background = make_sky_material(&Vec3::new(1.0, 1.0, 1.0), background);
-
-    let mut world = HitableList::new(background);
-
-    world.push(globe);
-
-    world
+/// Prescription of the `lens` projection
+const LENS_PATH: &str = "assets/lenses/dgauss.50mm.dat";
+/// Field of view of fisheyes when none is given, in degrees
+const DEFAULT_FISHEYE_FOV: f64 = 180.0;
+/// View height of orthographic cameras when none is given, in world units
+const DEFAULT_VIEW_HEIGHT: f64 = 10.0;
+
+impl Projection {
+    /// Names recognized by `from_name`
+    const NAMES: &'static str = "thin-lens, orthographic[:height], fisheye[:fov], \
+                                 equisolid[:fov], equirectangular, lens[:stop[:blades]]";
+
+    /// Projection given on the command line, a name optionally followed by `:` and values.
+    /// None when the name is unknown or a value can't be read.
+    fn from_name(spec: &str) -> Option<Projection> {
+        let mut parts = spec.split(':');
+        let name = parts.next()?;
+        let values = parts
+            .map(str::parse::<f64>)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let projection = match (name, values.as_slice()) {
+            ("thin-lens", []) => Projection::ThinLens,
+            ("orthographic", []) => Projection::Orthographic(DEFAULT_VIEW_HEIGHT),
+            ("orthographic", [height]) => Projection::Orthographic(*height),
+            ("fisheye", []) => {
+                Projection::Fisheye(FisheyeMapping::Equidistant, DEFAULT_FISHEYE_FOV)
+            }
+            ("fisheye", [fov]) => Projection::Fisheye(FisheyeMapping::Equidistant, *fov),
+            ("equisolid", []) => {
+                Projection::Fisheye(FisheyeMapping::Equisolid, DEFAULT_FISHEYE_FOV)
+            }
+            ("equisolid", [fov]) => Projection::Fisheye(FisheyeMapping::Equisolid, *fov),
+            ("equirectangular", []) => Projection::Equirectangular,
+            ("lens", []) => Projection::Lens(None, 0),
+            ("lens", [stop]) => Projection::Lens(Some(*stop), 0),
+            ("lens", [stop, blades]) if blades.fract() == 0.0 && *blades >= 0.0 => {
+                Projection::Lens(Some(*stop), *blades as u32)
+            }
+            _ => return None,
+        };
+        Some(projection)
+    }
 }
 
-struct BlackMaterial;
-
-impl Material for BlackMaterial {
-    fn scatter(&self, _ray: &Ray, _rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        None
+/// lens is the prescription of the `lens` projection, read once for all its cameras
+fn make_camera(
+    projection: Projection,
+    lens: Option<&LensSystem>,
+    settings: &CameraSettings,
+    aspect: f64,
+    shutter: (f64, f64),
+    eye_offset: f64,
+) -> Arc<dyn Camera> {
+    let CameraSettings {
+        lookfrom,
+        lookat,
+        vfov,
+        aperture,
+        focus_dist,
+    } = *settings;
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let (t0, t1) = shutter;
+    match projection {
+        Projection::ThinLens => Arc::new(ThinLensCamera::new_look_at(
+            lookfrom, lookat, up, vfov, aspect, aperture, focus_dist, t0, t1,
+        )),
+        Projection::Orthographic(view_height) => Arc::new(OrthographicCamera::new_look_at(
+            lookfrom,
+            lookat,
+            up,
+            view_height,
+            aspect,
+            t0,
+            t1,
+        )),
+        Projection::Fisheye(mapping, fov) => Arc::new(FisheyeCamera::new_look_at(
+            lookfrom, lookat, up, fov, aspect, mapping, t0, t1,
+        )),
+        Projection::Lens(aperture, blades) => Arc::new(
+            RealisticCamera::new_look_at(
+                lookfrom,
+                lookat,
+                up,
+                lens.expect("no lens prescription read"),
+                43.27,
+                aspect,
+                aperture,
+                blades,
+                (lookat - lookfrom).length(),
+                1000.0,
+                t0,
+                t1,
+            )
+            .expect("cannot focus the lens"),
+        ),
+        Projection::Equirectangular => Arc::new(
+            EquirectangularCamera::new_look_at(lookfrom, lookat, up, t0, t1)
+                .with_eye_offset(eye_offset),
+        ),
     }
 }
 
-fn simple_light() -> HitableList {
-    let background = Arc::new(BlackMaterial {});
-    let mut world = HitableList::new(background);
-
-    let mut texture_manager = ResourceManager::new();
-    let perlin_texture = texture_manager.get_resource(&TextureConfig {
-        kind: TextureKind::Perlin(256, 4),
-    });
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
-        material: Arc::new(Lambertian {
-            albedo: perlin_texture.clone(),
-        }),
-    }));
-    world.push(Arc::new(Sphere {
-        center: Vec3::new(0.0, 2.0, 0.0),
-        radius: 2.0,
-        material: Arc::new(Lambertian {
-            albedo: perlin_texture.clone(),
-        }),
-    }));
-
-    let light = Arc::new(Emissive {
-        emit: texture_manager.get_resource(&TextureConfig {
-            kind: TextureKind::Constant(Color {
-                r: 255,
-                g: 255,
-                b: 255,
-            }),
-        }),
-        multiplier: 4.0,
-    });
-    world.push(Arc::new(XYRect {
-        x0: 3.0,
-        x1: 5.0,
-        y0: 1.0,
-        y1: 3.0,
-        k: -2.0,
-        material: light,
-    }));
-
-    world
+/// Camera for the whole image, or both eyes over/under when an interpupillary distance is given
+fn make_rig(
+    projection: Projection,
+    lens: Option<&LensSystem>,
+    stereo: Option<f64>,
+    settings: &CameraSettings,
+    image_width: u32,
+    image_height: u32,
+    shutter: (f64, f64),
+) -> Arc<dyn Camera> {
+    match stereo {
+        None => make_camera(
+            projection,
+            lens,
+            settings,
+            f64::from(image_width) / f64::from(image_height),
+            shutter,
+            0.0,
+        ),
+        Some(ipd) => {
+            let aspect_ratio = f64::from(image_width) / f64::from(image_height / 2);
+            let eye = |(lookfrom, lookat): (Vec3, Vec3), side: f64| match projection {
+                // A panorama has no single viewing direction, the eyes turn with each ray
+                Projection::Equirectangular => make_camera(
+                    projection,
+                    lens,
+                    settings,
+                    aspect_ratio,
+                    shutter,
+                    side * ipd / 2.0,
+                ),
+                _ => {
+                    let settings = CameraSettings {
+                        lookfrom,
+                        lookat,
+                        ..*settings
+                    };
+                    make_camera(projection, lens, &settings, aspect_ratio, shutter, 0.0)
+                }
+            };
+            let [left, right] = stereo_eyes(
+                settings.lookfrom,
+                settings.lookat,
+                Vec3::new(0.0, 1.0, 0.0),
+                ipd,
+            );
+            Arc::new(StereoCamera::new(eye(left, -1.0), eye(right, 1.0)))
+        }
+    }
 }
 
-fn cornell_box() -> HitableList {
-    let background = Arc::new(BlackMaterial {});
-    let mut world = HitableList::new(background);
-    let mut texture_manager = ResourceManager::new();
-
-    let red = Arc::new(Lambertian {
-        albedo: texture_manager.get_resource(&TextureConfig {
-            kind: TextureKind::Constant(Color {
-                r: 166,
-                g: 13,
-                b: 13,
-            }),
-        }),
-    });
-    let white = Arc::new(Lambertian {
-        albedo: texture_manager.get_resource(&TextureConfig {
-            kind: TextureKind::Constant(Color {
-                r: 186,
-                g: 186,
-                b: 186,
-            }),
-        }),
-    });
-    let green = Arc::new(Lambertian {
-        albedo: texture_manager.get_resource(&TextureConfig {
-            kind: TextureKind::Constant(Color {
-                r: 31,
-                g: 115,
-                b: 38,
-            }),
-        }),
-    });
-    let light = Arc::new(Emissive {
-        emit: texture_manager.get_resource(&TextureConfig {
-            kind: TextureKind::Constant(Color {
-                r: 255,
-                g: 255,
-                b: 255,
-            }),
-        }),
-        multiplier: 15.0,
-    });
-
-    let box_size = 555.0;
-    world.push(Arc::new(YZRect {
-        material: green,
-        y0: 0.0,
-        y1: box_size,
-        z0: 0.0,
-        z1: box_size,
-        k: box_size,
-    }));
-    world.push(Arc::new(YZRect {
-        material: red,
-        y0: 0.0,
-        y1: box_size,
-        z0: 0.0,
-        z1: box_size,
-        k: 0.0,
-    }));
-    world.push(Arc::new(XZRect {
-        material: light,
-        x0: 213.0,
-        x1: 343.0,
-        z0: 227.0,
-        z1: 332.0,
-        k: 554.0,
-    }));
-    world.push(Arc::new(XZRect {
-        material: white.clone(),
-        x0: 0.0,
-        x1: box_size,
-        z0: 0.0,
-        z1: box_size,
-        k: 0.0,
-    }));
-    world.push(Arc::new(XZRect {
-        material: white.clone(),
-        x0: 0.0,
-        x1: box_size,
-        z0: 0.0,
-        z1: box_size,
-        k: box_size,
-    }));
-    world.push(Arc::new(XYRect {
-        material: white.clone(),
-        x0: 0.0,
-        x1: box_size,
-        y0: 0.0,
-        y1: box_size,
-        k: box_size,
-    }));
-
-    world
+/// Value following flag on the command line, None when the flag isn't given.
+/// Exits showing usage as the expected value when it is missing or can't be read.
+fn option<T: FromStr>(args: &[String], flag: &str, usage: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    let value = args.get(index + 1).and_then(|value| value.parse().ok());
+    if value.is_none() {
+        eprintln!("usage: {} <{}>", flag, usage);
+        process::exit(2);
+    }
+    value
 }
 
-/// Compute the color of the current ray
-/// in the world of hitables.
-/// This function run recursively until maximum number of recursions
-/// (depth parameter) is reached or no hitable is hit.
-fn color(ray: &Ray, background: &Arc<dyn Material>, world: &dyn Hitable, depth: u32) -> Vec3 {
-    if depth == 0 {
-        return Vec3::default();
+/// Passes given on the command line, `all` or names separated by commas.
+/// None when a name is unknown.
+fn aov_list(names: &str) -> Option<Vec<Aov>> {
+    if names == "all" {
+        return Some(Aov::ALL.to_vec());
     }
-
-    let record = world.hit(&ray, 0.001, std::f64::MAX);
-    match record {
-        Some(rec) => {
-            let res = rec.material.scatter(&ray, &rec);
-            let emitted = rec.material.emit(rec.u, rec.v, &rec.p);
-            if let Some((attenuation, scattered)) = res {
-                return emitted + attenuation * color(&scattered, background, world, depth - 1);
-            }
-            emitted
-        }
-        None => {
-            let emitted = background.emit(0.0, 0.0, &ray.direction());
-            emitted
+    let mut aovs: Vec<Aov> = vec![];
+    for name in names.split(',') {
+        let aov = Aov::from_name(name)?;
+        if !aovs.contains(&aov) {
+            aovs.push(aov);
         }
     }
+    Some(aovs)
 }
 
-fn gamma(color: Vec3) -> Vec3 {
-    Vec3 {
-        x: color.x.sqrt(),
-        y: color.y.sqrt(),
-        z: color.z.sqrt(),
+/// Corners of a crop window, from the top left
+const CROP_USAGE: &str = "x0,y0,x1,y1";
+
+/// Crop window given on the command line as `x0,y0,x1,y1`, fractions of the image size.
+/// None when a fraction can't be read or the window is empty.
+fn crop_window(spec: &str) -> Option<CropWindow> {
+    let fractions = spec
+        .split(',')
+        .map(str::parse::<f64>)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    match fractions.as_slice() {
+        &[x0, y0, x1, y1]
+            if (0.0..x1).contains(&x0) && x1 <= 1.0 && (0.0..y1).contains(&y0) && y1 <= 1.0 =>
+        {
+            Some(CropWindow { x0, y0, x1, y1 })
+        }
+        _ => None,
     }
 }
 
-/// Dispatch ray-tracing algorithm on several threads to create an image of the current scene
-fn render(
-    image_width: u32,
-    image_height: u32,
-    sample_per_pixel: u32,
-    world: HitableList,
-    camera: Arc<dyn Camera>,
-) -> Image {
-    let max_depth: u32 = 10;
-    let thread_count = debug_limiter(16, 1);
-
-    let mut handles = vec![];
-    let lines: Arc<Vec<u32>> = Arc::new((0..image_height).rev().collect());
-
-    let lines_per_thread = lines.len() / thread_count;
-    let tougher_threads = lines.len() % thread_count;
-    let mut offset = 0;
-
-    let (tx, rx) = mpsc::channel();
-
-    // Compute a BVH of the scene
-    let bvh = BVHNode::new(&world, 0.0, 0.1);
-
-    for id in 0..thread_count {
-        let chunksize = if id < tougher_threads {
-            lines_per_thread + 1
-        } else {
-            lines_per_thread
-        };
-
-        let camera = camera.clone();
-        let lines = lines.clone();
-        let tx = tx.clone();
-        let bvh = bvh.clone();
-        let background = world.background.clone();
-
-        handles.push(thread::spawn(move || {
-            let mut rng = rand::thread_rng();
-            let mut pixels = vec![];
-            let end = offset + chunksize;
-            for j in &lines[offset..end] {
-                for i in 0..image_width {
-                    let mut col = Vec3::default();
-                    for _s in 0..sample_per_pixel {
-                        let u = (f64::from(i) + rng.gen::<f64>()) / f64::from(image_width);
-                        let v = (f64::from(*j) + rng.gen::<f64>()) / f64::from(image_height);
-                        let r = camera.get_ray(u, v);
-
-                        let _p = r.point_at(2.0);
-                        col += color(&r, &background, &bvh, max_depth);
-                    }
-
-                    col /= f64::from(sample_per_pixel);
-                    let col = gamma(col);
-
-                    pixels.push(col.x);
-                    pixels.push(col.y);
-                    pixels.push(col.z);
-                }
-                tx.send(1).unwrap();
-            }
-            pixels
-        }));
-        offset += chunksize;
+/// Denoiser asked for on the command line, by `--denoise` or by setting one of its strengths
+fn denoiser_option(args: &[String]) -> Option<Denoiser> {
+    let defaults = Denoiser::default();
+    let denoiser = Denoiser {
+        iterations: option(args, "--denoise-iterations", "passes").unwrap_or(defaults.iterations),
+        luminance_strength: option(args, "--denoise-luminance", "standard deviations")
+            .unwrap_or(defaults.luminance_strength),
+        normal_sharpness: option(args, "--denoise-normal", "exponent")
+            .unwrap_or(defaults.normal_sharpness),
+        depth_strength: option(args, "--denoise-depth", "relative depth")
+            .unwrap_or(defaults.depth_strength),
+        albedo_strength: option(args, "--denoise-albedo", "albedo difference")
+            .unwrap_or(defaults.albedo_strength),
+    };
+    let asked = args
+        .iter()
+        .any(|arg| arg == "--denoise" || arg.starts_with("--denoise-"));
+    if asked {
+        Some(denoiser)
+    } else {
+        None
     }
+}
 
-    // print status
-    // lines is a reversed list of number, so it work well to count
-    for i in lines.iter() {
-        rx.recv().unwrap();
-        eprint!("Scanlines remaining: {}    \r", i);
+/// Sampling asked for on the command line, a fixed number of samples per pixel unless
+/// `--adaptive` or one of its settings is given
+fn sampling_option(args: &[String], samples: u32) -> Sampling {
+    let min = option(args, "--min-spp", "samples").unwrap_or(samples / 8);
+    let threshold = option(args, "--threshold", "relative error").unwrap_or(0.02);
+    let asked = args
+        .iter()
+        .any(|arg| arg == "--adaptive" || arg == "--min-spp" || arg == "--threshold");
+    if asked {
+        Sampling::Adaptive {
+            min,
+            max: samples,
+            threshold,
+        }
+    } else {
+        Sampling::Uniform(samples)
     }
+}
 
-    let mut buffer = Vec::new();
-
-    for handle in handles {
-        let mut pixels = handle.join().unwrap();
-        buffer.append(&mut pixels);
+/// Path settings asked for on the command line, the bounce limits of the scene otherwise
+fn path_settings_option(args: &[String], max_depth: u32) -> PathSettings {
+    let defaults = PathSettings::new(max_depth);
+    let roulette_depth = option::<String>(args, "--roulette-depth", "bounces|off")
+        .map(|depth| match depth.as_str() {
+            "off" => None,
+            _ => Some(depth.parse().unwrap_or_else(|_| {
+                eprintln!("usage: --roulette-depth <bounces|off>");
+                process::exit(2);
+            })),
+        })
+        .unwrap_or(defaults.roulette_depth);
+    PathSettings {
+        max_diffuse: option(args, "--max-diffuse", "bounces").unwrap_or(defaults.max_diffuse),
+        max_specular: option(args, "--max-specular", "bounces").unwrap_or(defaults.max_specular),
+        max_transmission: option(args, "--max-transmission", "bounces")
+            .unwrap_or(defaults.max_transmission),
+        roulette_depth,
+        clamp_indirect: option(args, "--clamp-indirect", "luminance"),
+        ..defaults
     }
-
-    Image::from(image_width, image_height, PixelFormat::RGBU8, &buffer)
 }
 
 /// divise a number by a divisor when debug is the active target
@@ -500,84 +336,422 @@ fn debug_limiter<T: Div<Output = T> + Copy>(number: T, divisor: T) -> T {
     }
 }
 
+/// Seed of every random choice, a given seed always renders the same image
+const SEED: u64 = 0;
+/// Frames per second of animated scenes
+const FRAME_RATE: f64 = 24.0;
+/// Fraction of the frame duration the shutter stays open
+const SHUTTER: f64 = 0.5;
+
+/// `compare <image.ppm> <reference.ppm> [difference.ppm]`, prints the error metrics of the image
+/// and writes the false color map of its perceptual error when a path is given
+fn compare_command(args: &[String]) -> i32 {
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("usage: rust_tracing compare <image.ppm> <reference.ppm> [difference.ppm]");
+        return 2;
+    }
+    let read = |path: &str| {
+        fs::read_to_string(path).and_then(|text| Image::decode(&text, ImageFormat::PPM))
+    };
+    let (image, reference) = match (read(&args[0]), read(&args[1])) {
+        (Ok(image), Ok(reference)) => (image, reference),
+        (Err(error), _) | (_, Err(error)) => {
+            eprintln!("cannot read the images: {}", error);
+            return 1;
+        }
+    };
+    if (image.width(), image.height()) != (reference.width(), reference.height()) {
+        eprintln!(
+            "{}x{} image and {}x{} reference",
+            image.width(),
+            image.height(),
+            reference.width(),
+            reference.height()
+        );
+        return 1;
+    }
+
+    let errors = image.compare(&reference);
+    println!("MSE     {:.6}", errors.mse);
+    println!("RMSE    {:.6}", errors.rmse);
+    println!("relMSE  {:.6}", errors.relative_mse);
+    println!("PSNR    {:.2} dB", errors.psnr);
+    println!("SSIM    {:.4}", errors.ssim);
+    println!("FLIP    {:.4}", errors.flip);
+    if let Some(path) = args.get(2) {
+        let difference = image.difference_image(&reference);
+        if let Err(error) = fs::write(path, format!("{}\n", difference.encode(ImageFormat::PPM))) {
+            eprintln!("cannot write {}: {}", path, error);
+            return 1;
+        }
+    }
+    0
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("compare") {
+        process::exit(compare_command(&args[2..]));
+    }
+    // `--integrator <name>` picks how the light is estimated, paths sampling the lights by default
+    let integrator_kind = option::<String>(&args, "--integrator", IntegratorKind::NAMES)
+        .map(|name| {
+            IntegratorKind::from_name(&name).unwrap_or_else(|| {
+                eprintln!("usage: --integrator <{}>", IntegratorKind::NAMES);
+                process::exit(2);
+            })
+        })
+        .unwrap_or(IntegratorKind::Path);
+    let integrator = integrator_kind.create();
+
     let image_width: u32 = debug_limiter(1920, 4);
     let image_height: u32 = debug_limiter(1080, 4);
-    let sample_per_pixel: u32 = debug_limiter(400, 2);
-
-    // Create a scene
-    let scene = 5;
-    let (world, lookfrom, lookat, vfov, aperture) = match scene {
-        0 => (
-            random_scene(&Vec3::new(0.5, 0.7, 1.0)),
-            Vec3::new(13.0, 2.0, 3.0),
-            Vec3::default(),
-            20.0,
-            0.1,
-        ),
-        1 => (
-            two_spheres(&Vec3::new(0.5, 0.7, 1.0)),
-            Vec3::new(13.0, 2.0, 3.0),
-            Vec3::default(),
-            20.0,
-            0.0,
-        ),
-        2 => (
-            two_perlin_spheres(&Vec3::new(0.5, 0.7, 1.0)),
-            Vec3::new(13.0, 2.0, 3.0),
-            Vec3::default(),
-            20.0,
-            0.0,
-        ),
-        3 => (
-            earth(&Vec3::new(0.5, 0.7, 1.0)),
-            Vec3::new(13.0, 2.0, 3.0),
-            Vec3::default(),
-            20.0,
-            0.0,
-        ),
-        4 => (
-            simple_light(),
-            Vec3::new(26.0, 3.0, 6.0),
-            Vec3::new(0.0, 2.0, 0.0),
-            20.0,
-            0.0,
-        ),
-        5 | _ => (
-            cornell_box(),
-            Vec3::new(278.0, 278.0, -800.0),
-            Vec3::new(278.0, 278.0, 0.0),
-            40.0,
-            0.0,
+    // `--target-spp <samples>` is the number of samples of each pixel, the most when adaptive
+    let sample_per_pixel: u32 =
+        option(&args, "--target-spp", "samples").unwrap_or_else(|| debug_limiter(400, 2));
+    if sample_per_pixel == 0 {
+        eprintln!("usage: --target-spp <samples>");
+        process::exit(2);
+    }
+    // `--time-budget <secs>` stops the render, or each frame, once the time is up
+    let time_budget = option::<f64>(&args, "--time-budget", "seconds").map(|secs| {
+        Duration::try_from_secs_f64(secs).unwrap_or_else(|_| {
+            eprintln!("usage: --time-budget <seconds>");
+            process::exit(2);
+        })
+    });
+
+    // `--scene <number>` picks one of the built-in scenes, the Cornell box by default
+    let scene: u32 = option(&args, "--scene", "0-11").unwrap_or(5);
+    let Preset {
+        world,
+        camera: still,
+        max_depth,
+    } = preset(scene, SEED);
+
+    // `--spectral` traces wavelengths instead of RGB, slower but dispersion shows (scene 8)
+    let mode = if args.iter().any(|arg| arg == "--spectral") {
+        ColorMode::Spectral
+    } else {
+        ColorMode::Rgb
+    };
+    // Only the path tracer follows the wavelengths through the scene,
+    // the other integrators would quietly render without dispersion
+    if matches!(mode, ColorMode::Spectral) && integrator_kind != IntegratorKind::Path {
+        eprintln!("--spectral only works with --integrator path");
+        process::exit(2);
+    }
+    // Russian roulette ends dim paths after `--roulette-depth <bounces|off>` (3 by default),
+    // `--max-<diffuse|specular|transmission> <bounces>` limit the bounces of each kind and
+    // `--clamp-indirect <luminance>` clamps the indirect light to remove fireflies
+    let paths = path_settings_option(&args, max_depth);
+
+    // `--sampler <name>` picks the sequence deciding the random choices, independent by default
+    let sampler_kind = option::<String>(&args, "--sampler", SamplerKind::NAMES)
+        .map(|name| {
+            SamplerKind::from_name(&name).unwrap_or_else(|| {
+                eprintln!("usage: --sampler <{}>", SamplerKind::NAMES);
+                process::exit(2);
+            })
+        })
+        .unwrap_or(SamplerKind::Independent);
+    // `--adaptive` stops converged pixels early, after `--min-spp <samples>` (an eighth by
+    // default) once their error is below `--threshold <relative error>` (0.02 by default).
+    // The sample count heat map shows where the samples went.
+    let sampling = sampling_option(&args, sample_per_pixel);
+    // `--projection <name>` picks the camera model, a thin lens by default
+    let projection = option::<String>(&args, "--projection", Projection::NAMES)
+        .map(|name| {
+            Projection::from_name(&name).unwrap_or_else(|| {
+                eprintln!("usage: --projection <{}>", Projection::NAMES);
+                process::exit(2);
+            })
+        })
+        .unwrap_or(Projection::ThinLens);
+    let lens = match projection {
+        Projection::Lens(..) => Some(
+            LensSystem::from_file(std::path::Path::new(LENS_PATH))
+                .expect("cannot read the lens prescription"),
         ),
+        _ => None,
     };
+    // `--stereo <distance>` renders both eyes over/under, separated by the distance in scene units
+    let stereo: Option<f64> = option(&args, "--stereo", "interpupillary distance");
+    // `--filter <name>` weighs the samples around each pixel, a box over the pixel by default
+    let filter = option::<String>(&args, "--filter", Filter::NAMES)
+        .map(|name| {
+            Filter::from_name(&name).unwrap_or_else(|| {
+                eprintln!("usage: --filter <{}>", Filter::NAMES);
+                process::exit(2);
+            })
+        })
+        .unwrap_or(Filter::Box { radius: 0.5 });
+    // `--crop <x0,y0,x1,y1>` only renders this part of the image, in fractions of its size
+    let crop = option::<String>(&args, "--crop", CROP_USAGE).map(|window| {
+        crop_window(&window).unwrap_or_else(|| {
+            eprintln!("usage: --crop <{}>", CROP_USAGE);
+            process::exit(2);
+        })
+    });
+    // `--aovs <name,name,...|all>` writes these passes to an OpenEXR file, none by default
+    let mut aovs: Vec<Aov> = option::<String>(&args, "--aovs", "all|names")
+        .map(|names| {
+            aov_list(&names).unwrap_or_else(|| {
+                eprintln!("usage: --aovs <all or names among {}>", Aov::NAMES);
+                process::exit(2);
+            })
+        })
+        .unwrap_or_default();
+    // `--denoise` denoises the image, the noisy one is kept next to it.
+    // `--denoise-<iterations|luminance|normal|depth|albedo> <value>` tune the filter
+    let denoiser = denoiser_option(&args);
+    if denoiser.is_some() {
+        // Guides of the denoiser, also written with the other passes
+        for guide in &[Aov::Albedo, Aov::Normal, Aov::Depth] {
+            if !aovs.contains(guide) {
+                aovs.push(*guide);
+            }
+        }
+    }
+    let aov_layout = AovLayout::new(&aovs, world.lights.len());
 
-    let aspect_ratio = f64::from(image_width) / f64::from(image_height);
-    let up = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let cam = ThinLensCamera::new_look_at(
-        lookfrom,
-        lookat,
-        up,
-        vfov,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-        0.0,
-        1.0,
+    // Numbered frames are written to the frames directory for animated scenes
+    let (animation, frames) = match scene {
+        11 => (turntable_camera(still), Some(0..debug_limiter(48u32, 4))),
+        _ => (CameraAnimation::new(still), None),
+    };
+
+    // Nothing but the image is written unless asked for:
+    // `--checkpoint <path>` saves the progress every minute and on Ctrl-C,
+    // `--resume` continues from it, `--snapshot <path>` writes the current estimate every
+    // 10 seconds, `--stats <path>` the render statistics as JSON,
+    // `--heat-map <path>` the samples taken by each pixel with `--adaptive`.
+    // Animations put the frame number before the extension of each path.
+    let checkpoint: Option<String> = option(&args, "--checkpoint", "path");
+    let snapshot: Option<String> = option(&args, "--snapshot", "path");
+    let stats: Option<String> = option(&args, "--stats", "path");
+    let heat_map: Option<String> = option(&args, "--heat-map", "path");
+    // Without adaptive sampling every pixel takes the same samples, the map would be flat
+    if heat_map.is_some() && !matches!(sampling, Sampling::Adaptive { .. }) {
+        eprintln!("--heat-map only works with --adaptive");
+        process::exit(2);
+    }
+    // `--aovs-path <path>` is where the passes go, aovs.exr or frames/aovs.exr by default
+    let aovs_path: String = option(&args, "--aovs-path", "path").unwrap_or_else(|| {
+        match frames {
+            None => "aovs.exr",
+            Some(_) => "frames/aovs.exr",
+        }
+        .to_string()
+    });
+    let resume = args.iter().any(|arg| arg == "--resume");
+    if resume && checkpoint.is_none() && frames.is_none() {
+        eprintln!("usage: --resume needs --checkpoint <path>");
+        process::exit(2);
+    }
+    ctrlc::set_handler(|| {
+        // A second Ctrl-C doesn't wait for the checkpoint
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            process::exit(130);
+        }
+    })
+    .expect("cannot handle SIGINT");
+
+    // Everything the pixels depend on, a checkpoint only resumes a render with the same settings.
+    // The scene is described by its content too, it may have changed since the checkpoint.
+    // The description probes each object with a single ray, see `HitableList::describe`:
+    // a change only on faces the probe misses still resumes.
+    let settings = format!(
+        "{:?}",
+        (
+            (scene, world.describe(0.0, 1.0)),
+            (image_width, image_height, crop),
+            sampling,
+            paths,
+            (mode, integrator_kind),
+            sampler_kind,
+            (projection, stereo, &animation),
+            filter,
+            &aovs,
+        )
     );
+    // Renders until the sampling is done when there's no time budget
+    let progress = |frame: Option<u32>| {
+        let path = |path: &String| frame.map_or(path.clone(), |frame| frame_path(path, frame));
+        // The checkpoint of a frame only resumes that frame
+        let frame_settings = format!("{} frame {}", settings, frame.unwrap_or(0));
+        Progress {
+            time_budget,
+            samples_per_pass: 4,
+            thread_count: debug_limiter(16, 1),
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            snapshot: snapshot
+                .as_ref()
+                .map(|snapshot| (path(snapshot), Duration::from_secs(10))),
+            checkpoint: checkpoint.as_ref().map(|checkpoint| Checkpointing {
+                path: path(checkpoint),
+                interval: Duration::from_secs(60),
+                settings_hash: settings_hash(&frame_settings),
+            }),
+        }
+    };
 
     let before = Instant::now();
 
-    let image = render(
-        image_width,
-        image_height,
-        sample_per_pixel,
-        world,
-        Arc::from(cam),
-    );
+    match frames {
+        None => {
+            let shutter = (0.0, 1.0);
+            let cam = make_rig(
+                projection,
+                lens.as_ref(),
+                stereo,
+                &animation.at(0.0),
+                image_width,
+                image_height,
+                shutter,
+            );
+            let progress = progress(None);
+            let resumed = progress
+                .checkpoint
+                .as_ref()
+                .filter(|_| resume)
+                .and_then(|checkpointing| load_checkpoint(checkpointing, SEED));
+            let mut film = Film::new(image_width, image_height, filter, crop)
+                .with_aov_channels(aov_layout.channel_count());
+            if let Some(checkpoint) = resumed {
+                film.restore(checkpoint.pixels, checkpoint.aovs);
+            }
+            let rendered = render(
+                &film,
+                sampling,
+                &progress,
+                paths,
+                mode,
+                integrator.as_ref(),
+                &world,
+                cam,
+                shutter,
+                sampler_kind,
+                SEED,
+                &aov_layout,
+                denoiser.as_ref(),
+            )
+            .unwrap_or_else(|| process::exit(130));
+
+            eprintln!("Done in {}secs!           ", before.elapsed().as_secs());
+            eprintln!("{}", rendered.stats);
+            if let Some(path) = &stats {
+                fs::write(path, format!("{}\n", rendered.stats.to_json()))
+                    .expect("cannot write the render statistics");
+            }
+            if let Some(path) = &heat_map {
+                fs::write(
+                    path,
+                    format!("{}\n", rendered.sample_heat_map.encode(ImageFormat::PPM)),
+                )
+                .expect("cannot write the sample heat map");
+            }
+            if let Some(aovs) = &rendered.aovs {
+                aovs.write_exr(std::path::Path::new(&aovs_path))
+                    .expect("cannot write the AOVs");
+            }
+            if let Some(noisy) = &rendered.noisy {
+                fs::write("noisy.ppm", format!("{}\n", noisy.encode(ImageFormat::PPM)))
+                    .expect("cannot write the noisy image");
+            }
+            println!("{}", rendered.image.encode(ImageFormat::PPM));
+        }
+        Some(frames) => {
+            fs::create_dir_all("frames").expect("cannot create the frames directory");
+            for frame in frames {
+                let path = format!("frames/frame_{:04}.ppm", frame);
+                if resume && std::path::Path::new(&path).exists() {
+                    continue;
+                }
 
-    eprintln!("Done in {}secs!           ", before.elapsed().as_secs());
+                let open = f64::from(frame) / FRAME_RATE;
+                let shutter = (open, open + SHUTTER / FRAME_RATE);
+                // The camera moves while the shutter is open
+                let cam = Arc::new(AnimatedCamera::new(
+                    &animation,
+                    shutter,
+                    |settings, time| {
+                        make_rig(
+                            projection,
+                            lens.as_ref(),
+                            stereo,
+                            settings,
+                            image_width,
+                            image_height,
+                            (time, time),
+                        )
+                    },
+                ));
+                // A new noise pattern each frame
+                let seed = SEED.wrapping_add(u64::from(frame));
+                let progress = progress(Some(frame));
+                let resumed = progress
+                    .checkpoint
+                    .as_ref()
+                    .filter(|_| resume)
+                    .and_then(|checkpointing| load_checkpoint(checkpointing, seed));
+                let mut film = Film::new(image_width, image_height, filter, crop)
+                    .with_aov_channels(aov_layout.channel_count());
+                if let Some(checkpoint) = resumed {
+                    film.restore(checkpoint.pixels, checkpoint.aovs);
+                }
+                let rendered = render(
+                    &film,
+                    sampling,
+                    &progress,
+                    paths,
+                    mode,
+                    integrator.as_ref(),
+                    &world,
+                    cam,
+                    shutter,
+                    sampler_kind,
+                    seed,
+                    &aov_layout,
+                    denoiser.as_ref(),
+                )
+                .unwrap_or_else(|| process::exit(130));
+
+                fs::write(
+                    &path,
+                    format!("{}\n", rendered.image.encode(ImageFormat::PPM)),
+                )
+                .expect("cannot write the frame");
+                if let Some(path) = &stats {
+                    fs::write(
+                        frame_path(path, frame),
+                        format!("{}\n", rendered.stats.to_json()),
+                    )
+                    .expect("cannot write the render statistics");
+                }
+                if let Some(path) = &heat_map {
+                    fs::write(
+                        frame_path(path, frame),
+                        format!("{}\n", rendered.sample_heat_map.encode(ImageFormat::PPM)),
+                    )
+                    .expect("cannot write the sample heat map");
+                }
+                if let Some(aovs) = &rendered.aovs {
+                    aovs.write_exr(std::path::Path::new(&frame_path(&aovs_path, frame)))
+                        .expect("cannot write the AOVs");
+                }
+                if let Some(noisy) = &rendered.noisy {
+                    fs::write(
+                        format!("frames/noisy_{:04}.ppm", frame),
+                        format!("{}\n", noisy.encode(ImageFormat::PPM)),
+                    )
+                    .expect("cannot write the noisy image");
+                }
+                eprintln!("Frame {} written to {}       ", frame, path);
+            }
 
-    println!("{}", image.encode(ImageFormat::PPM));
+            eprintln!("Done in {}secs!           ", before.elapsed().as_secs());
+        }
+    }
 }
@@ -1,13 +1,121 @@
 use super::reflect;
 use super::Material;
 use crate::hitables::HitRecord;
+use crate::samplers::Sampler;
 use crate::structs::{dot, unit_vector, Ray, Vec3};
 
-use rand::Rng;
+/// Variation of the refractive index with the wavelength
+#[derive(Debug, Copy, Clone)]
+pub enum Dispersion {
+    None,
+    /// n = a + b / λ², λ in micrometers
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// n² = 1 + Σ b λ² / (λ² - c), λ in micrometers
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+/// Wavelength of the sodium D line (nm), where refractive indices are usually given
+const REFERENCE_WAVELENGTH: f64 = 589.3;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Dielectric {
+    /// Refractive index used when rays don't carry a wavelength
     pub ref_idx: f64,
+    pub dispersion: Dispersion,
+}
+
+impl Dielectric {
+    pub fn new(ref_idx: f64) -> Dielectric {
+        Dielectric {
+            ref_idx,
+            dispersion: Dispersion::None,
+        }
+    }
+
+    pub fn cauchy(a: f64, b: f64) -> Dielectric {
+        Dielectric::dispersive(Dispersion::Cauchy { a, b })
+    }
+
+    pub fn sellmeier(b: [f64; 3], c: [f64; 3]) -> Dielectric {
+        Dielectric::dispersive(Dispersion::Sellmeier { b, c })
+    }
+
+    /// Common crown glass (Schott N-BK7)
+    pub fn crown_glass() -> Dielectric {
+        Dielectric::sellmeier(
+            [1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653],
+        )
+    }
+
+    /// Dense flint glass (Schott SF11), strongly dispersive
+    pub fn flint_glass() -> Dielectric {
+        Dielectric::sellmeier(
+            [1.73759695, 0.313747346, 1.89878101],
+            [0.013188707, 0.0623068142, 155.23629],
+        )
+    }
+
+    pub fn diamond() -> Dielectric {
+        Dielectric::sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+    }
+
+    fn dispersive(dispersion: Dispersion) -> Dielectric {
+        let mut dielectric = Dielectric {
+            ref_idx: 1.0,
+            dispersion,
+        };
+        dielectric.ref_idx = dielectric.ref_idx_at(REFERENCE_WAVELENGTH);
+        dielectric
+    }
+
+    /// Refractive index at the given wavelength (nm)
+    pub fn ref_idx_at(&self, wavelength: f64) -> f64 {
+        let l = wavelength / 1000.0;
+        let l2 = l * l;
+        match self.dispersion {
+            Dispersion::None => self.ref_idx,
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let mut n2 = 1.0;
+                for i in 0..3 {
+                    n2 += b[i] * l2 / (l2 - c[i]);
+                }
+                n2.sqrt()
+            }
+        }
+    }
+
+    /// Ratio of the refractive indices on both sides of the surface, at the ray wavelength
+    fn etai_over_etat(&self, ray: &Ray, rec: &HitRecord) -> f64 {
+        let ref_idx = match ray.wavelength() {
+            Some(wavelength) => self.ref_idx_at(wavelength),
+            None => self.ref_idx,
+        };
+        if rec.front_face {
+            1.0 / ref_idx
+        } else {
+            ref_idx
+        }
+    }
+
+    /// Fraction of the light of the ray the surface reflects, one beyond the critical angle
+    pub fn reflectance(&self, ray: &Ray, rec: &HitRecord) -> f64 {
+        let etai_over_etat = self.etai_over_etat(ray, rec);
+        let cos_theta = dot(-unit_vector(ray.direction()), rec.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        if etai_over_etat * sin_theta > 1.0 {
+            1.0
+        } else {
+            schlick(cos_theta, etai_over_etat)
+        }
+    }
 }
 
 fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
@@ -25,14 +133,15 @@ fn schlick(cosine: f64, ref_idx: f64) -> f64 {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
         let attenuation = Vec3::new(1.0, 1.0, 1.0);
 
-        let etai_over_etat = if rec.front_face {
-            1.0 / self.ref_idx
-        } else {
-            self.ref_idx
-        };
+        let etai_over_etat = self.etai_over_etat(ray, rec);
 
         let unit_direction = unit_vector(ray.direction());
 
@@ -47,7 +156,7 @@ impl Material for Dielectric {
         }
         // Can refract
         let reflect_prob = schlick(cos_theta, etai_over_etat);
-        if rand::thread_rng().gen::<f64>() < reflect_prob {
+        if sampler.get_1d() < reflect_prob {
             let reflected = reflect(&unit_direction, &rec.normal);
             let scattered = Ray::new(rec.p, reflected, ray.time());
             return Some((attenuation, scattered));
@@ -57,4 +166,13 @@ impl Material for Dielectric {
         let scattered = Ray::new(rec.p, refracted, ray.time());
         Some((attenuation, scattered))
     }
+
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        // Clear glass lets all the light through
+        Vec3::new(1.0, 1.0, 1.0)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        !matches!(self.dispersion, Dispersion::None)
+    }
 }
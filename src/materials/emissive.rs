@@ -1,6 +1,7 @@
 use super::Material;
 
 use crate::hitables::HitRecord;
+use crate::samplers::Sampler;
 use crate::structs::{Ray, Texture, Vec3};
 
 use std::sync::Arc;
@@ -11,7 +12,12 @@ pub struct Emissive {
 }
 
 impl Material for Emissive {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
         None
     }
 
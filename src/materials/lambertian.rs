@@ -1,8 +1,10 @@
 use super::random_unit_vector;
 use super::Material;
 use crate::hitables::HitRecord;
-use crate::structs::{Ray, Texture, Vec3};
+use crate::samplers::Sampler;
+use crate::structs::{dot, Ray, Texture, Vec3};
 
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -11,10 +13,28 @@ pub struct Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
         // Different diffuse distributions possibles, see materials/mod.rs
-        let scatter_direction = rec.normal + random_unit_vector(); // TODO pre-gen for better performances
+        let scatter_direction = rec.normal + random_unit_vector(sampler);
         let scattered = Ray::new(rec.p, scatter_direction, ray.time());
         Some((self.albedo.value(rec.u, rec.v, &rec.p), scattered))
     }
+
+    fn eval(&self, _ray: &Ray, rec: &HitRecord, direction: &Vec3) -> Vec3 {
+        let cosine = dot(rec.normal, *direction).max(0.0);
+        self.albedo.value(rec.u, rec.v, &rec.p) * (cosine / PI)
+    }
+
+    fn pdf(&self, _ray: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        dot(rec.normal, *direction).max(0.0) / PI
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p)
+    }
 }
@@ -1,13 +1,41 @@
 use crate::hitables::HitRecord;
+use crate::samplers::Sampler;
 use crate::structs::{Ray, Vec3};
 
 pub trait Material: Send + Sync {
     /// Returns a tuple composed of attenuation on RGB channels and the scattered ray, if applicable
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)>;
+    fn scatter(&self, ray: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler)
+        -> Option<(Vec3, Ray)>;
+
+    /// Return the BSDF times the cosine term for light coming from direction
+    /// (unit vector) and leaving toward the ray origin.
+    /// Used to gather light sources, default implementation is black,
+    /// which fits perfectly specular materials.
+    fn eval(&self, _ray: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Vec3 {
+        Vec3::default()
+    }
+
+    /// Return the solid angle density with which scatter chooses direction (unit vector).
+    /// Zero for materials scattering in discrete directions.
+    fn pdf(&self, _ray: &Ray, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        0.0
+    }
 
     /// Return the emissive color
     /// Default implementation return no emissive color (black)
     fn emit(&self, _u: f64, _b: f64, _p: &Vec3) -> Vec3 {
         Vec3::default()
     }
+
+    /// Fraction of light the surface scatters, regardless of direction.
+    /// Written to the albedo AOV, black by default.
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::default()
+    }
+
+    /// Return true if the scattered direction depends on the ray wavelength.
+    /// Spectral paths then only keep their hero wavelength.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
@@ -1,8 +1,11 @@
 use super::Material;
 use super::{random_in_unit_sphere, reflect};
 use crate::hitables::HitRecord;
+use crate::samplers::Sampler;
 use crate::structs::{dot, unit_vector, Ray, Vec3};
 
+use std::f64::consts::PI;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Metal {
     pub albedo: Vec3,
@@ -13,17 +16,40 @@ impl Metal {
     pub fn new(albedo: Vec3, fuzz: f64) -> Metal {
         Metal {
             albedo,
-            fuzziness: fuzz.max(0.0).min(1.0),
+            fuzziness: fuzz.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Density of the directions scatter draws before the ones below the surface
+    /// are absorbed: a point uniform in a ball of radius fuzziness around the reflected direction
+    fn lobe_pdf(&self, reflected: &Vec3, direction: &Vec3) -> f64 {
+        // The segment of the line along direction inside the ball
+        let b = dot(*direction, *reflected);
+        let discriminant = b * b - (1.0 - self.fuzziness * self.fuzziness);
+        if discriminant < 0.0 {
+            return 0.0;
+        }
+        let far = b + discriminant.sqrt();
+        if far <= 0.0 {
+            return 0.0;
         }
+        let near = (b - discriminant.sqrt()).max(0.0);
+        // Volume of the cone of directions over the volume of the ball
+        (far.powi(3) - near.powi(3)) / (4.0 * PI * self.fuzziness.powi(3))
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
         let reflected: Vec3 = reflect(&unit_vector(ray.direction()), &rec.normal);
         let scattered = Ray::new(
             rec.p,
-            reflected + self.fuzziness * random_in_unit_sphere(), // TODO pre-gen for better performances
+            reflected + self.fuzziness * random_in_unit_sphere(sampler),
             ray.time(),
         );
         let attenuation = self.albedo;
@@ -33,4 +59,22 @@ impl Material for Metal {
             None
         }
     }
+
+    fn eval(&self, ray: &Ray, rec: &HitRecord, direction: &Vec3) -> Vec3 {
+        // Every direction scatter keeps is weighted by the albedo alone
+        self.albedo * self.pdf(ray, rec, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        // A smooth metal reflects in a discrete direction
+        if self.fuzziness == 0.0 || dot(*direction, rec.normal) <= 0.0 {
+            return 0.0;
+        }
+        let reflected = reflect(&unit_vector(ray.direction()), &rec.normal);
+        self.lobe_pdf(&reflected, direction)
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        self.albedo
+    }
 }
@@ -5,13 +5,15 @@ pub use lambertian::Lambertian;
 mod metal;
 pub use metal::Metal;
 mod dielectric;
-pub use dielectric::Dielectric;
+pub use dielectric::{Dielectric, Dispersion};
 mod emissive;
 pub use emissive::Emissive;
+mod subsurface;
+pub use subsurface::Subsurface;
 
-use rand::Rng;
 use std::f64::consts::PI;
 
+use crate::samplers::Sampler;
 use crate::structs::{dot, Vec3};
 
 // Common material functions
@@ -23,17 +25,17 @@ fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
 // Distributions
 
 /// Distribution for Lambertian Approximation
-pub fn random_unit_vector() -> Vec3 {
-    let mut rng = rand::thread_rng();
-    let a: f64 = rng.gen_range(0.0, 2.0 * PI);
-    let z: f64 = rng.gen_range(-1.0, 1.0);
+pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let a = 2.0 * PI * u;
+    let z = 2.0 * v - 1.0;
     let r = (1.0 - z * z).sqrt();
     Vec3::new(r * a.cos(), r * a.sin(), z)
 }
 
 /// More intuitive distribution
-pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
-    let in_unit_sphere = random_in_unit_sphere();
+pub fn random_in_hemisphere(normal: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+    let in_unit_sphere = random_in_unit_sphere(sampler);
     if dot(in_unit_sphere, normal) > 0.0 {
         in_unit_sphere
     } else {
@@ -41,17 +43,8 @@ pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
     }
 }
 
-pub fn random_in_unit_sphere() -> Vec3 {
-    let mut rng = rand::thread_rng();
-    loop {
-        let p: Vec3 = Vec3::new(
-            rng.gen_range(-1.0, 1.0),
-            rng.gen_range(-1.0, 1.0),
-            rng.gen_range(-1.0, 1.0),
-        );
-        if p.squared_length() >= 1.0 {
-            continue;
-        }
-        return p;
-    }
+/// Uniform point in the unit sphere, drawn from three dimensions
+pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vec3 {
+    let direction = random_unit_vector(sampler);
+    direction * sampler.get_1d().cbrt()
 }
@@ -0,0 +1,141 @@
+use super::{random_unit_vector, reflect};
+use super::{Dielectric, Material};
+use crate::hitables::HitRecord;
+use crate::samplers::Sampler;
+use crate::structs::{dot, unit_vector, Ray, Vec3};
+
+use std::f64::consts::PI;
+
+/// Translucent material scattering light inside a closed surface
+/// (skin, wax, marble...).
+/// The boundary behaves like a `Dielectric`, and inside the volume
+/// rays follow a random walk driven by the scattering and absorption
+/// coefficients of each RGB channel.
+/// Rays that aren't reflected back inside leave the medium in a diffuse lobe,
+/// so the lights can be sampled where the walk ends.
+#[derive(Debug, Copy, Clone)]
+pub struct Subsurface {
+    pub boundary: Dielectric,
+    sigma_s: Vec3,
+    sigma_t: Vec3,
+}
+
+impl Subsurface {
+    /// Create a medium from its scattering and absorption coefficients (per unit length)
+    pub fn new(ref_idx: f64, sigma_s: Vec3, sigma_a: Vec3) -> Subsurface {
+        Subsurface {
+            boundary: Dielectric::new(ref_idx),
+            sigma_s,
+            sigma_t: sigma_s + sigma_a,
+        }
+    }
+
+    /// Create a medium from its single scattering albedo
+    /// and the mean distance travelled between two interactions
+    pub fn from_albedo(ref_idx: f64, albedo: Vec3, mean_free_path: Vec3) -> Subsurface {
+        let sigma_t = Vec3::new(
+            1.0 / mean_free_path.x,
+            1.0 / mean_free_path.y,
+            1.0 / mean_free_path.z,
+        );
+        Subsurface {
+            boundary: Dielectric::new(ref_idx),
+            sigma_s: albedo * sigma_t,
+            sigma_t,
+        }
+    }
+}
+
+fn transmittance(sigma_t: &Vec3, distance: f64) -> Vec3 {
+    Vec3::new(
+        (-sigma_t.x * distance).exp(),
+        (-sigma_t.y * distance).exp(),
+        (-sigma_t.z * distance).exp(),
+    )
+}
+
+fn average(v: &Vec3) -> f64 {
+    (v.x + v.y + v.z) / 3.0
+}
+
+impl Subsurface {
+    /// Transmittance of the walk from the ray origin to the boundary hit by rec
+    fn transmittance_to_boundary(&self, ray: &Ray, rec: &HitRecord) -> Vec3 {
+        transmittance(&self.sigma_t, rec.t * ray.direction().length())
+    }
+
+    /// Density of the directions leaving the medium, for a ray that reached the boundary
+    fn exit_pdf(&self, ray: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        // The normal of a hit from inside points inside
+        let cosine = -dot(rec.normal, *direction);
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - self.boundary.reflectance(ray, rec)) * cosine / PI
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
+        if rec.front_face {
+            // Entering (or reflecting on) the surface
+            return self.boundary.scatter(ray, rec, sampler);
+        }
+
+        // The ray travelled inside the medium up to the boundary,
+        // sample a free flight distance on a randomly chosen channel
+        let distance = rec.t * ray.direction().length();
+        let channel = ((sampler.get_1d() * 3.0) as usize).min(2);
+        let flight = -(1.0 - sampler.get_1d()).ln() / self.sigma_t[channel];
+
+        if flight < distance {
+            // Scattering event inside the volume, isotropic phase function
+            let tr = transmittance(&self.sigma_t, flight);
+            let pdf = average(&(self.sigma_t * tr));
+            let origin = ray.origin() + (flight / distance * rec.t) * ray.direction();
+            let scattered = Ray::new(origin, random_unit_vector(sampler), ray.time());
+            return Some((self.sigma_s * tr / pdf, scattered));
+        }
+
+        // Reached the boundary, reflect back inside or leave the medium
+        let tr = transmittance(&self.sigma_t, distance);
+        let weight = tr / average(&tr);
+        let direction = if sampler.get_1d() < self.boundary.reflectance(ray, rec) {
+            reflect(&unit_vector(ray.direction()), &rec.normal)
+        } else {
+            random_unit_vector(sampler) - rec.normal
+        };
+        Some((weight, Ray::new(rec.p, direction, ray.time())))
+    }
+
+    /// Light can only reach the walk where it leaves the medium,
+    /// the entry through the boundary is specular
+    fn eval(&self, ray: &Ray, rec: &HitRecord, direction: &Vec3) -> Vec3 {
+        if rec.front_face {
+            return Vec3::default();
+        }
+        self.transmittance_to_boundary(ray, rec) * self.exit_pdf(ray, rec, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, rec: &HitRecord, direction: &Vec3) -> f64 {
+        if rec.front_face {
+            return 0.0;
+        }
+        // Scatter reaches the boundary with the average transmittance of the channels
+        average(&self.transmittance_to_boundary(ray, rec)) * self.exit_pdf(ray, rec, direction)
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        // Single scattering albedo of the medium
+        self.sigma_s / self.sigma_t
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.boundary.is_dispersive()
+    }
+}
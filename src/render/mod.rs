@@ -0,0 +1,8 @@
+mod scene;
+pub use scene::*;
+mod paths;
+pub use paths::*;
+mod sampling;
+pub use sampling::*;
+mod renderer;
+pub use renderer::*;
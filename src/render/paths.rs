@@ -0,0 +1,134 @@
+use crate::hitables::HitRecord;
+use crate::samplers::{roulette_dimension, Sampler};
+use crate::structs::{count, dot, Counter, Ray};
+
+/// Kind of a bounce, each one has its own limit
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BounceKind {
+    /// Scattered by a material sampling a density of directions, fuzzy metals included
+    Diffuse,
+    /// Reflected in a discrete direction
+    Specular,
+    /// Refracted through a surface, or scattered inside an object
+    Transmission,
+}
+
+impl BounceKind {
+    /// Kind of the bounce from rec toward scattered, pdf being the density of scattered
+    pub fn of(rec: &HitRecord, scattered: &Ray, pdf: Option<f64>) -> BounceKind {
+        if !rec.front_face || dot(scattered.direction(), rec.normal) < 0.0 {
+            BounceKind::Transmission
+        } else if pdf.is_some() {
+            BounceKind::Diffuse
+        } else {
+            BounceKind::Specular
+        }
+    }
+}
+
+/// Bounces of each kind taken by a path
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Bounces {
+    pub diffuse: u32,
+    pub specular: u32,
+    pub transmission: u32,
+}
+
+impl Bounces {
+    pub fn total(&self) -> u32 {
+        self.diffuse + self.specular + self.transmission
+    }
+
+    /// Bounces after one more of the given kind
+    pub fn after(mut self, kind: BounceKind) -> Bounces {
+        match kind {
+            BounceKind::Diffuse => self.diffuse += 1,
+            BounceKind::Specular => self.specular += 1,
+            BounceKind::Transmission => self.transmission += 1,
+        }
+        self
+    }
+}
+
+/// How long paths go on, and what they may bring back
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PathSettings {
+    /// Rays traced along a path at most, the camera ray included
+    pub max_depth: u32,
+    /// Bounces of each kind allowed
+    pub max_diffuse: u32,
+    pub max_specular: u32,
+    pub max_transmission: u32,
+    /// Bounces after which Russian roulette may end a path, never when None.
+    /// Paths survive with a probability given by their throughput and are weighted up,
+    /// so dim paths stop early without biasing the image.
+    pub roulette_depth: Option<u32>,
+    /// Largest luminance a light reached after a bounce adds to a sample, unlimited when None.
+    /// Removes fireflies at the cost of some energy.
+    pub clamp_indirect: Option<f64>,
+}
+
+impl PathSettings {
+    /// Paths of max_depth rays whatever their bounces, Russian roulette after 3 bounces
+    pub fn new(max_depth: u32) -> PathSettings {
+        PathSettings {
+            max_depth,
+            max_diffuse: max_depth,
+            max_specular: max_depth,
+            max_transmission: max_depth,
+            roulette_depth: Some(3),
+            clamp_indirect: None,
+        }
+    }
+
+    fn allows(&self, bounces: &Bounces) -> bool {
+        bounces.total() < self.max_depth
+            && bounces.diffuse <= self.max_diffuse
+            && bounces.specular <= self.max_specular
+            && bounces.transmission <= self.max_transmission
+    }
+
+    /// Probability with which a path that just took its last bounce goes on, None when it stops.
+    /// max_throughput is the largest channel of its throughput, bounce included.
+    pub(crate) fn survival(
+        &self,
+        bounces: &Bounces,
+        max_throughput: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<f64> {
+        if !self.allows(bounces) {
+            return None;
+        }
+        match self.roulette_depth {
+            Some(depth) if bounces.total() > depth => {
+                let probability = max_throughput.min(1.0);
+                sampler.set_dimension(roulette_dimension(bounces.total() - 1));
+                if sampler.get_1d() < probability {
+                    Some(probability)
+                } else {
+                    count(Counter::RouletteTerminations);
+                    None
+                }
+            }
+            _ => Some(1.0),
+        }
+    }
+
+    /// Factor keeping a light found after a bounce under the clamp,
+    /// luminance being what it adds to the sample
+    pub(crate) fn clamp_factor(&self, bounces: &Bounces, luminance: f64) -> f64 {
+        match self.clamp_indirect {
+            Some(max) if bounces.total() > 0 && luminance > max => max / luminance,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Quantity carried by the paths
+#[derive(Debug, Copy, Clone)]
+pub enum ColorMode {
+    /// RGB triplets, fast
+    Rgb,
+    /// Sampled wavelengths, needed to show dispersion
+    Spectral,
+}
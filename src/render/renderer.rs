@@ -0,0 +1,419 @@
+use super::{ColorMode, PathSettings, Sampling, Scene};
+use crate::cameras::Camera;
+use crate::hitables::{Hitable, HitableList};
+use crate::integrators::Integrator;
+use crate::samplers::{Sampler, SamplerKind, CAMERA_DIMENSION, WAVELENGTH_DIMENSION};
+use crate::scheduler::{for_each_stealing, tiles, Tile, TileOrder};
+use crate::structs::*;
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Outputs of a render
+pub struct Rendered {
+    pub image: Image,
+    /// Image before denoising, None when not denoised
+    pub noisy: Option<Image>,
+    /// Samples taken per pixel, relative to the maximum
+    pub sample_heat_map: Image,
+    /// Linear beauty and the selected AOVs, None when none is selected
+    pub aovs: Option<AovImage>,
+    pub stats: RenderStats,
+}
+
+/// How a progressive render goes on and what it shows on the way
+pub struct Progress {
+    /// Wall-clock budget, pixels stop taking samples once it runs out
+    pub time_budget: Option<Duration>,
+    /// Samples added to each pixel by a pass over the image
+    pub samples_per_pass: u32,
+    pub thread_count: usize,
+    /// Side of the square tiles threads render at once, in pixels
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    /// Path the current estimate is written to, and how often
+    pub snapshot: Option<(String, Duration)>,
+    /// Where the render saves its progress, also done on SIGINT
+    pub checkpoint: Option<Checkpointing>,
+}
+
+/// Periodic checkpoints of a render
+pub struct Checkpointing {
+    pub path: String,
+    pub interval: Duration,
+    /// Identifies the settings the checkpoint can be resumed with
+    pub settings_hash: u64,
+}
+
+/// Set by the first SIGINT, renders then stop after saving a checkpoint
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Trace one sample through the point (u, v) of the image and return the light it brings.
+/// The AOV channels of the sample are written to channels.
+#[allow(clippy::too_many_arguments)]
+fn trace_sample(
+    u: f64,
+    v: f64,
+    mode: ColorMode,
+    integrator: &dyn Integrator,
+    camera: &dyn Camera,
+    scene: &Scene,
+    sampler: &mut dyn Sampler,
+    layout: &AovLayout,
+    channels: &mut [f64],
+) -> Vec3 {
+    for value in channels.iter_mut() {
+        *value = 0.0;
+    }
+    sampler.set_dimension(CAMERA_DIMENSION);
+    let (r, weight) = match camera.sample_ray(u, v, sampler) {
+        Some(sample) => sample,
+        None => return Vec3::default(),
+    };
+    count(Counter::CameraRays);
+
+    let aovs = if channels.is_empty() {
+        None
+    } else {
+        Some((layout, &mut channels[..]))
+    };
+    let col = match mode {
+        ColorMode::Rgb => integrator.li(&r, scene, sampler, aovs),
+        ColorMode::Spectral => {
+            sampler.set_dimension(WAVELENGTH_DIMENSION);
+            let mut wavelengths = SampledWavelengths::sample(sampler.get_1d());
+            let r = r.with_wavelength(wavelengths.hero());
+            let radiance = integrator.spectral_li(&r, &mut wavelengths, scene, sampler, aovs);
+            wavelengths.to_rgb(&radiance)
+        }
+    };
+    layout.scale_lighting(channels, weight);
+    weight * col
+}
+
+/// Object and material IDs seen through the first sample of each pixel of the crop, top row first.
+/// Materials are numbered in the order they appear.
+fn id_passes(
+    film: &Film,
+    camera: &dyn Camera,
+    scene: &Scene,
+    sampler: &mut dyn Sampler,
+) -> (Vec<f32>, Vec<f32>) {
+    let (image_width, image_height) = (film.full_width(), film.full_height());
+    let crop = film.crop();
+    let mut object_ids = Vec::with_capacity(crop.pixel_count());
+    let mut material_ids = Vec::with_capacity(crop.pixel_count());
+    let mut materials: Vec<*const u8> = vec![];
+    for y in crop.y0..crop.y1 {
+        let j = image_height - 1 - y;
+        for i in crop.x0..crop.x1 {
+            // Same camera ray as the first sample of the pixel
+            sampler.start_pixel_sample(i, j, 0);
+            let (du, dv) = sampler.get_2d();
+            sampler.set_dimension(CAMERA_DIMENSION);
+            let u = (f64::from(i) + du) / f64::from(image_width);
+            let v = 1.0 - (f64::from(y) + 1.0 - dv) / f64::from(image_height);
+            let rec = camera
+                .sample_ray(u, v, sampler)
+                .and_then(|(r, _)| scene.world.hit(&r, 0.001, f64::MAX));
+            match rec {
+                Some(rec) => {
+                    let material = Arc::as_ptr(&rec.material) as *const u8;
+                    let index = match materials.iter().position(|m| *m == material) {
+                        Some(index) => index,
+                        None => {
+                            materials.push(material);
+                            materials.len() - 1
+                        }
+                    };
+                    object_ids.push(rec.object_id as f32);
+                    material_ids.push((index + 1) as f32);
+                }
+                None => {
+                    object_ids.push(0.0);
+                    material_ids.push(0.0);
+                }
+            }
+        }
+    }
+    (object_ids, material_ids)
+}
+
+/// Samples taken by every pixel relative to max_samples, top row first
+fn heat_map_image(
+    image_width: u32,
+    image_height: u32,
+    pixels: &[FilmPixel],
+    max_samples: u32,
+) -> Image {
+    let mut buffer = Vec::with_capacity(pixels.len() * 3);
+    for pixel in pixels {
+        let heat = heat_color(f64::from(pixel.stats.count()) / f64::from(max_samples.max(1)));
+        buffer.push(heat.x);
+        buffer.push(heat.y);
+        buffer.push(heat.z);
+    }
+    Image::from(image_width, image_height, PixelFormat::RGBU8, &buffer)
+}
+
+/// Dispatch ray-tracing algorithm on several threads to create an image of the current scene.
+/// Passes over the crop of the film refine the estimate until every pixel has converged
+/// or the time budget runs out.
+/// Returns None when interrupted, after saving a checkpoint the render can resume from.
+/// The denoiser needs the albedo, normal and depth AOVs.
+///
+/// Threads splat into a buffer of their own tile, reaching as far as the filter does, rather
+/// than into the shared film: the splats of neighbour tiles overlap, and floating point sums
+/// depend on the order they are added in. The buffers of a pass are merged in tile order once
+/// every tile is done, so a seed always renders the same image whatever the number of threads
+/// and the order they finished in. This costs a buffer per tile of the pass, about the size
+/// of the film itself, held until the end of the pass.
+#[allow(clippy::too_many_arguments)]
+pub fn render(
+    film: &Film,
+    sampling: Sampling,
+    progress: &Progress,
+    paths: PathSettings,
+    mode: ColorMode,
+    integrator: &dyn Integrator,
+    world: &HitableList,
+    camera: Arc<dyn Camera>,
+    shutter: (f64, f64),
+    sampler_kind: SamplerKind,
+    seed: u64,
+    aovs: &AovLayout,
+    denoiser: Option<&Denoiser>,
+) -> Option<Rendered> {
+    let start = Instant::now();
+    let deadline = progress.time_budget.map(|budget| start + budget);
+    let max_samples = sampling.max_samples();
+    let (image_width, image_height) = (film.full_width(), film.full_height());
+    let crop = film.crop();
+
+    // Compute a BVH of the scene
+    let scene = Scene::new(world, shutter, paths);
+    let build_time = start.elapsed();
+    // Counters of each thread, summed over the passes
+    let thread_counters = Mutex::new(vec![RayCounters::default(); progress.thread_count.max(1)]);
+
+    // Tiles with pixels still taking samples
+    let mut pending: Vec<Tile> = tiles(
+        crop.width(),
+        crop.height(),
+        progress.tile_size,
+        progress.tile_order,
+    )
+    .into_iter()
+    .map(|tile| Tile {
+        x0: tile.x0 + crop.x0,
+        y0: tile.y0 + crop.y0,
+        x1: tile.x1 + crop.x0,
+        y1: tile.y1 + crop.y0,
+    })
+    .collect();
+    let mut last_snapshot = start;
+    let mut last_checkpoint = start;
+    let mut pass = 0;
+    // Whether the time budget ended the render before the pixels were done
+    let out_of_time = loop {
+        let tile_count = pending.len();
+        let tiles_done = AtomicUsize::new(0);
+        let threads_started = AtomicUsize::new(0);
+        // Tiles rendered by the pass, by index in pending
+        let rendered: Mutex<Vec<Option<FilmTile>>> =
+            Mutex::new((0..tile_count).map(|_| None).collect());
+        for_each_stealing(
+            pending.iter().copied().enumerate().collect(),
+            progress.thread_count,
+            || {
+                (
+                    sampler_kind.create(seed, max_samples),
+                    vec![0.0; film.aov_channels()],
+                    threads_started.fetch_add(1, Ordering::Relaxed),
+                )
+            },
+            |(sampler, channels, thread), (index, tile)| {
+                let sampler = sampler.as_mut();
+                let mut film_tile = film.tile(&tile);
+                for y in tile.y0..tile.y1 {
+                    if INTERRUPTED.load(Ordering::Relaxed)
+                        || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                    {
+                        break;
+                    }
+                    // Film rows go down, pixel rows go up
+                    let j = image_height - 1 - y;
+                    for i in tile.x0..tile.x1 {
+                        for _ in 0..progress.samples_per_pass {
+                            let stats = film_tile.stats(i, y);
+                            if sampling.converged(stats) {
+                                break;
+                            }
+                            // Numbers only depend on the pixel and the sample index,
+                            // not on the thread, the tile or the pass rendering them
+                            sampler.start_pixel_sample(i, j, stats.count());
+                            let (du, dv) = sampler.get_2d();
+                            let film_x = f64::from(i) + du;
+                            let film_y = f64::from(y) + 1.0 - dv;
+                            let col = trace_sample(
+                                film_x / f64::from(image_width),
+                                1.0 - film_y / f64::from(image_height),
+                                mode,
+                                integrator,
+                                camera.as_ref(),
+                                &scene,
+                                sampler,
+                                aovs,
+                                channels,
+                            );
+                            film_tile.add_sample(i, y, film_x, film_y, col, channels);
+                        }
+                    }
+                }
+                rendered.lock().unwrap()[index] = Some(film_tile);
+                thread_counters.lock().unwrap()[*thread] += take_counters();
+
+                let done = tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+                eprint!("Pass {}: {}/{} tiles    \r", pass + 1, done, tile_count);
+            },
+        );
+        pass += 1;
+        // Splats of neighbour tiles overlap, adding them in a fixed order
+        // keeps the sums the same whatever the threads and the order they finished in
+        for film_tile in rendered.into_inner().unwrap().into_iter().flatten() {
+            film.merge_tile(film_tile);
+        }
+
+        let pixels = film.pixels();
+        pending.retain(|tile| {
+            (tile.y0..tile.y1).any(|y| {
+                (tile.x0..tile.x1).any(|x| {
+                    let index = (y - crop.y0) * crop.width() + x - crop.x0;
+                    !sampling.converged(&pixels[index as usize].stats)
+                })
+            })
+        });
+        let samples: u64 = pixels.iter().map(|p| u64::from(p.stats.count())).sum();
+        eprint!(
+            "Pass {}: {:.1} spp on average after {}secs    \r",
+            pass,
+            samples as f64 / pixels.len() as f64,
+            start.elapsed().as_secs()
+        );
+
+        let interrupted = INTERRUPTED.load(Ordering::Relaxed);
+        if let Some(checkpointing) = &progress.checkpoint {
+            if interrupted || last_checkpoint.elapsed() >= checkpointing.interval {
+                let checkpoint = Checkpoint {
+                    settings_hash: checkpointing.settings_hash,
+                    width: crop.width(),
+                    height: crop.height(),
+                    seed,
+                    pixels: pixels.clone(),
+                    aovs: film.aovs(),
+                };
+                checkpoint
+                    .write(std::path::Path::new(&checkpointing.path))
+                    .expect("cannot write the checkpoint");
+                last_checkpoint = Instant::now();
+                if interrupted {
+                    eprintln!(
+                        "\nInterrupted, run again with --resume to continue from {}",
+                        checkpointing.path
+                    );
+                }
+            }
+        }
+        if interrupted {
+            return None;
+        }
+
+        let converged = pending.is_empty();
+        let out_of_time = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if converged || out_of_time {
+            break !converged;
+        }
+
+        if let Some((path, interval)) = &progress.snapshot {
+            if last_snapshot.elapsed() >= *interval {
+                let snapshot = film.image();
+                fs::write(path, format!("{}\n", snapshot.encode(ImageFormat::PPM)))
+                    .expect("cannot write the snapshot");
+                last_snapshot = Instant::now();
+            }
+        }
+    };
+
+    // Nothing left to resume
+    if let Some(checkpointing) = &progress.checkpoint {
+        let _ = fs::remove_file(&checkpointing.path);
+    }
+
+    let pixels = film.pixels();
+    let counts = pixels.iter().map(|p| p.stats.count());
+    let samples: u64 = counts.clone().map(u64::from).sum();
+    if out_of_time {
+        eprintln!(
+            "Time budget of {:.1}secs ran out before every pixel was done    ",
+            progress.time_budget.unwrap_or_default().as_secs_f64()
+        );
+    }
+    eprintln!(
+        "Achieved {} to {} spp, {:.1} on average, in {} passes    ",
+        counts.clone().min().unwrap_or(0),
+        counts.max().unwrap_or(0),
+        samples as f64 / pixels.len() as f64,
+        pass
+    );
+
+    let aov_image = if aovs.is_empty() {
+        None
+    } else {
+        let mut aov_image = film.aov_image(&aovs.channel_names());
+        if aovs.contains(Aov::ObjectId) || aovs.contains(Aov::MaterialId) {
+            let mut sampler = sampler_kind.create(seed, max_samples);
+            let (object_ids, material_ids) =
+                id_passes(film, camera.as_ref(), &scene, sampler.as_mut());
+            if aovs.contains(Aov::ObjectId) {
+                aov_image
+                    .channels
+                    .push(("objectId".to_string(), object_ids));
+            }
+            if aovs.contains(Aov::MaterialId) {
+                aov_image
+                    .channels
+                    .push(("materialId".to_string(), material_ids));
+            }
+        }
+        Some(aov_image)
+    };
+
+    let (image, noisy) = match denoiser {
+        Some(denoiser) => {
+            let variance = pixels.iter().map(|p| p.stats.variance_of_mean()).collect();
+            let guides = aov_image
+                .as_ref()
+                .and_then(|aov_image| DenoiseGuides::from_aovs(aov_image, variance))
+                .expect("denoising needs the albedo, normal and depth AOVs");
+            let denoised = denoiser.denoise(&film.linear_image(), &guides);
+            (denoised.gamma_corrected(), Some(film.image()))
+        }
+        None => (film.image(), None),
+    };
+
+    let stats = RenderStats {
+        threads: thread_counters.into_inner().unwrap(),
+        build_time,
+        render_time: start.elapsed() - build_time,
+    };
+
+    Some(Rendered {
+        image,
+        noisy,
+        sample_heat_map: heat_map_image(crop.width(), crop.height(), &pixels, max_samples),
+        aovs: aov_image,
+        stats,
+    })
+}
@@ -0,0 +1,33 @@
+use crate::structs::RunningStats;
+
+/// How many samples each pixel gets
+#[derive(Debug, Copy, Clone)]
+pub enum Sampling {
+    /// The same count everywhere
+    Uniform(u32),
+    /// At least min and at most max samples, a pixel stops as soon as
+    /// the standard error of its mean drops below threshold times the mean
+    Adaptive { min: u32, max: u32, threshold: f64 },
+}
+
+impl Sampling {
+    pub fn max_samples(&self) -> u32 {
+        match *self {
+            Sampling::Uniform(spp) => spp,
+            Sampling::Adaptive { max, .. } => max,
+        }
+    }
+
+    pub fn converged(&self, stats: &RunningStats) -> bool {
+        match *self {
+            Sampling::Uniform(spp) => stats.count() >= spp,
+            Sampling::Adaptive {
+                min,
+                max,
+                threshold,
+            } => {
+                stats.count() >= max || (stats.count() >= min && stats.relative_error() < threshold)
+            }
+        }
+    }
+}
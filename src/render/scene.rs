@@ -0,0 +1,105 @@
+use super::PathSettings;
+use crate::hitables::{BVHNode, HitRecord, Hitable, HitableList};
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::samplers::{light_dimension, Sampler};
+use crate::structs::{count, unit_vector, Counter, Ray, Vec3};
+
+use std::sync::Arc;
+
+/// What the paths are traced against
+pub struct Scene {
+    pub world: BVHNode,
+    pub background: Arc<dyn Material>,
+    pub lights: Vec<Arc<dyn Light>>,
+    pub environment: Option<Arc<dyn Light>>,
+    pub paths: PathSettings,
+}
+
+/// Weight of a strategy sampling with density pdf_a when pdf_b is the other one's density
+pub(crate) fn power_heuristic(pdf_a: f64, pdf_b: f64) -> f64 {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+/// Density with which the material chose the scattered ray,
+/// None when the scattered direction can't be picked by light sampling
+pub(crate) fn pdf_of_scattered(ray: &Ray, rec: &HitRecord, scattered: &Ray) -> Option<f64> {
+    let pdf = rec
+        .material
+        .pdf(ray, rec, &unit_vector(scattered.direction()));
+    if pdf > 0.0 {
+        Some(pdf)
+    } else {
+        None
+    }
+}
+
+impl Scene {
+    /// Bounding boxes of moving objects cover the shutter interval
+    pub fn new(world: &HitableList, shutter: (f64, f64), paths: PathSettings) -> Scene {
+        Scene {
+            world: BVHNode::new(&world.with_object_ids(), shutter.0, shutter.1),
+            background: world.background.clone(),
+            lights: world.lights.clone(),
+            environment: world.environment.clone(),
+            paths,
+        }
+    }
+
+    /// Light coming from the background along a ray leaving the scene.
+    /// When the background is also sampled as a light, the contribution is
+    /// weighted against light sampling using the density of the scattered ray.
+    pub fn background(&self, ray: &Ray, scattering_pdf: Option<f64>) -> Vec3 {
+        let emitted = self.background.emit(0.0, 0.0, &ray.direction());
+        match (&self.environment, scattering_pdf) {
+            (Some(environment), Some(pdf)) => {
+                let light_pdf = environment.pdf(&ray.origin(), &ray.direction());
+                emitted * power_heuristic(pdf, light_pdf)
+            }
+            _ => emitted,
+        }
+    }
+
+    /// Trace a shadow ray toward a sample of each light, at the dimensions of the bounce,
+    /// and call gather with the index, BSDF and incoming light of the unoccluded ones
+    pub fn sample_lights<F: FnMut(usize, Vec3, Vec3)>(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        bounce: u32,
+        sampler: &mut dyn Sampler,
+        mut gather: F,
+    ) {
+        for (index, light) in self.lights.iter().enumerate() {
+            sampler.set_dimension(light_dimension(bounce, index));
+            let sample = match light.sample(&rec.p, sampler.get_2d()) {
+                Some(s) => s,
+                None => continue,
+            };
+            let bsdf = rec.material.eval(ray, rec, &sample.direction);
+            if bsdf == Vec3::default() {
+                continue;
+            }
+            let shadow_ray = Ray::new(rec.p, sample.direction, ray.time());
+            count(Counter::ShadowRays);
+            if self
+                .world
+                .hit(&shadow_ray, 0.001, sample.distance - 0.001)
+                .is_none()
+            {
+                let weight = match sample.pdf {
+                    Some(pdf) => {
+                        power_heuristic(pdf, rec.material.pdf(ray, rec, &sample.direction))
+                    }
+                    None => 1.0,
+                };
+                gather(index, bsdf, sample.radiance * weight);
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use super::sobol_sampler::{owen_scramble, sobol_2d, to_unit};
+use super::{hash, shuffled_index, Pcg32, Sampler};
+
+use rand::Rng;
+use std::sync::OnceLock;
+
+/// Side of the tiled blue noise mask
+const MASK_SIZE: usize = 64;
+/// Spread of the energy function used to find clusters and voids
+const MASK_SIGMA: f32 = 1.9;
+const MASK_SEED: u64 = 0xb1e;
+
+/// Toroidal blue noise threshold mask, values evenly spread in [0, 1),
+/// made with the void and cluster method (Ulichney)
+fn blue_noise_mask() -> &'static [f64] {
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    MASK.get_or_init(|| {
+        let n = MASK_SIZE * MASK_SIZE;
+        let ranks = void_and_cluster(n);
+        ranks.iter().map(|r| (*r as f64 + 0.5) / n as f64).collect()
+    })
+}
+
+/// Energy of the pattern, and the pattern itself, updated when a pixel is toggled
+struct Pattern {
+    on: Vec<bool>,
+    energy: Vec<f32>,
+    kernel: Vec<f32>,
+}
+
+impl Pattern {
+    fn new() -> Pattern {
+        let n = MASK_SIZE * MASK_SIZE;
+        let mut kernel = vec![0.0; n];
+        for dy in 0..MASK_SIZE {
+            for dx in 0..MASK_SIZE {
+                let x = dx.min(MASK_SIZE - dx) as f32;
+                let y = dy.min(MASK_SIZE - dy) as f32;
+                kernel[dy * MASK_SIZE + dx] =
+                    (-(x * x + y * y) / (2.0 * MASK_SIGMA * MASK_SIGMA)).exp();
+            }
+        }
+        Pattern {
+            on: vec![false; n],
+            energy: vec![0.0; n],
+            kernel,
+        }
+    }
+
+    fn set(&mut self, index: usize, on: bool) {
+        self.on[index] = on;
+        let sign = if on { 1.0 } else { -1.0 };
+        let (x0, y0) = (index % MASK_SIZE, index / MASK_SIZE);
+        for y in 0..MASK_SIZE {
+            let dy = (y + MASK_SIZE - y0) % MASK_SIZE;
+            for x in 0..MASK_SIZE {
+                let dx = (x + MASK_SIZE - x0) % MASK_SIZE;
+                self.energy[y * MASK_SIZE + x] += sign * self.kernel[dy * MASK_SIZE + dx];
+            }
+        }
+    }
+
+    /// Set pixel with the most set neighbours
+    fn tightest_cluster(&self) -> usize {
+        (0..self.on.len())
+            .filter(|i| self.on[*i])
+            .max_by(|a, b| self.energy[*a].total_cmp(&self.energy[*b]))
+            .unwrap()
+    }
+
+    /// Unset pixel with the fewest set neighbours
+    fn largest_void(&self) -> usize {
+        (0..self.on.len())
+            .filter(|i| !self.on[*i])
+            .min_by(|a, b| self.energy[*a].total_cmp(&self.energy[*b]))
+            .unwrap()
+    }
+}
+
+fn void_and_cluster(n: usize) -> Vec<usize> {
+    // Random initial pattern, then spread evenly
+    let mut rng = Pcg32::new(MASK_SEED);
+    let mut prototype = Pattern::new();
+    let initial = n / 10;
+    let mut count = 0;
+    while count < initial {
+        let i = rng.gen_range(0, n);
+        if !prototype.on[i] {
+            prototype.set(i, true);
+            count += 1;
+        }
+    }
+    loop {
+        let cluster = prototype.tightest_cluster();
+        prototype.set(cluster, false);
+        let void = prototype.largest_void();
+        if void == cluster {
+            prototype.set(cluster, true);
+            break;
+        }
+        prototype.set(void, true);
+    }
+
+    let mut ranks = vec![0; n];
+
+    // Rank the initial pixels, removing clusters first
+    let mut pattern = Pattern {
+        on: prototype.on.clone(),
+        energy: prototype.energy.clone(),
+        kernel: prototype.kernel.clone(),
+    };
+    for rank in (0..initial).rev() {
+        let cluster = pattern.tightest_cluster();
+        pattern.set(cluster, false);
+        ranks[cluster] = rank;
+    }
+
+    // Then fill the voids
+    for rank in initial..n {
+        let void = prototype.largest_void();
+        prototype.set(void, true);
+        ranks[void] = rank;
+    }
+    ranks
+}
+
+/// Sobol points shared by every pixel, offset by a blue noise mask:
+/// the error left in the image is pushed to high frequencies,
+/// which looks smoother at low sample counts.
+pub struct BlueNoiseSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    x: u32,
+    y: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> BlueNoiseSampler {
+        // Build the mask now rather than in the middle of a render
+        blue_noise_mask();
+        BlueNoiseSampler {
+            seed,
+            samples_per_pixel: samples_per_pixel.max(1),
+            x: 0,
+            y: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Mask value of the pixel, the mask being moved around for each dimension
+    fn offset(&self, h: u64) -> f64 {
+        let dx = (h % MASK_SIZE as u64) as usize;
+        let dy = ((h >> 8) % MASK_SIZE as u64) as usize;
+        let x = (self.x as usize + dx) % MASK_SIZE;
+        let y = (self.y as usize + dy) % MASK_SIZE;
+        blue_noise_mask()[y * MASK_SIZE + x]
+    }
+
+    /// Point of the current dimension, the same for all pixels
+    fn next(&mut self) -> (u32, u32, u64) {
+        let h = hash(&[self.seed, u64::from(self.dimension)]);
+        self.dimension += 1;
+        let index = shuffled_index(self.index, self.samples_per_pixel, h as u32);
+        let (x, y) = sobol_2d(index);
+        (
+            owen_scramble(x, (h >> 32) as u32),
+            owen_scramble(y, h as u32 ^ (h >> 40) as u32),
+            h,
+        )
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.x = x;
+        self.y = y;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn set_dimension(&mut self, dimension: u32) {
+        self.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (x, _, h) = self.next();
+        (to_unit(x) + self.offset(h)).fract()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (x, y, h) = self.next();
+        self.dimension += 1;
+        let h2 = hash(&[h]);
+        (
+            (to_unit(x) + self.offset(h)).fract(),
+            (to_unit(y) + self.offset(h2)).fract(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(mask: &[f64], x: usize, y: usize) -> f64 {
+        mask[(y % MASK_SIZE) * MASK_SIZE + x % MASK_SIZE]
+    }
+
+    #[test]
+    fn mask_values_are_evenly_spread() {
+        let n = MASK_SIZE * MASK_SIZE;
+        let mut ranks: Vec<usize> = blue_noise_mask()
+            .iter()
+            .map(|v| (v * n as f64) as usize)
+            .collect();
+        ranks.sort_unstable();
+        assert!(ranks.iter().enumerate().all(|(i, rank)| i == *rank));
+    }
+
+    #[test]
+    fn mask_has_no_low_frequencies() {
+        // A shuffled mask, white noise, gives about 0.034, 0.33 and 80
+        let mask = blue_noise_mask();
+        // Spread of the means of 8x8 blocks around 1/2
+        let block = MASK_SIZE / 8;
+        let deviation = (0..64)
+            .map(|b| {
+                let (x0, y0) = (b % 8 * block, b / 8 * block);
+                let sum: f64 = (0..block * block)
+                    .map(|i| at(mask, x0 + i % block, y0 + i / block))
+                    .sum();
+                (sum / (block * block) as f64 - 0.5).powi(2)
+            })
+            .sum::<f64>()
+            / 64.0;
+        assert!(deviation.sqrt() < 0.02, "{}", deviation.sqrt());
+
+        // Neighbours differ more than random values, and the darkest tenth of the
+        // mask is made of isolated pixels
+        let (mut difference, mut clumped) = (0.0, 0);
+        for y in 0..MASK_SIZE {
+            for x in 0..MASK_SIZE {
+                let (value, right, below) =
+                    (at(mask, x, y), at(mask, x + 1, y), at(mask, x, y + 1));
+                difference += (value - right).abs() + (value - below).abs();
+                if value < 0.1 && (right < 0.1 || below < 0.1) {
+                    clumped += 1;
+                }
+            }
+        }
+        let difference = difference / (2 * MASK_SIZE * MASK_SIZE) as f64;
+        assert!(difference > 0.37, "{}", difference);
+        assert!(clumped < 10, "{}", clumped);
+    }
+}
@@ -0,0 +1,90 @@
+use super::{hash, hash_to_unit, pixel_key, Sampler};
+
+use std::sync::OnceLock;
+
+/// Dimensions with their own prime base, higher dimensions are sampled independently
+pub(crate) const PRIME_COUNT: usize = 1024;
+
+fn primes() -> &'static [u32] {
+    static PRIMES: OnceLock<Vec<u32>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        let mut primes: Vec<u32> = Vec::with_capacity(PRIME_COUNT);
+        let mut candidate = 2;
+        while primes.len() < PRIME_COUNT {
+            if primes
+                .iter()
+                .take_while(|p| *p * *p <= candidate)
+                .all(|p| candidate % p != 0)
+            {
+                primes.push(candidate);
+            }
+            candidate += 1;
+        }
+        primes
+    })
+}
+
+/// Digits of index in the base, mirrored around the radix point
+fn radical_inverse(base: u32, mut index: u64) -> f64 {
+    let inverse_base = 1.0 / f64::from(base);
+    let mut inverse_digits = 0.0;
+    let mut factor = inverse_base;
+    while index > 0 {
+        let digit = index % u64::from(base);
+        inverse_digits += digit as f64 * factor;
+        factor *= inverse_base;
+        index /= u64::from(base);
+    }
+    inverse_digits
+}
+
+/// Halton sequence: a prime base per dimension.
+/// Each pixel's sequence is shifted by a random offset (Cranley-Patterson rotation)
+/// so that neighbouring pixels don't share the same points.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = pixel_key(x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn set_dimension(&mut self, dimension: u32) {
+        self.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = u64::from(self.dimension);
+        self.dimension += 1;
+        let index = u64::from(self.index);
+        match primes().get(dimension as usize) {
+            Some(&base) => {
+                let offset = hash_to_unit(hash(&[self.seed, self.pixel, dimension]));
+                (radical_inverse(base, index) + offset).fract()
+            }
+            // Reusing a base would correlate the dimensions sharing it
+            None => hash_to_unit(hash(&[self.seed, self.pixel, dimension, index])),
+        }
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
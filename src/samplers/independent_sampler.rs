@@ -0,0 +1,43 @@
+use super::{hash, pixel_key, Pcg32, Sampler};
+
+/// Uncorrelated uniform random numbers
+pub struct IndependentSampler {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    rng: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            rng: Pcg32::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = pixel_key(x, y);
+        self.index = index;
+        self.set_dimension(0);
+    }
+
+    fn set_dimension(&mut self, dimension: u32) {
+        self.rng = Pcg32::with_stream(
+            hash(&[self.seed, self.pixel, u64::from(self.index)]),
+            u64::from(dimension),
+        );
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.next_f64(), self.rng.next_f64())
+    }
+}
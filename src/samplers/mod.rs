@@ -0,0 +1,356 @@
+mod pcg32;
+pub use pcg32::*;
+mod independent_sampler;
+pub use independent_sampler::*;
+mod stratified_sampler;
+pub use stratified_sampler::*;
+mod halton_sampler;
+pub use halton_sampler::*;
+mod sobol_sampler;
+pub use sobol_sampler::*;
+mod blue_noise_sampler;
+pub use blue_noise_sampler::*;
+
+// Dimensions of a path sample.
+// Each decision always draws from the same dimensions, whatever was drawn before,
+// so that low discrepancy sequences stay well distributed for each of them.
+
+/// Position inside the pixel, 2D
+pub const PIXEL_DIMENSION: u32 = 0;
+/// Lens position (2D) then shutter time (1D)
+pub const CAMERA_DIMENSION: u32 = 2;
+pub const WAVELENGTH_DIMENSION: u32 = 5;
+/// First dimension of the first bounce
+pub const BOUNCE_DIMENSION: u32 = 6;
+/// Dimensions available to a material choosing a scattered direction
+pub const SCATTER_DIMENSIONS: u32 = 4;
+/// Dimensions of a bounce: scattering, then a 2D sample per light,
+/// the last one deciding whether Russian roulette ends the path
+pub const DIMENSIONS_PER_BOUNCE: u32 = 16;
+/// Lights sampled from the dimensions of their bounce, between scattering and roulette
+pub const BOUNCE_LIGHTS: usize = ((DIMENSIONS_PER_BOUNCE - SCATTER_DIMENSIONS - 1) / 2) as usize;
+/// First dimension of the lights past BOUNCE_LIGHTS, far after those of any path
+pub const EXTRA_LIGHT_DIMENSION: u32 = 1 << 30;
+/// Dimensions of the extra lights of a bounce, the extra lights of a scene fit in them
+const EXTRA_LIGHT_DIMENSIONS: u32 = 1 << 14;
+
+/// First dimension used to scatter at a given bounce (0 for the camera ray hit)
+pub fn scatter_dimension(bounce: u32) -> u32 {
+    BOUNCE_DIMENSION + bounce * DIMENSIONS_PER_BOUNCE
+}
+
+/// Dimension of the 2D sample of a light at a given bounce.
+/// The first lights fit in the dimensions of the bounce, the others get their own,
+/// never shared with the other decisions of the path.
+pub fn light_dimension(bounce: u32, light: usize) -> u32 {
+    if light < BOUNCE_LIGHTS {
+        return scatter_dimension(bounce) + SCATTER_DIMENSIONS + 2 * light as u32;
+    }
+    let extra = 2 * (light - BOUNCE_LIGHTS) as u32;
+    assert!(
+        extra < EXTRA_LIGHT_DIMENSIONS && bounce < EXTRA_LIGHT_DIMENSION / EXTRA_LIGHT_DIMENSIONS,
+        "no sampler dimension left for light {} at bounce {}",
+        light,
+        bounce
+    );
+    EXTRA_LIGHT_DIMENSION + bounce * EXTRA_LIGHT_DIMENSIONS + extra
+}
+
+/// Dimension deciding whether the path goes on after a given bounce
+pub fn roulette_dimension(bounce: u32) -> u32 {
+    scatter_dimension(bounce + 1) - 1
+}
+
+/// Source of the numbers deciding every random choice of a path
+pub trait Sampler {
+    /// Restart at dimension 0 for a sample of a pixel
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32);
+
+    /// Jump to a dimension, following draws use the next ones
+    fn set_dimension(&mut self, dimension: u32);
+
+    /// Uniform number in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    /// Uniform point in [0, 1)², both coordinates well distributed together
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// Sampler picked for a render, each thread creates its own
+#[derive(Debug, Copy, Clone)]
+pub enum SamplerKind {
+    /// Uncorrelated random numbers
+    Independent,
+    /// Jittered strata, shuffled between dimensions
+    Stratified,
+    /// Halton sequence, randomly shifted for each pixel
+    Halton,
+    /// Sobol sequence with Owen scrambling
+    Sobol,
+    /// Sobol sequence shared by all pixels and shifted by a blue noise mask,
+    /// the remaining error looks like blue noise
+    BlueNoise,
+}
+
+impl SamplerKind {
+    /// Names recognized by `from_name`
+    pub const NAMES: &'static str = "independent, stratified, halton, sobol, bluenoise";
+
+    /// Kind given on the command line, None when the name is unknown
+    pub fn from_name(name: &str) -> Option<SamplerKind> {
+        let kind = match name {
+            "independent" => SamplerKind::Independent,
+            "stratified" => SamplerKind::Stratified,
+            "halton" => SamplerKind::Halton,
+            "sobol" => SamplerKind::Sobol,
+            "bluenoise" => SamplerKind::BlueNoise,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn create(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed, samples_per_pixel)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(seed, samples_per_pixel)),
+        }
+    }
+}
+
+/// SplitMix64 finalizer, spreads close values far apart
+pub(crate) fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Hash of several values
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e37_79b9_7f4a_7c15, |h, v| {
+        mix(h ^ v.wrapping_add(0x9e37_79b9))
+    })
+}
+
+fn pixel_key(x: u32, y: u32) -> u64 {
+    (u64::from(y) << 32) | u64::from(x)
+}
+
+/// Uniform number in [0, 1) from a hash
+fn hash_to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Element i of a random permutation of 0..l chosen by p,
+/// without storing the permutation (Kensler, "Correlated Multi-Jittered Sampling")
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % l
+}
+
+/// Shuffle sample indices by blocks of l
+fn shuffled_index(index: u32, l: u32, p: u32) -> u32 {
+    index - index % l + permutation_element(index % l, l, p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [SamplerKind; 5] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ];
+
+    /// Samples of a pixel drawn from a dimension, a pair for each
+    fn draw_2d(sampler: &mut dyn Sampler, dimension: u32, count: u32) -> Vec<(f64, f64)> {
+        (0..count)
+            .map(|index| {
+                sampler.start_pixel_sample(3, 5, index);
+                sampler.set_dimension(dimension);
+                sampler.get_2d()
+            })
+            .collect()
+    }
+
+    fn draw_1d(sampler: &mut dyn Sampler, dimension: u32, count: u32) -> Vec<f64> {
+        (0..count)
+            .map(|index| {
+                sampler.start_pixel_sample(3, 5, index);
+                sampler.set_dimension(dimension);
+                sampler.get_1d()
+            })
+            .collect()
+    }
+
+    /// Every one of count strata of [0, 1) holds a value
+    fn assert_stratified(values: &[f64], count: usize) {
+        let mut strata = vec![0; count];
+        for value in values {
+            strata[(value * count as f64) as usize] += 1;
+        }
+        assert!(strata.iter().all(|&n| n == 1), "{:?}", strata);
+    }
+
+    #[test]
+    fn samples_are_in_the_unit_interval() {
+        for kind in &KINDS {
+            let mut sampler = kind.create(1, 16);
+            for &dimension in &[0, 5, scatter_dimension(40), light_dimension(3, 20)] {
+                let samples = draw_2d(sampler.as_mut(), dimension, 256);
+                let ones = draw_1d(sampler.as_mut(), dimension, 256);
+                for value in samples.iter().flat_map(|&(u, v)| vec![u, v]).chain(ones) {
+                    assert!((0.0..1.0).contains(&value), "{:?} drew {}", kind, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_samples() {
+        for kind in &KINDS {
+            let mut a = kind.create(7, 16);
+            let mut b = kind.create(7, 16);
+            let mut other = kind.create(8, 16);
+            let samples = draw_2d(a.as_mut(), BOUNCE_DIMENSION, 64);
+            assert_eq!(samples, draw_2d(b.as_mut(), BOUNCE_DIMENSION, 64));
+            assert_ne!(samples, draw_2d(other.as_mut(), BOUNCE_DIMENSION, 64));
+        }
+    }
+
+    #[test]
+    fn sobol_is_a_net() {
+        let mut sampler = SobolSampler::new(2, 64);
+        for &dimension in &[PIXEL_DIMENSION, BOUNCE_DIMENSION, light_dimension(2, 1)] {
+            assert_stratified(&draw_1d(&mut sampler, dimension, 64), 64);
+            // Every elementary interval of area 1/64 holds a point
+            let points = draw_2d(&mut sampler, dimension, 64);
+            for log_width in 0..=6 {
+                let (columns, rows) = (1 << log_width, 64 >> log_width);
+                let mut cells = vec![0; 64];
+                for (u, v) in &points {
+                    let (x, y) = ((u * columns as f64) as usize, (v * rows as f64) as usize);
+                    cells[y * columns + x] += 1;
+                }
+                assert!(cells.iter().all(|&n| n == 1), "{}x{}", columns, rows);
+            }
+        }
+    }
+
+    #[test]
+    fn halton_is_stratified() {
+        // Dimensions 0 and 1 use the bases 2 and 3
+        let mut sampler = HaltonSampler::new(2);
+        assert_stratified(&draw_1d(&mut sampler, 0, 64), 64);
+        assert_stratified(&draw_1d(&mut sampler, 1, 81), 81);
+    }
+
+    #[test]
+    fn halton_does_not_reuse_bases() {
+        // Past the prime table, dimensions don't repeat the ones sharing their base
+        let mut sampler = HaltonSampler::new(2);
+        let first = draw_1d(&mut sampler, 0, 64);
+        for &dimension in &[PRIME_COUNT as u32, light_dimension(0, BOUNCE_LIGHTS)] {
+            let samples = draw_1d(&mut sampler, dimension, 64);
+            let shifts: Vec<f64> = samples
+                .iter()
+                .zip(&first)
+                .map(|(a, b)| (a - b).rem_euclid(1.0))
+                .collect();
+            assert!(shifts.iter().any(|shift| (shift - shifts[0]).abs() > 1e-9));
+        }
+    }
+
+    #[test]
+    fn stratified_is_stratified() {
+        let mut sampler = StratifiedSampler::new(2, 64);
+        assert_stratified(&draw_1d(&mut sampler, BOUNCE_DIMENSION, 64), 64);
+        let points = draw_2d(&mut sampler, BOUNCE_DIMENSION, 64);
+        let cells: Vec<f64> = points
+            .iter()
+            .map(|(u, v)| ((v * 8.0).floor() * 8.0 + (u * 8.0).floor()) / 64.0)
+            .collect();
+        assert_stratified(&cells, 64);
+    }
+
+    #[test]
+    fn stratified_is_uniform_with_non_square_counts() {
+        // 32 samples fill a 5x6 grid and leave 2 over
+        let mut sampler = StratifiedSampler::new(2, 32);
+        let pixels = 2000;
+        let mut cells = vec![0; 30];
+        for pixel in 0..pixels {
+            for index in 0..32 {
+                sampler.start_pixel_sample(pixel, 0, index);
+                sampler.set_dimension(BOUNCE_DIMENSION);
+                let (u, v) = sampler.get_2d();
+                cells[(v * 6.0) as usize * 5 + (u * 5.0) as usize] += 1;
+            }
+        }
+        let expected = f64::from(pixels * 32) / 30.0;
+        for &count in &cells {
+            assert!(
+                (f64::from(count) / expected - 1.0).abs() < 0.05,
+                "{:?}",
+                cells
+            );
+        }
+    }
+
+    #[test]
+    fn lights_keep_to_their_dimensions() {
+        for bounce in 0..4 {
+            let budget = scatter_dimension(bounce) + SCATTER_DIMENSIONS..roulette_dimension(bounce);
+            let mut dimensions: Vec<u32> = vec![];
+            for light in 0..20 {
+                let dimension = light_dimension(bounce, light);
+                if light < BOUNCE_LIGHTS {
+                    assert!(budget.contains(&dimension) && budget.contains(&(dimension + 1)));
+                } else {
+                    assert!(dimension >= EXTRA_LIGHT_DIMENSION);
+                }
+                dimensions.extend(&[dimension, dimension + 1]);
+            }
+            dimensions.sort_unstable();
+            dimensions.dedup();
+            assert_eq!(dimensions.len(), 40);
+        }
+        assert_ne!(
+            light_dimension(0, BOUNCE_LIGHTS),
+            light_dimension(1, BOUNCE_LIGHTS)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use super::{hash, pixel_key, shuffled_index, Sampler};
+
+/// Point of the first two dimensions of the Sobol sequence, as 32 bits fractions.
+/// Any pair of dimensions uses these two, with its own shuffling and scrambling.
+pub(crate) fn sobol_2d(index: u32) -> (u32, u32) {
+    let mut y = 0;
+    let mut v = 1 << 31;
+    let mut i = index;
+    while i != 0 {
+        if i & 1 != 0 {
+            y ^= v;
+        }
+        i >>= 1;
+        v ^= v >> 1;
+    }
+    (index.reverse_bits(), y)
+}
+
+/// Hash based nested uniform scrambling of the bits of a fraction
+/// (Laine and Karras, "Stratified sampling for stochastic transparency")
+pub(crate) fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+    v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20_adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x0552_6c56);
+    v ^= v.wrapping_mul(0x53a2_2864);
+    v.reverse_bits()
+}
+
+pub(crate) fn to_unit(v: u32) -> f64 {
+    f64::from(v) / (1u64 << 32) as f64
+}
+
+/// Sobol sequence, padded: each draw uses the first dimensions of the sequence
+/// with the sample indices shuffled, and Owen scrambling per pixel and dimension.
+/// Best with a power of two number of samples per pixel.
+pub struct SobolSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> SobolSampler {
+        SobolSampler {
+            seed,
+            samples_per_pixel: samples_per_pixel.max(1),
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Shuffled index and scrambling seed of the current dimension
+    fn next(&mut self) -> (u32, u64) {
+        let h = hash(&[self.seed, self.pixel, u64::from(self.dimension)]);
+        self.dimension += 1;
+        (
+            shuffled_index(self.index, self.samples_per_pixel, h as u32),
+            h,
+        )
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = pixel_key(x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn set_dimension(&mut self, dimension: u32) {
+        self.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, h) = self.next();
+        to_unit(owen_scramble(sobol_2d(index).0, (h >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (index, h) = self.next();
+        self.dimension += 1;
+        let (x, y) = sobol_2d(index);
+        let h2 = hash(&[h]);
+        (
+            to_unit(owen_scramble(x, (h >> 32) as u32)),
+            to_unit(owen_scramble(y, (h2 >> 32) as u32)),
+        )
+    }
+}
@@ -0,0 +1,78 @@
+use super::{hash, hash_to_unit, permutation_element, pixel_key, Sampler};
+
+/// Each dimension is split in one stratum per sample of the pixel,
+/// a random point is taken in each stratum.
+/// Strata are shuffled differently for each dimension.
+/// Pairs fall in a grid of strata, the samples it can't hold are uniform.
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    // 2D strata grid
+    x_strata: u32,
+    y_strata: u32,
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> StratifiedSampler {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let x_strata = f64::from(samples_per_pixel).sqrt() as u32;
+        StratifiedSampler {
+            seed,
+            samples_per_pixel,
+            x_strata,
+            y_strata: samples_per_pixel / x_strata,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Stratum of the current sample for the current dimension, and random numbers to jitter in it
+    fn next_stratum(&mut self) -> (u32, u64) {
+        let h = hash(&[self.seed, self.pixel, u64::from(self.dimension)]);
+        let stratum = permutation_element(
+            self.index % self.samples_per_pixel,
+            self.samples_per_pixel,
+            h as u32,
+        );
+        let jitter = hash(&[h, u64::from(self.index)]);
+        self.dimension += 1;
+        (stratum, jitter)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = pixel_key(x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn set_dimension(&mut self, dimension: u32) {
+        self.dimension = dimension;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (stratum, jitter) = self.next_stratum();
+        (f64::from(stratum) + hash_to_unit(jitter)) / f64::from(self.samples_per_pixel)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (stratum, jitter) = self.next_stratum();
+        self.dimension += 1;
+        let (u, v) = (hash_to_unit(jitter), hash_to_unit(hash(&[jitter])));
+        // with a non square sample count, the samples left over by the grid
+        // are spread over the whole square so no cell gets more than its share
+        if stratum >= self.x_strata * self.y_strata {
+            return (u, v);
+        }
+        let (x, y) = (stratum % self.x_strata, stratum / self.x_strata);
+        (
+            (f64::from(x) + u) / f64::from(self.x_strata),
+            (f64::from(y) + v) / f64::from(self.y_strata),
+        )
+    }
+}
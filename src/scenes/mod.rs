@@ -0,0 +1,768 @@
+use crate::cameras::{CameraAnimation, CameraSettings};
+use crate::hitables::*;
+use crate::lights::*;
+use crate::materials::*;
+use crate::samplers::*;
+
+use rand::Rng;
+use std::sync::Arc;
+
+/// Empty world lit by the physical sky model and its sun
+pub fn daylight_world(sun_elevation: f64, sun_azimuth: f64) -> HitableList {
+    let sky = Arc::new(PhysicalSky::new(sun_elevation, sun_azimuth, 3.0, 0.04));
+    let mut world = HitableList::new(sky.clone());
+    world.set_environment(sky);
+    world
+}
+
+/// Generate a random scene with 484 little random spheres,
+/// 3 bigger spheres in center, and a spheric ground.
+pub fn random_scene(seed: u64) -> HitableList {
+    let mut rng = Pcg32::new(seed);
+    let mut world = daylight_world(40.0, 30.0);
+
+    let mut texture_manager = ResourceManager::new();
+
+    let odd_color = TextureConfig {
+        kind: TextureKind::Constant(Color::new(51, 77, 26)),
+    };
+    let even_color = TextureConfig {
+        kind: TextureKind::Constant(Color::new(230, 230, 230)),
+    };
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::Checker(&odd_color, &even_color),
+            }),
+        }),
+    }));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.gen::<f64>();
+            let center = Vec3::new(
+                f64::from(a) + 0.9 * rng.gen::<f64>(),
+                0.2,
+                f64::from(b) + 0.9 * rng.gen::<f64>(),
+            );
+            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    // Diffuse
+                    let albedo = rng.gen::<Vec3>() * rng.gen::<Vec3>();
+                    let texture = texture_manager.get_resource(&TextureConfig {
+                        kind: TextureKind::Constant(Color::new(
+                            (albedo.x * 255.99) as u8,
+                            (albedo.y * 255.99) as u8,
+                            (albedo.z * 255.99) as u8,
+                        )),
+                    });
+                    world.push(Arc::new(MovingSphere {
+                        center0: center,
+                        center1: center + Vec3::new(0.0, 0.5 * rng.gen::<f64>(), 0.0),
+                        time0: 0.0,
+                        time1: 1.0,
+                        radius: 0.2,
+                        material: Arc::new(Lambertian { albedo: texture }),
+                    }));
+                } else if choose_mat < 0.95 {
+                    // Metal
+                    let albedo = Vec3::random_range(&mut rng, 0.5, 1.0);
+                    let fuzz = rng.gen_range(0.0, 0.5);
+                    world.push(Arc::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Arc::new(Metal::new(albedo, fuzz)),
+                    }));
+                } else {
+                    // Glass
+                    world.push(Arc::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Arc::new(Dielectric::new(1.5)),
+                    }));
+                }
+            }
+        }
+    }
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Dielectric::new(1.5)),
+    }));
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.4, 0.2, 0.1))),
+        }),
+    }));
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
+    }));
+
+    world
+}
+
+pub fn two_spheres() -> HitableList {
+    let mut world = daylight_world(40.0, 30.0);
+
+    let mut texture_manager = ResourceManager::new();
+
+    let odd_color = TextureConfig {
+        kind: TextureKind::Constant(Color::new(51, 77, 26)),
+    };
+    let even_color = TextureConfig {
+        kind: TextureKind::Constant(Color::new(230, 230, 230)),
+    };
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -10.0, 0.0),
+        radius: 10.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::Checker(&odd_color, &even_color),
+            }),
+        }),
+    }));
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 10.0, 0.0),
+        radius: 10.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::Checker(&odd_color, &even_color),
+            }),
+        }),
+    }));
+
+    world
+}
+
+pub fn two_perlin_spheres() -> HitableList {
+    let mut world = daylight_world(40.0, 30.0);
+
+    let mut texture_manager = ResourceManager::new();
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::Perlin(256, 4),
+            }),
+        }),
+    }));
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 2.0, 0.0),
+        radius: 2.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::Perlin(256, 4),
+            }),
+        }),
+    }));
+
+    world
+}
+
+pub fn earth() -> HitableList {
+    let mut texture_manager = ResourceManager::new();
+    let earth_texture = texture_manager.get_resource(&TextureConfig {
+        kind: TextureKind::FromFile(std::path::Path::new("assets/images/earthmap.jpg")),
+    });
+    let earth_surface = Arc::new(Lambertian {
+        albedo: earth_texture,
+    });
+    let globe = Arc::new(Sphere {
+        center: Vec3::default(),
+        radius: 2.0,
+        material: earth_surface,
+    });
+
+    let mut world = daylight_world(40.0, 30.0);
+
+    world.push(globe);
+
+    world
+}
+
+struct BlackMaterial;
+
+impl Material for BlackMaterial {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+}
+
+pub fn simple_light() -> HitableList {
+    let background = Arc::new(BlackMaterial {});
+    let mut world = HitableList::new(background);
+
+    let mut texture_manager = ResourceManager::new();
+    let perlin_texture = texture_manager.get_resource(&TextureConfig {
+        kind: TextureKind::Perlin(256, 4),
+    });
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: perlin_texture.clone(),
+        }),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 2.0, 0.0),
+        radius: 2.0,
+        material: Arc::new(Lambertian {
+            albedo: perlin_texture.clone(),
+        }),
+    }));
+
+    let light = Arc::new(Emissive {
+        emit: texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Constant(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            }),
+        }),
+        multiplier: 4.0,
+    });
+    world.push(Arc::new(XYRect {
+        x0: 3.0,
+        x1: 5.0,
+        y0: 1.0,
+        y1: 3.0,
+        k: -2.0,
+        material: light,
+    }));
+
+    world
+}
+
+pub fn cornell_box() -> HitableList {
+    let background = Arc::new(BlackMaterial {});
+    let mut world = HitableList::new(background);
+    let mut texture_manager = ResourceManager::new();
+
+    let red = Arc::new(Lambertian {
+        albedo: texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Constant(Color {
+                r: 166,
+                g: 13,
+                b: 13,
+            }),
+        }),
+    });
+    let white = Arc::new(Lambertian {
+        albedo: texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Constant(Color {
+                r: 186,
+                g: 186,
+                b: 186,
+            }),
+        }),
+    });
+    let green = Arc::new(Lambertian {
+        albedo: texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Constant(Color {
+                r: 31,
+                g: 115,
+                b: 38,
+            }),
+        }),
+    });
+    let light = Arc::new(Emissive {
+        emit: texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Constant(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+            }),
+        }),
+        multiplier: 15.0,
+    });
+
+    let box_size = 555.0;
+    world.push(Arc::new(YZRect {
+        material: green,
+        y0: 0.0,
+        y1: box_size,
+        z0: 0.0,
+        z1: box_size,
+        k: box_size,
+    }));
+    world.push(Arc::new(YZRect {
+        material: red,
+        y0: 0.0,
+        y1: box_size,
+        z0: 0.0,
+        z1: box_size,
+        k: 0.0,
+    }));
+    world.push(Arc::new(XZRect {
+        material: light,
+        x0: 213.0,
+        x1: 343.0,
+        z0: 227.0,
+        z1: 332.0,
+        k: 554.0,
+    }));
+    world.push(Arc::new(XZRect {
+        material: white.clone(),
+        x0: 0.0,
+        x1: box_size,
+        z0: 0.0,
+        z1: box_size,
+        k: 0.0,
+    }));
+    world.push(Arc::new(XZRect {
+        material: white.clone(),
+        x0: 0.0,
+        x1: box_size,
+        z0: 0.0,
+        z1: box_size,
+        k: box_size,
+    }));
+    world.push(Arc::new(XYRect {
+        material: white.clone(),
+        x0: 0.0,
+        x1: box_size,
+        y0: 0.0,
+        y1: box_size,
+        k: box_size,
+    }));
+
+    world
+}
+
+/// A sphere seen through a fence made of a single alpha masked rectangle
+pub fn fence() -> HitableList {
+    let mut world = daylight_world(40.0, 30.0);
+    let mut texture_manager = ResourceManager::new();
+
+    let transparent = TextureConfig {
+        kind: TextureKind::Constant(Color::black()),
+    };
+    let opaque = TextureConfig {
+        kind: TextureKind::Constant(Color::white()),
+    };
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+        }),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 1.0, -2.0),
+        radius: 1.0,
+        material: Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.1)),
+    }));
+
+    let planks = Arc::new(XYRect {
+        x0: -3.0,
+        x1: 3.0,
+        y0: 0.0,
+        y1: 2.0,
+        k: 1.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.4, 0.2, 0.1))),
+        }),
+    });
+    world.push(Arc::new(AlphaMask::new(
+        planks,
+        texture_manager.get_resource(&TextureConfig {
+            kind: TextureKind::Checker(&transparent, &opaque),
+        }),
+        AlphaMode::Threshold(0.5),
+    )));
+
+    world
+}
+
+/// A wax candle lit by its own flame.
+/// Light goes through the wax thanks to subsurface scattering.
+/// Random walks inside the wax need a deep maximum recursion.
+pub fn candle() -> HitableList {
+    let background = Arc::new(BlackMaterial {});
+    let mut world = HitableList::new(background);
+
+    world.push(Arc::new(XZRect {
+        x0: -50.0,
+        x1: 50.0,
+        z0: -50.0,
+        z1: 50.0,
+        k: 0.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.4, 0.3, 0.25))),
+        }),
+    }));
+    world.push(Arc::new(XYRect {
+        x0: -50.0,
+        x1: 50.0,
+        y0: 0.0,
+        y1: 50.0,
+        k: -4.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+        }),
+    }));
+
+    let wax = Subsurface::from_albedo(1.45, Vec3::new(0.995, 0.97, 0.85), Vec3::new(0.5, 0.4, 0.3));
+    world.push(Arc::new(Cylinder {
+        center: Vec3::default(),
+        radius: 1.0,
+        y0: 0.0,
+        y1: 3.0,
+        material: Arc::new(wax),
+    }));
+
+    let flame = Arc::new(Emissive {
+        emit: Arc::new(SolidColor::new(Vec3::new(1.0, 0.6, 0.2))),
+        multiplier: 20.0,
+    });
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 3.45, 0.0),
+        radius: 0.25,
+        material: flame,
+    }));
+
+    // Dim fill light
+    world.push(Arc::new(XZRect {
+        x0: -3.0,
+        x1: 3.0,
+        z0: 2.0,
+        z1: 6.0,
+        k: 10.0,
+        material: Arc::new(Emissive {
+            emit: Arc::new(SolidColor::new(Vec3::new(1.0, 1.0, 1.0))),
+            multiplier: 1.0,
+        }),
+    }));
+
+    world
+}
+
+/// A flint glass ball lit from the side,
+/// its caustic on the ground is split into colors in spectral mode
+pub fn dispersion() -> HitableList {
+    let background = Arc::new(BlackMaterial {});
+    let mut world = HitableList::new(background);
+
+    world.push(Arc::new(XZRect {
+        x0: -50.0,
+        x1: 50.0,
+        z0: -50.0,
+        z1: 50.0,
+        k: 0.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.8, 0.8, 0.8))),
+        }),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Dielectric::flint_glass()),
+    }));
+    world.push(Arc::new(YZRect {
+        y0: 0.5,
+        y1: 3.5,
+        z0: -1.5,
+        z1: 1.5,
+        k: -5.0,
+        material: Arc::new(Emissive {
+            emit: Arc::new(SolidColor::new(Vec3::new(1.0, 1.0, 1.0))),
+            multiplier: 8.0,
+        }),
+    }));
+
+    world
+}
+
+/// Spheres lit by a point light, a spot light and the sun
+pub fn punctual_lights() -> HitableList {
+    let background = Arc::new(BlackMaterial {});
+    let mut world = HitableList::new(background);
+
+    let grey = Arc::new(Lambertian {
+        albedo: Arc::new(SolidColor::new(Vec3::new(0.6, 0.6, 0.6))),
+    });
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: grey.clone(),
+    }));
+    for x in [-3.0, 0.0, 3.0].iter() {
+        world.push(Arc::new(Sphere {
+            center: Vec3::new(*x, 1.0, 0.0),
+            radius: 1.0,
+            material: grey.clone(),
+        }));
+    }
+
+    world.push_light(Arc::new(PointLight {
+        position: Vec3::new(-3.0, 4.0, 3.0),
+        intensity: Vec3::new(20.0, 14.0, 8.0),
+    }));
+    world.push_light(Arc::new(SpotLight::new(
+        Vec3::new(3.0, 6.0, 2.0),
+        Vec3::new(3.0, 0.0, 0.0),
+        Vec3::new(20.0, 30.0, 60.0),
+        25.0,
+        15.0,
+    )));
+    world.push_light(Arc::new(DirectionalLight::new(
+        Vec3::new(-1.0, -1.0, -0.5),
+        Vec3::new(0.3, 0.3, 0.3),
+        0.53,
+    )));
+
+    world
+}
+
+/// Spheres lit by a HDR environment map
+pub fn environment() -> HitableList {
+    let mut world = HitableList::new(Arc::new(BlackMaterial {}));
+    let environment = EnvironmentLight::from_file(
+        std::path::Path::new("assets/environments/sunny_sky.hdr"),
+        0.0,
+        1.0,
+    )
+    .expect("Can't load the environment map");
+    world.set_environment(Arc::new(environment));
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+        }),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(-2.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.7, 0.2, 0.2))),
+        }),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.8), 0.05)),
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(2.2, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Dielectric::new(1.5)),
+    }));
+
+    world
+}
+
+/// A spinning globe, a bouncing ball and a pulsing glass sphere, two seconds long
+pub fn turntable() -> HitableList {
+    let mut world = daylight_world(40.0, 30.0);
+    let mut texture_manager = ResourceManager::new();
+
+    world.push(Arc::new(Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.5, 0.5, 0.5))),
+        }),
+    }));
+
+    let globe = Arc::new(Sphere {
+        center: Vec3::default(),
+        radius: 1.0,
+        material: Arc::new(Lambertian {
+            albedo: texture_manager.get_resource(&TextureConfig {
+                kind: TextureKind::FromFile(std::path::Path::new("assets/images/earthmap.jpg")),
+            }),
+        }),
+    });
+    world.push(Arc::new(
+        Animated::new(globe)
+            .with_translation(Track::constant(Vec3::new(0.0, 1.0, 0.0)))
+            .with_rotation(Track::new(Interpolation::Linear, 0.0, 0.0).key(2.0, 360.0)),
+    ));
+
+    let ball = Arc::new(Sphere {
+        center: Vec3::default(),
+        radius: 0.5,
+        material: Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3::new(0.7, 0.2, 0.2))),
+        }),
+    });
+    world.push(Arc::new(
+        Animated::new(ball).with_translation(
+            Track::new(Interpolation::CatmullRom, 0.0, Vec3::new(-2.5, 0.5, 1.0))
+                .key(0.5, Vec3::new(-2.5, 2.5, 1.0))
+                .key(1.0, Vec3::new(-2.5, 0.5, 1.0))
+                .key(1.5, Vec3::new(-2.5, 2.5, 1.0))
+                .key(2.0, Vec3::new(-2.5, 0.5, 1.0)),
+        ),
+    ));
+
+    let glass = Arc::new(Sphere {
+        center: Vec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Dielectric::new(1.5)),
+    });
+    world.push(Arc::new(
+        Animated::new(glass)
+            .with_translation(Track::constant(Vec3::new(2.5, 0.0, 0.0)))
+            .with_scale(
+                Track::new(Interpolation::CatmullRom, 0.0, 1.0)
+                    .key(1.0, 0.6)
+                    .key(2.0, 1.0),
+            ),
+    ));
+
+    world
+}
+
+/// Quarter orbit around the turntable, zooming in
+pub fn turntable_camera(still: CameraSettings) -> CameraAnimation {
+    let mut animation = CameraAnimation::new(still);
+    animation.lookfrom = Track::new(Interpolation::CatmullRom, 0.0, Vec3::new(0.0, 3.0, 10.0))
+        .key(1.0, Vec3::new(7.07, 4.0, 7.07))
+        .key(2.0, Vec3::new(10.0, 3.0, 0.0));
+    animation.vfov = Track::new(Interpolation::Linear, 0.0, 40.0).key(2.0, 30.0);
+    animation
+}
+
+/// A built-in scene and the camera framing it
+pub struct Preset {
+    pub world: HitableList,
+    pub camera: CameraSettings,
+    pub max_depth: u32,
+}
+
+/// Built-in scene number scene, the Cornell box for unknown numbers.
+/// seed drives the random choices of the scenes that make some.
+pub fn preset(scene: u32, seed: u64) -> Preset {
+    let (world, lookfrom, lookat, vfov, aperture, max_depth) = match scene {
+        0 => (
+            random_scene(seed),
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::default(),
+            20.0,
+            0.1,
+            10,
+        ),
+        1 => (
+            two_spheres(),
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::default(),
+            20.0,
+            0.0,
+            10,
+        ),
+        2 => (
+            two_perlin_spheres(),
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::default(),
+            20.0,
+            0.0,
+            10,
+        ),
+        3 => (
+            earth(),
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::default(),
+            20.0,
+            0.0,
+            10,
+        ),
+        4 => (
+            simple_light(),
+            Vec3::new(26.0, 3.0, 6.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            20.0,
+            0.0,
+            10,
+        ),
+        6 => (
+            fence(),
+            Vec3::new(0.0, 2.0, 10.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            0.0,
+            10,
+        ),
+        7 => (
+            candle(),
+            Vec3::new(0.0, 3.0, 9.0),
+            Vec3::new(0.0, 1.8, 0.0),
+            30.0,
+            0.0,
+            64,
+        ),
+        8 => (
+            dispersion(),
+            Vec3::new(3.0, 7.0, 8.0),
+            Vec3::new(1.5, 0.5, 0.0),
+            30.0,
+            0.0,
+            10,
+        ),
+        9 => (
+            punctual_lights(),
+            Vec3::new(0.0, 4.0, 12.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            35.0,
+            0.0,
+            10,
+        ),
+        10 => (
+            environment(),
+            Vec3::new(0.0, 2.5, 10.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            0.0,
+            10,
+        ),
+        11 => (
+            turntable(),
+            Vec3::new(0.0, 3.0, 10.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            0.0,
+            10,
+        ),
+        _ => (
+            cornell_box(),
+            Vec3::new(278.0, 278.0, -800.0),
+            Vec3::new(278.0, 278.0, 0.0),
+            40.0,
+            0.0,
+            10,
+        ),
+    };
+    Preset {
+        world,
+        camera: CameraSettings {
+            lookfrom,
+            lookat,
+            vfov,
+            aperture,
+            focus_dist: 10.0,
+        },
+        max_depth,
+    }
+}
@@ -0,0 +1,4 @@
+mod tile;
+pub use tile::*;
+mod pool;
+pub use pool::*;
@@ -0,0 +1,49 @@
+use crossbeam_deque::{Injector, Steal, Stealer, Worker};
+
+use std::iter;
+use std::thread;
+
+/// Run job on every item with thread_count threads.
+/// Threads take items from a shared queue in the order they are given, a batch at a time,
+/// and steal from the others' batches once the queue is empty, so none idles while work is left.
+/// Each thread calls init once to build the state it passes to job, a sampler for instance.
+pub fn for_each_stealing<T, S, I, F>(items: Vec<T>, thread_count: usize, init: I, job: F)
+where
+    T: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, T) + Sync,
+{
+    let injector = Injector::new();
+    for item in items {
+        injector.push(item);
+    }
+    let workers: Vec<Worker<T>> = (0..thread_count.max(1))
+        .map(|_| Worker::new_fifo())
+        .collect();
+    let stealers: Vec<Stealer<T>> = workers.iter().map(|worker| worker.stealer()).collect();
+
+    thread::scope(|scope| {
+        for worker in workers {
+            let (injector, stealers, init, job) = (&injector, &stealers, &init, &job);
+            scope.spawn(move || {
+                let mut state = init();
+                while let Some(item) = find_item(&worker, injector, stealers) {
+                    job(&mut state, item);
+                }
+            });
+        }
+    });
+}
+
+/// Next item for a thread: its own first, then from the shared queue, then from the others
+fn find_item<T>(local: &Worker<T>, injector: &Injector<T>, stealers: &[Stealer<T>]) -> Option<T> {
+    local.pop().or_else(|| {
+        iter::repeat_with(|| {
+            injector
+                .steal_batch_and_pop(local)
+                .or_else(|| stealers.iter().map(|stealer| stealer.steal()).collect())
+        })
+        .find(|steal| !steal.is_retry())
+        .and_then(Steal::success)
+    })
+}
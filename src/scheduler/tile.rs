@@ -0,0 +1,187 @@
+/// Rectangle of pixels rendered as a unit, rows counted from the top of the image.
+/// Covers x0..x1 and y0..y1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.width() as usize * self.height() as usize
+    }
+}
+
+/// Order in which tiles are handed out
+#[derive(Debug, Copy, Clone)]
+pub enum TileOrder {
+    /// Left to right, top to bottom
+    Scanline,
+    /// Outward from the center of the image, where the subject usually is
+    Spiral,
+    /// Along a Hilbert curve, consecutive tiles stay close to each other
+    Hilbert,
+}
+
+/// Cut the image in tiles of at most tile_size pixels a side, listed in the given order
+pub fn tiles(image_width: u32, image_height: u32, tile_size: u32, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = image_width.div_ceil(tile_size);
+    let rows = image_height.div_ceil(tile_size);
+    let tile = |column: u32, row: u32| Tile {
+        x0: column * tile_size,
+        y0: row * tile_size,
+        x1: ((column + 1) * tile_size).min(image_width),
+        y1: ((row + 1) * tile_size).min(image_height),
+    };
+
+    let cells = match order {
+        TileOrder::Scanline => (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            let mut cells: Vec<(u32, u32)> = (0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (column, row)))
+                .collect();
+            cells.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+            cells
+        }
+    };
+
+    cells
+        .into_iter()
+        .map(|(column, row)| tile(column, row))
+        .collect()
+}
+
+/// Cells of a columns x rows grid walked in a square spiral from the center one
+fn spiral(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    if columns == 0 || rows == 0 {
+        return vec![];
+    }
+    let count = columns as usize * rows as usize;
+    let mut cells = Vec::with_capacity(count);
+    let (mut x, mut y) = (i64::from((columns - 1) / 2), i64::from((rows - 1) / 2));
+    // right, down, left, up, the run length grows every second turn
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut run = 1;
+    let mut turn = 0;
+    while cells.len() < count {
+        for _ in 0..2 {
+            let (dx, dy) = directions[turn % 4];
+            for _ in 0..run {
+                if x >= 0 && y >= 0 && x < i64::from(columns) && y < i64::from(rows) {
+                    cells.push((x as u32, y as u32));
+                }
+                x += dx;
+                y += dy;
+            }
+            turn += 1;
+        }
+        run += 1;
+    }
+    cells.truncate(count);
+    cells
+}
+
+/// Distance along the Hilbert curve filling a side x side grid, side being a power of two
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        index += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve inside it starts and ends at the right corners
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDERS: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+    /// Every pixel of the image is covered by exactly one tile
+    fn assert_covered(width: u32, height: u32, tile_size: u32, order: TileOrder) {
+        let mut covered = vec![0; width as usize * height as usize];
+        for tile in tiles(width, height, tile_size, order) {
+            assert!(tile.x1 <= width && tile.y1 <= height && tile.pixel_count() > 0);
+            for y in tile.y0..tile.y1 {
+                for x in tile.x0..tile.x1 {
+                    covered[(y * width + x) as usize] += 1;
+                }
+            }
+        }
+        assert!(
+            covered.iter().all(|&count| count == 1),
+            "{:?} tiles of {} pixels over {}x{}",
+            order,
+            tile_size,
+            width,
+            height
+        );
+    }
+
+    #[test]
+    fn tiles_cover_the_image_once() {
+        for order in &ORDERS {
+            for &(width, height) in &[(1, 1), (64, 64), (100, 37), (37, 100), (250, 3)] {
+                for &tile_size in &[1, 8, 16, 64] {
+                    assert_covered(width, height, tile_size, *order);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_image_has_no_tiles() {
+        for order in &ORDERS {
+            assert!(tiles(0, 10, 16, *order).is_empty());
+            assert!(tiles(10, 0, 16, *order).is_empty());
+        }
+    }
+
+    #[test]
+    fn spiral_starts_in_the_center() {
+        assert_eq!(spiral(5, 5)[0], (2, 2));
+        assert_eq!(spiral(4, 3)[0], (1, 1));
+    }
+
+    #[test]
+    fn hilbert_steps_to_neighbours() {
+        let cells: Vec<(u32, u32)> = {
+            let mut cells: Vec<(u32, u32)> = (0..8)
+                .flat_map(|row| (0..8).map(move |column| (column, row)))
+                .collect();
+            cells.sort_by_key(|&(column, row)| hilbert_index(8, column, row));
+            cells
+        };
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let distance = (a.0 as i64 - b.0 as i64).abs() + (a.1 as i64 - b.1 as i64).abs();
+            assert_eq!(distance, 1, "{:?} to {:?}", a, b);
+        }
+    }
+}
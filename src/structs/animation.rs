@@ -0,0 +1,80 @@
+use std::ops::{Add, Mul, Sub};
+
+/// How values are blended between two keyframes
+#[derive(Debug, Copy, Clone)]
+pub enum Interpolation {
+    Linear,
+    /// Smooth Catmull-Rom spline going through every key
+    CatmullRom,
+}
+
+/// Value changing over time, defined by keyframes.
+/// Before the first key and after the last one, the value holds.
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keys: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T> Track<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+{
+    /// Track starting from a first key, a track always has one to hold
+    pub fn new(interpolation: Interpolation, time: f64, value: T) -> Track<T> {
+        Track {
+            keys: vec![(time, value)],
+            interpolation,
+        }
+    }
+
+    /// Track holding the same value at all times
+    pub fn constant(value: T) -> Track<T> {
+        Track::new(Interpolation::Linear, 0.0, value)
+    }
+
+    /// Add a keyframe, keys may be given in any order
+    pub fn key(mut self, time: f64, value: T) -> Track<T> {
+        let index = self.keys.partition_point(|(t, _)| *t <= time);
+        self.keys.insert(index, (time, value));
+        self
+    }
+
+    pub fn key_times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.keys.iter().map(|(t, _)| *t)
+    }
+
+    pub fn at(&self, time: f64) -> T {
+        let (first, last) = (self.keys[0], self.keys[self.keys.len() - 1]);
+        if time <= first.0 {
+            return first.1;
+        }
+        if time >= last.0 {
+            return last.1;
+        }
+
+        // keys surrounding the time, i is at least 1 here
+        let i = self.keys.partition_point(|(t, _)| *t <= time);
+        let (t0, p0) = self.keys[i - 1];
+        let (t1, p1) = self.keys[i];
+        let s = (time - t0) / (t1 - t0);
+
+        match self.interpolation {
+            Interpolation::Linear => p0 + (p1 - p0) * s,
+            Interpolation::CatmullRom => {
+                // tangents from the neighbouring keys, scaled to this segment's duration
+                let (t_prev, p_prev) = if i >= 2 { self.keys[i - 2] } else { (t0, p0) };
+                let (t_next, p_next) = self.keys.get(i + 1).copied().unwrap_or((t1, p1));
+                let m0 = (p1 - p_prev) * ((t1 - t0) / (t1 - t_prev));
+                let m1 = (p_next - p0) * ((t1 - t0) / (t_next - t0));
+
+                let s2 = s * s;
+                let s3 = s2 * s;
+                p0 * (2.0 * s3 - 3.0 * s2 + 1.0)
+                    + m0 * (s3 - 2.0 * s2 + s)
+                    + p1 * (-2.0 * s3 + 3.0 * s2)
+                    + m1 * (s3 - s2)
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use super::Vec3;
+
+use exr::prelude::{
+    read_all_flat_layers_from_file, AnyChannel, AnyChannels, Encoding, FlatSamples,
+    Image as ExrImage, Layer, LayerAttributes, SmallVec, WritableImage,
+};
+use std::error::Error;
+use std::path::Path;
+
+/// Render passes captured at the first hit of camera paths, for compositing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Aov {
+    /// Distance from the camera
+    Depth,
+    /// World space position
+    Position,
+    /// Shading normal, facing the camera
+    Normal,
+    /// Fraction of light the material scatters
+    Albedo,
+    /// Texture coordinates
+    Uv,
+    /// Position of the object in the world list, starting at 1, 0 for the background
+    ObjectId,
+    /// Materials numbered by order of appearance from the top left corner, 0 for the background
+    MaterialId,
+    /// Light emitted by the surface, or the background when nothing is hit
+    Emission,
+    /// Light reaching the surface from light samples
+    Direct,
+    /// Light brought by the scattered ray, emissive surfaces that are not lights included
+    Indirect,
+    /// Direct light of each light separately
+    Lights,
+}
+
+impl Aov {
+    /// Every pass, in the order of `NAMES`
+    pub const ALL: [Aov; 11] = [
+        Aov::Depth,
+        Aov::Position,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::Uv,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::Emission,
+        Aov::Direct,
+        Aov::Indirect,
+        Aov::Lights,
+    ];
+
+    /// Names recognized by `from_name`
+    pub const NAMES: &'static str = "depth, position, normal, albedo, uv, object-id, material-id, \
+                                     emission, direct, indirect, lights";
+
+    /// Pass given on the command line, None when the name is unknown
+    pub fn from_name(name: &str) -> Option<Aov> {
+        let aov = match name {
+            "depth" => Aov::Depth,
+            "position" => Aov::Position,
+            "normal" => Aov::Normal,
+            "albedo" => Aov::Albedo,
+            "uv" => Aov::Uv,
+            "object-id" => Aov::ObjectId,
+            "material-id" => Aov::MaterialId,
+            "emission" => Aov::Emission,
+            "direct" => Aov::Direct,
+            "indirect" => Aov::Indirect,
+            "lights" => Aov::Lights,
+            _ => return None,
+        };
+        Some(aov)
+    }
+
+    /// IDs are taken from a single sample, a blend of two IDs means nothing
+    pub fn is_id(&self) -> bool {
+        matches!(self, Aov::ObjectId | Aov::MaterialId)
+    }
+
+    /// Names of the channels of the pass in OpenEXR files
+    pub fn channel_names(&self, light_count: usize) -> Vec<String> {
+        let group = |name: &str, channels: &[&str]| -> Vec<String> {
+            channels
+                .iter()
+                .map(|channel| format!("{}.{}", name, channel))
+                .collect()
+        };
+        match self {
+            Aov::Depth => vec!["Z".to_string()],
+            Aov::Position => group("P", &["X", "Y", "Z"]),
+            Aov::Normal => group("N", &["X", "Y", "Z"]),
+            Aov::Albedo => group("albedo", &["R", "G", "B"]),
+            Aov::Uv => group("uv", &["U", "V"]),
+            Aov::ObjectId => vec!["objectId".to_string()],
+            Aov::MaterialId => vec!["materialId".to_string()],
+            Aov::Emission => group("emission", &["R", "G", "B"]),
+            Aov::Direct => group("direct", &["R", "G", "B"]),
+            Aov::Indirect => group("indirect", &["R", "G", "B"]),
+            Aov::Lights => (1..=light_count)
+                .flat_map(|light| group(&format!("light{}", light), &["R", "G", "B"]))
+                .collect(),
+        }
+    }
+}
+
+/// Where the values of the selected passes go in the channels of a sample.
+/// ID passes are kept apart, they have no channel there.
+#[derive(Debug, Clone)]
+pub struct AovLayout {
+    aovs: Vec<Aov>,
+    light_count: usize,
+}
+
+impl AovLayout {
+    pub fn new(aovs: &[Aov], light_count: usize) -> AovLayout {
+        AovLayout {
+            aovs: aovs.to_vec(),
+            light_count,
+        }
+    }
+
+    pub fn contains(&self, aov: Aov) -> bool {
+        self.aovs.contains(&aov)
+    }
+
+    /// No pass selected
+    pub fn is_empty(&self) -> bool {
+        self.aovs.is_empty()
+    }
+
+    /// Names of the channels accumulated with the samples
+    pub fn channel_names(&self) -> Vec<String> {
+        self.aovs
+            .iter()
+            .filter(|aov| !aov.is_id())
+            .flat_map(|aov| aov.channel_names(self.light_count))
+            .collect()
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channel_names().len()
+    }
+
+    /// Channels of an AOV, None when it is not selected or is an ID
+    fn channels(&self, aov: Aov) -> Option<std::ops::Range<usize>> {
+        let mut offset = 0;
+        for selected in self.aovs.iter().filter(|aov| !aov.is_id()) {
+            let count = selected.channel_names(self.light_count).len();
+            if *selected == aov {
+                return Some(offset..offset + count);
+            }
+            offset += count;
+        }
+        None
+    }
+
+    /// Store the value of an AOV in the channels of a sample, if selected
+    pub fn set(&self, channels: &mut [f64], aov: Aov, value: &[f64]) {
+        if let Some(range) = self.channels(aov) {
+            channels[range].copy_from_slice(value);
+        }
+    }
+
+    pub fn set_color(&self, channels: &mut [f64], aov: Aov, color: Vec3) {
+        self.set(channels, aov, &[color.x, color.y, color.z]);
+    }
+
+    /// Add to the direct light of one light, if the Lights AOV is selected
+    pub fn add_light(&self, channels: &mut [f64], light: usize, color: Vec3) {
+        if let Some(range) = self.channels(Aov::Lights) {
+            let first = range.start + 3 * light;
+            channels[first] += color.x;
+            channels[first + 1] += color.y;
+            channels[first + 2] += color.z;
+        }
+    }
+
+    /// Multiply the lighting passes by the weight of the camera ray, so they add up to the beauty
+    pub fn scale_lighting(&self, channels: &mut [f64], weight: f64) {
+        for aov in &[Aov::Emission, Aov::Direct, Aov::Indirect, Aov::Lights] {
+            if let Some(range) = self.channels(*aov) {
+                for value in &mut channels[range] {
+                    *value *= weight;
+                }
+            }
+        }
+    }
+}
+
+/// Named float channels of an image, top row first
+pub struct AovImage {
+    pub width: u32,
+    pub height: u32,
+    pub channels: Vec<(String, Vec<f32>)>,
+}
+
+impl AovImage {
+    pub fn channel(&self, name: &str) -> Option<&[f32]> {
+        self.channels
+            .iter()
+            .find(|(channel, _)| channel == name)
+            .map(|(_, values)| &values[..])
+    }
+
+    /// Write every channel to a single OpenEXR layer
+    pub fn write_exr(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let channels = self
+            .channels
+            .iter()
+            .map(|(name, values)| AnyChannel::new(name.as_str(), FlatSamples::F32(values.clone())))
+            .collect();
+        let layer = Layer::new(
+            (self.width as usize, self.height as usize),
+            LayerAttributes::named("aovs"),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(SmallVec::from_vec(channels)),
+        );
+        ExrImage::from_layer(layer).write().to_file(path)?;
+        Ok(())
+    }
+
+    /// Read the channels of the first layer of an OpenEXR file, as written by `write_exr`
+    pub fn read_exr(path: &Path) -> Result<AovImage, Box<dyn Error>> {
+        let image = read_all_flat_layers_from_file(path)?;
+        let layer = image.layer_data.first().ok_or("no layer in the file")?;
+        let channels = layer
+            .channel_data
+            .list
+            .iter()
+            .map(|channel| {
+                let values = channel.sample_data.values_as_f32().collect();
+                (channel.name.to_string(), values)
+            })
+            .collect();
+        Ok(AovImage {
+            width: layer.size.width() as u32,
+            height: layer.size.height() as u32,
+            channels,
+        })
+    }
+}
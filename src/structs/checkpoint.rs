@@ -0,0 +1,184 @@
+use super::FilmPixel;
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// File signature, the last byte is the format version
+const MAGIC: &[u8; 8] = b"RTCKPT\0\x03";
+
+/// Everything an interrupted render needs to go on where it stopped.
+/// Samplers only depend on the seed, the pixel and the sample index,
+/// so the sample counts of the pixels are the whole sampler state.
+pub struct Checkpoint {
+    /// Hash of the settings of the render, see `settings_hash`
+    pub settings_hash: u64,
+    /// Size of the crop of the film
+    pub width: u32,
+    pub height: u32,
+    pub seed: u64,
+    /// Accumulated radiance and sample count of every pixel of the film, top row first
+    pub pixels: Vec<FilmPixel>,
+    /// Weighted sums of the AOV channels of the film, pixel after pixel
+    pub aovs: Vec<f64>,
+}
+
+/// FNV-1a hash of a description of the render settings.
+/// A checkpoint is only resumed by a render with the same hash.
+pub fn settings_hash(description: &str) -> u64 {
+    description
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl Checkpoint {
+    /// Write to a temporary file first, an interruption never leaves a truncated checkpoint
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let partial = path.with_extension("partial");
+        {
+            let mut out = BufWriter::new(File::create(&partial)?);
+            out.write_all(MAGIC)?;
+            out.write_all(&self.settings_hash.to_le_bytes())?;
+            out.write_all(&self.width.to_le_bytes())?;
+            out.write_all(&self.height.to_le_bytes())?;
+            out.write_all(&self.seed.to_le_bytes())?;
+            for pixel in &self.pixels {
+                pixel.write_to(&mut out)?;
+            }
+            out.write_all(&(self.aovs.len() as u64).to_le_bytes())?;
+            for value in &self.aovs {
+                out.write_all(&value.to_le_bytes())?;
+            }
+            out.flush()?;
+        }
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Checkpoint to continue a render with these settings and seed from.
+    /// Refuses one written by a render with other settings.
+    pub fn resume(
+        path: &Path,
+        settings_hash: u64,
+        seed: u64,
+    ) -> Result<Checkpoint, Box<dyn Error>> {
+        let checkpoint = Checkpoint::read(path)?;
+        if checkpoint.settings_hash != settings_hash || checkpoint.seed != seed {
+            return Err(format!(
+                "{} was written by a render with other settings",
+                path.display()
+            )
+            .into());
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn read(path: &Path) -> Result<Checkpoint, Box<dyn Error>> {
+        let mut input = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not a checkpoint, or from another version".into());
+        }
+
+        let mut word = [0; 8];
+        let mut half = [0; 4];
+        input.read_exact(&mut word)?;
+        let settings_hash = u64::from_le_bytes(word);
+        input.read_exact(&mut half)?;
+        let width = u32::from_le_bytes(half);
+        input.read_exact(&mut half)?;
+        let height = u32::from_le_bytes(half);
+        input.read_exact(&mut word)?;
+        let seed = u64::from_le_bytes(word);
+
+        let pixels = (0..u64::from(width) * u64::from(height))
+            .map(|_| FilmPixel::read_from(&mut input))
+            .collect::<Result<_, _>>()?;
+        input.read_exact(&mut word)?;
+        let aovs = (0..u64::from_le_bytes(word))
+            .map(|_| {
+                input.read_exact(&mut word)?;
+                Ok(f64::from_le_bytes(word))
+            })
+            .collect::<Result<_, std::io::Error>>()?;
+
+        Ok(Checkpoint {
+            settings_hash,
+            width,
+            height,
+            seed,
+            pixels,
+            aovs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{Film, Filter, Vec3};
+
+    /// Checkpoint of a small film with a few samples, in a file of the temporary directory
+    fn checkpoint(name: &str) -> (Checkpoint, std::path::PathBuf) {
+        let film = Film::new(6, 4, Filter::Box { radius: 0.5 }, None).with_aov_channels(2);
+        let crop = film.crop();
+        let mut tile = film.tile(&crop);
+        for y in 0..4 {
+            for x in 0..6 {
+                let color = Vec3::new(f64::from(x), f64::from(y), 0.25);
+                let (film_x, film_y) = (f64::from(x) + 0.3, f64::from(y) + 0.6);
+                tile.add_sample(x, y, film_x, film_y, color, &[0.5, f64::from(x * y)]);
+                tile.add_sample(x, y, film_x, film_y, 2.0 * color, &[1.5, 0.0]);
+            }
+        }
+        film.merge_tile(tile);
+        let checkpoint = Checkpoint {
+            settings_hash: settings_hash("6x4 box filter"),
+            width: crop.width(),
+            height: crop.height(),
+            seed: 7,
+            pixels: film.pixels(),
+            aovs: film.aovs(),
+        };
+        let path = std::env::temp_dir().join(format!("{}_{}.rtc", name, std::process::id()));
+        (checkpoint, path)
+    }
+
+    fn bytes(checkpoint: &Checkpoint) -> Vec<u8> {
+        let mut bytes = vec![];
+        for pixel in &checkpoint.pixels {
+            pixel.write_to(&mut bytes).unwrap();
+        }
+        for value in &checkpoint.aovs {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn film_is_read_back() {
+        let (written, path) = checkpoint("film_is_read_back");
+        written.write(&path).unwrap();
+        let read = Checkpoint::resume(&path, written.settings_hash, written.seed).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((read.width, read.height), (6, 4));
+        assert_eq!(read.aovs.len(), 6 * 4 * 2);
+        assert_eq!(bytes(&read), bytes(&written));
+    }
+
+    #[test]
+    fn other_settings_are_refused() {
+        let (written, path) = checkpoint("other_settings_are_refused");
+        written.write(&path).unwrap();
+        let other_settings = Checkpoint::resume(&path, settings_hash("6x4 tent filter"), 7);
+        let other_seed = Checkpoint::resume(&path, written.settings_hash, 8);
+        fs::remove_file(&path).unwrap();
+        assert!(other_settings.is_err());
+        assert!(other_seed.is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use super::Vec3;
+
 #[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
 pub struct Color {
     pub r: u8,
@@ -22,3 +24,13 @@ impl Color {
         }
     }
 }
+
+/// False color from black at 0 through red and yellow to white at 1
+pub fn heat_color(t: f64) -> Vec3 {
+    let t = t.clamp(0.0, 1.0);
+    Vec3::new(
+        (3.0 * t).min(1.0),
+        (3.0 * t - 1.0).clamp(0.0, 1.0),
+        (3.0 * t - 2.0).max(0.0),
+    )
+}
@@ -0,0 +1,361 @@
+use super::{heat_color, luminance, Image, PixelFormat, Vec3};
+
+/// Differences between an image and a reference of the same size.
+/// Channels are taken as stored, from 0 to 1.
+#[derive(Debug, Copy, Clone)]
+pub struct ImageErrors {
+    /// Mean squared error of the channels
+    pub mse: f64,
+    /// Root mean squared error, in the units of the channels
+    pub rmse: f64,
+    /// Squared errors relative to the squared reference, so dark areas count as much as bright ones
+    pub relative_mse: f64,
+    /// Peak signal to noise ratio in dB, infinite for identical images
+    pub psnr: f64,
+    /// Mean structural similarity of the luminance, 1 for identical images
+    pub ssim: f64,
+    /// Mean perceptual error from 0 to 1, see `Image::error_map`
+    pub flip: f64,
+}
+
+/// Reference white of the sRGB primaries (D65)
+const WHITE: Vec3 = Vec3 {
+    x: 0.950_456,
+    y: 1.0,
+    z: 1.088_754,
+};
+
+fn linear_rgb_to_xyz(c: &Vec3) -> Vec3 {
+    Vec3::new(
+        0.412_456_4 * c.x + 0.357_576_1 * c.y + 0.180_437_5 * c.z,
+        0.212_672_9 * c.x + 0.715_152_2 * c.y + 0.072_175_0 * c.z,
+        0.019_333_9 * c.x + 0.119_192_0 * c.y + 0.950_304_1 * c.z,
+    )
+}
+
+fn xyz_to_linear_rgb(c: &Vec3) -> Vec3 {
+    Vec3::new(
+        3.240_454_2 * c.x - 1.537_138_5 * c.y - 0.498_531_4 * c.z,
+        -0.969_266_0 * c.x + 1.876_010_8 * c.y + 0.041_556_0 * c.z,
+        0.055_643_4 * c.x - 0.204_025_9 * c.y + 1.057_225_2 * c.z,
+    )
+}
+
+/// Opponent color space where the eye's contrast sensitivity is applied,
+/// linear in XYZ unlike L*a*b*
+fn xyz_to_ycxcz(c: &Vec3) -> Vec3 {
+    let (x, y, z) = (c.x / WHITE.x, c.y / WHITE.y, c.z / WHITE.z);
+    Vec3::new(116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+}
+
+fn ycxcz_to_xyz(c: &Vec3) -> Vec3 {
+    let y = (c.x + 16.0) / 116.0;
+    Vec3::new(
+        (c.y / 500.0 + y) * WHITE.x,
+        y * WHITE.y,
+        (y - c.z / 200.0) * WHITE.z,
+    )
+}
+
+fn xyz_to_lab(c: &Vec3) -> Vec3 {
+    let f = |t: f64| {
+        let delta: f64 = 6.0 / 29.0;
+        if t > delta.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * delta * delta) + 4.0 / 29.0
+        }
+    };
+    let (x, y, z) = (f(c.x / WHITE.x), f(c.y / WHITE.y), f(c.z / WHITE.z));
+    Vec3::new(116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+}
+
+/// Distance in L*a*b* suited to large differences:
+/// lightness and chroma differences add up instead of forming a Euclidean distance
+fn hyab(a: &Vec3, b: &Vec3) -> f64 {
+    (a.x - b.x).abs() + ((a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+/// Separable Gaussian blur of a single channel, taps outside the image are dropped
+fn gaussian_blur(values: &[f64], width: usize, height: usize, sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil() as isize;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|i| (-(i * i) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let pass = |values: &[f64], horizontal: bool| -> Vec<f64> {
+        let mut blurred = vec![0.0; values.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let (mut sum, mut weight_sum) = (0.0, 0.0);
+                for (k, weight) in kernel.iter().enumerate() {
+                    let offset = k as isize - radius;
+                    let (qx, qy) = if horizontal {
+                        (x as isize + offset, y as isize)
+                    } else {
+                        (x as isize, y as isize + offset)
+                    };
+                    if qx < 0 || qy < 0 || qx >= width as isize || qy >= height as isize {
+                        continue;
+                    }
+                    sum += weight * values[qy as usize * width + qx as usize];
+                    weight_sum += weight;
+                }
+                blurred[y * width + x] = sum / weight_sum;
+            }
+        }
+        blurred
+    };
+    pass(&pass(values, true), false)
+}
+
+/// Correlation of the values around each pixel with a 3x3 kernel, zero outside the image
+fn convolve3(values: &[f64], width: usize, height: usize, kernel: &[[f64; 3]; 3]) -> Vec<f64> {
+    let mut result = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = 0.0;
+            for (ky, row) in kernel.iter().enumerate() {
+                for (kx, weight) in row.iter().enumerate() {
+                    let (qx, qy) = (x + kx, y + ky);
+                    if qx < 1 || qy < 1 || qx > width || qy > height {
+                        continue;
+                    }
+                    sum += weight * values[(qy - 1) * width + qx - 1];
+                }
+            }
+            result[y * width + x] = sum;
+        }
+    }
+    result
+}
+
+impl Image {
+    fn colors(&self) -> Vec<Vec3> {
+        self.get_pixels()
+            .chunks(3)
+            .map(|c| Vec3::new(c[0], c[1], c[2]))
+            .collect()
+    }
+
+    fn assert_same_size(&self, reference: &Image) {
+        assert_eq!(
+            (self.width(), self.height()),
+            (reference.width(), reference.height()),
+            "images of different sizes"
+        );
+    }
+
+    /// Error metrics of the image against a reference.
+    /// Images without pixels don't differ, they get the metrics of identical images.
+    pub fn compare(&self, reference: &Image) -> ImageErrors {
+        self.assert_same_size(reference);
+        if self.get_pixels().is_empty() {
+            return ImageErrors {
+                mse: 0.0,
+                rmse: 0.0,
+                relative_mse: 0.0,
+                psnr: f64::INFINITY,
+                ssim: 1.0,
+                flip: 0.0,
+            };
+        }
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let (a, b) = (self.get_pixels(), reference.get_pixels());
+        let count = a.len() as f64;
+
+        let mse = a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>() / count;
+        let relative_mse = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| (a - b).powi(2) / (b * b + 0.01))
+            .sum::<f64>()
+            / count;
+        let psnr = if mse == 0.0 {
+            f64::INFINITY
+        } else {
+            -10.0 * mse.log10()
+        };
+
+        // Means, variances and covariance over 11x11 Gaussian windows (Wang et al. 2004)
+        let y_a: Vec<f64> = self.colors().iter().map(luminance).collect();
+        let y_b: Vec<f64> = reference.colors().iter().map(luminance).collect();
+        let blur = |values: Vec<f64>| gaussian_blur(&values, width, height, 1.5);
+        let mean_a = blur(y_a.clone());
+        let mean_b = blur(y_b.clone());
+        let square_a = blur(y_a.iter().map(|y| y * y).collect());
+        let square_b = blur(y_b.iter().map(|y| y * y).collect());
+        let product = blur(y_a.iter().zip(&y_b).map(|(a, b)| a * b).collect());
+        let (c1, c2) = (0.01f64.powi(2), 0.03f64.powi(2));
+        let ssim = (0..width * height)
+            .map(|i| {
+                let (ma, mb) = (mean_a[i], mean_b[i]);
+                let variance_a = square_a[i] - ma * ma;
+                let variance_b = square_b[i] - mb * mb;
+                let covariance = product[i] - ma * mb;
+                ((2.0 * ma * mb + c1) * (2.0 * covariance + c2))
+                    / ((ma * ma + mb * mb + c1) * (variance_a + variance_b + c2))
+            })
+            .sum::<f64>()
+            / (width * height) as f64;
+
+        let errors = self.error_map(reference);
+        let flip = errors.iter().sum::<f64>() / errors.len() as f64;
+
+        ImageErrors {
+            mse,
+            rmse: mse.sqrt(),
+            relative_mse,
+            psnr,
+            ssim,
+            flip,
+        }
+    }
+
+    /// Perceptual error of each pixel from 0 to 1, top row first.
+    /// A simplified take on FLIP (Andersson et al. 2020) for images seen at about 67 pixels
+    /// per degree: colors are blurred as the eye does, compared in L*a*b*,
+    /// and errors on edges and points count more.
+    /// Channels are taken as square root gamma encoded, as the renders are written.
+    pub fn error_map(&self, reference: &Image) -> Vec<f64> {
+        self.assert_same_size(reference);
+        let (width, height) = (self.width() as usize, self.height() as usize);
+
+        // Color pipeline, the eye blurs chromatic contrast more than achromatic one
+        let filtered = |image: &Image| -> Vec<Vec3> {
+            let opponent: Vec<Vec3> = image
+                .colors()
+                .iter()
+                .map(|c| xyz_to_ycxcz(&linear_rgb_to_xyz(&(c * c))))
+                .collect();
+            let channel = |c: usize, sigma: f64| {
+                let values: Vec<f64> = opponent.iter().map(|o| o[c]).collect();
+                gaussian_blur(&values, width, height, sigma)
+            };
+            let (y, cx, cz) = (channel(0, 0.5), channel(1, 1.5), channel(2, 1.5));
+            (0..width * height)
+                .map(|i| {
+                    let rgb = xyz_to_linear_rgb(&ycxcz_to_xyz(&Vec3::new(y[i], cx[i], cz[i])));
+                    let rgb = Vec3::new(
+                        rgb.x.clamp(0.0, 1.0),
+                        rgb.y.clamp(0.0, 1.0),
+                        rgb.z.clamp(0.0, 1.0),
+                    );
+                    xyz_to_lab(&linear_rgb_to_xyz(&rgb))
+                })
+                .collect()
+        };
+        let (lab_a, lab_b) = (filtered(self), filtered(reference));
+        // Largest difference between two colors of the gamut, green and blue
+        let green = xyz_to_lab(&linear_rgb_to_xyz(&Vec3::new(0.0, 1.0, 0.0)));
+        let blue = xyz_to_lab(&linear_rgb_to_xyz(&Vec3::new(0.0, 0.0, 1.0)));
+        let max_difference = hyab(&green, &blue).powf(0.7);
+
+        // Feature pipeline, edges and points of the achromatic channel
+        let features = |image: &Image| -> (Vec<f64>, Vec<f64>) {
+            let lightness: Vec<f64> = image
+                .colors()
+                .iter()
+                .map(|c| xyz_to_lab(&linear_rgb_to_xyz(&(c * c))).x / 100.0)
+                .collect();
+            let sobel_x = [[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]];
+            let sobel_y = [[-1.0, -2.0, -1.0], [0.0, 0.0, 0.0], [1.0, 2.0, 1.0]];
+            let laplacian = [[0.0, 1.0, 0.0], [1.0, -4.0, 1.0], [0.0, 1.0, 0.0]];
+            let gx = convolve3(&lightness, width, height, &sobel_x);
+            let gy = convolve3(&lightness, width, height, &sobel_y);
+            let edges = gx
+                .iter()
+                .zip(&gy)
+                .map(|(x, y)| (x * x + y * y).sqrt() / 4.0)
+                .collect();
+            let points = convolve3(&lightness, width, height, &laplacian)
+                .iter()
+                .map(|p| p.abs() / 4.0)
+                .collect();
+            (edges, points)
+        };
+        let (edges_a, points_a) = features(self);
+        let (edges_b, points_b) = features(reference);
+
+        (0..width * height)
+            .map(|i| {
+                let color = (hyab(&lab_a[i], &lab_b[i]).powf(0.7) / max_difference).min(1.0);
+                let feature = ((edges_a[i] - edges_b[i])
+                    .abs()
+                    .max((points_a[i] - points_b[i]).abs())
+                    / 2f64.sqrt())
+                .min(1.0)
+                .sqrt();
+                color.powf(1.0 - feature)
+            })
+            .collect()
+    }
+
+    /// False color map of the perceptual error of each pixel, black where the images match
+    pub fn difference_image(&self, reference: &Image) -> Image {
+        let mut buffer = Vec::with_capacity(self.get_pixels().len());
+        for error in self.error_map(reference) {
+            let color = heat_color(error);
+            buffer.push(color.x);
+            buffer.push(color.y);
+            buffer.push(color.z);
+        }
+        Image::from(self.width(), self.height(), PixelFormat::RGBU8, &buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u32 = 24;
+    const HEIGHT: u32 = 16;
+
+    fn image(pixel: impl Fn(u32, u32) -> f64) -> Image {
+        let mut buffer = vec![];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                buffer.extend(&[pixel(x, y); 3]);
+            }
+        }
+        Image::from(WIDTH, HEIGHT, PixelFormat::RGBU8, &buffer)
+    }
+
+    #[test]
+    fn identical_images() {
+        let a = image(|x, y| f64::from((x * 7 + y * 13) % 10) / 10.0);
+        let errors = a.compare(&a);
+        assert_eq!(errors.mse, 0.0);
+        assert_eq!(errors.psnr, f64::INFINITY);
+        assert!((errors.ssim - 1.0).abs() < 1e-9, "{}", errors.ssim);
+        assert_eq!(errors.flip, 0.0);
+    }
+
+    #[test]
+    fn offset_images() {
+        let errors = image(|_, _| 0.6).compare(&image(|_, _| 0.5));
+        assert!((errors.mse - 0.01).abs() < 1e-12);
+        assert!((errors.rmse - 0.1).abs() < 1e-12);
+        assert!((errors.psnr - 20.0).abs() < 1e-9);
+        // Only the luminance term is left on flat images: 2ab / (a² + b²), stabilized
+        let c1 = 0.01f64.powi(2);
+        let expected = (2.0 * 0.6 * 0.5 + c1) / (0.6 * 0.6 + 0.5 * 0.5 + c1);
+        assert!((errors.ssim - expected).abs() < 1e-9, "{}", errors.ssim);
+
+        // Half of the pixels off by 0.2
+        let errors =
+            image(|x, _| if x < WIDTH / 2 { 0.7 } else { 0.5 }).compare(&image(|_, _| 0.5));
+        assert!((errors.mse - 0.02).abs() < 1e-12);
+        assert!((errors.psnr - 10.0 * 50f64.log10()).abs() < 1e-9);
+        assert!(errors.ssim < 1.0 && errors.flip > 0.0);
+    }
+
+    #[test]
+    fn empty_images() {
+        let empty = Image::new(0, 0, PixelFormat::RGBU8);
+        let errors = empty.compare(&empty);
+        assert_eq!(errors.mse, 0.0);
+        assert_eq!(errors.psnr, f64::INFINITY);
+        assert_eq!(errors.ssim, 1.0);
+        assert_eq!(errors.flip, 0.0);
+    }
+}
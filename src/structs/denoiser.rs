@@ -0,0 +1,201 @@
+use super::{dot, luminance, unit_vector, AovImage, Image, PixelFormat, Vec3};
+
+/// Edge-avoiding à-trous wavelet filter.
+/// Each pass blurs the image with a 5x5 B3 spline kernel whose taps spread twice as far
+/// as in the previous pass, weighted down across edges of the albedo, normal and depth
+/// of the surfaces, and across differences of luminance larger than the noise.
+/// Textures are divided out before filtering and put back afterwards.
+#[derive(Debug, Copy, Clone)]
+pub struct Denoiser {
+    /// Passes of the filter, 5 passes reach 62 pixels away
+    pub iterations: u32,
+    /// How much luminance may differ, in standard deviations of the noise of the difference:
+    /// the weight of a neighbour is `exp(-|lp - lq| / (strength * sqrt(vp + vq)))`
+    pub luminance_strength: f64,
+    /// Exponent of the cosine between normals, higher keeps creases sharper:
+    /// the weight of a neighbour is `max(0, np·nq)^sharpness`
+    pub normal_sharpness: f64,
+    /// How much depth may differ, as a fraction of the depth of the farther pixel:
+    /// the weight of a neighbour is `exp(-|dp - dq| / (strength * max(dp, dq)))`
+    pub depth_strength: f64,
+    /// How much albedo may differ:
+    /// the weight of a neighbour is `exp(-|ap - aq|² / strength²)`
+    pub albedo_strength: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Denoiser {
+        Denoiser {
+            iterations: 5,
+            luminance_strength: 4.0,
+            normal_sharpness: 128.0,
+            depth_strength: 0.1,
+            albedo_strength: 0.1,
+        }
+    }
+}
+
+/// Features of the pixels steering the denoiser, top row first
+pub struct DenoiseGuides {
+    pub albedo: Vec<Vec3>,
+    pub normal: Vec<Vec3>,
+    pub depth: Vec<f64>,
+    /// Variance of the estimate of each pixel
+    pub variance: Vec<f64>,
+}
+
+impl DenoiseGuides {
+    /// Guides from the albedo, normal and depth channels of AOVs, None when one is missing
+    pub fn from_aovs(aovs: &AovImage, variance: Vec<f64>) -> Option<DenoiseGuides> {
+        let vectors = |names: [&str; 3]| -> Option<Vec<Vec3>> {
+            let x = aovs.channel(names[0])?;
+            let y = aovs.channel(names[1])?;
+            let z = aovs.channel(names[2])?;
+            Some(
+                (0..x.len())
+                    .map(|i| Vec3::new(f64::from(x[i]), f64::from(y[i]), f64::from(z[i])))
+                    .collect(),
+            )
+        };
+        Some(DenoiseGuides {
+            albedo: vectors(["albedo.R", "albedo.G", "albedo.B"])?,
+            normal: vectors(["N.X", "N.Y", "N.Z"])?,
+            depth: aovs.channel("Z")?.iter().map(|z| f64::from(*z)).collect(),
+            variance,
+        })
+    }
+}
+
+/// Weights of the taps of the B3 spline kernel
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Albedo the color of a pixel is divided by, black channels are left alone
+fn demodulation(albedo: &Vec3) -> Vec3 {
+    let channel = |a: f64| if a > 0.01 { a } else { 1.0 };
+    Vec3::new(channel(albedo.x), channel(albedo.y), channel(albedo.z))
+}
+
+/// 3x3 Gaussian blur, steadies the variance estimated from few samples
+fn blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    const WEIGHTS: [f64; 3] = [0.25, 0.5, 0.25];
+    let mut blurred = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let (mut sum, mut weight_sum) = (0.0, 0.0);
+            for (dy, wy) in WEIGHTS.iter().enumerate() {
+                for (dx, wx) in WEIGHTS.iter().enumerate() {
+                    let (qx, qy) = (x + dx, y + dy);
+                    if qx < 1 || qy < 1 || qx > width || qy > height {
+                        continue;
+                    }
+                    sum += wx * wy * values[(qy - 1) * width + qx - 1];
+                    weight_sum += wx * wy;
+                }
+            }
+            blurred[y * width + x] = sum / weight_sum;
+        }
+    }
+    blurred
+}
+
+impl Denoiser {
+    /// Denoise a linear image with the guides of its pixels
+    pub fn denoise(&self, image: &Image, guides: &DenoiseGuides) -> Image {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let count = width * height;
+        assert_eq!(guides.albedo.len(), count);
+        assert_eq!(guides.normal.len(), count);
+        assert_eq!(guides.depth.len(), count);
+        assert_eq!(guides.variance.len(), count);
+
+        let pixels = image.get_pixels();
+        let albedo: Vec<Vec3> = guides.albedo.iter().map(demodulation).collect();
+        let mut color: Vec<Vec3> = (0..count)
+            .map(|i| Vec3::new(pixels[3 * i], pixels[3 * i + 1], pixels[3 * i + 2]) / albedo[i])
+            .collect();
+        let mut variance: Vec<f64> = (0..count)
+            .map(|i| guides.variance[i] / luminance(&albedo[i]).powi(2))
+            .collect();
+
+        for iteration in 0..self.iterations {
+            let step = 1 << iteration;
+            let blurred_variance = blur(&variance, width, height);
+            let mut filtered = vec![Vec3::default(); count];
+            let mut filtered_variance = vec![0.0; count];
+            for y in 0..height {
+                for x in 0..width {
+                    let p = y * width + x;
+                    let luminance_p = luminance(&color[p]);
+                    let mut sum = Vec3::default();
+                    let mut weight_sum = 0.0;
+                    let mut variance_sum = 0.0;
+                    for (ky, hy) in KERNEL.iter().enumerate() {
+                        let qy = y as isize + (ky as isize - 2) * step;
+                        if qy < 0 || qy >= height as isize {
+                            continue;
+                        }
+                        for (kx, hx) in KERNEL.iter().enumerate() {
+                            let qx = x as isize + (kx as isize - 2) * step;
+                            if qx < 0 || qx >= width as isize {
+                                continue;
+                            }
+                            let q = qy as usize * width + qx as usize;
+                            let mut weight = hx * hy;
+                            if q != p {
+                                // The difference of two noisy pixels is noisier than either
+                                let deviation = self.luminance_strength
+                                    * (blurred_variance[p] + blurred_variance[q]).sqrt()
+                                    + 1e-6;
+                                weight *= self.edge_weight(p, q, guides)
+                                    * (-(luminance_p - luminance(&color[q])).abs() / deviation)
+                                        .exp();
+                            }
+                            sum += weight * color[q];
+                            weight_sum += weight;
+                            variance_sum += weight * weight * variance[q];
+                        }
+                    }
+                    // The center tap always weighs its share of the kernel
+                    filtered[p] = sum / weight_sum;
+                    filtered_variance[p] = variance_sum / (weight_sum * weight_sum);
+                }
+            }
+            color = filtered;
+            variance = filtered_variance;
+        }
+
+        let mut buffer = Vec::with_capacity(count * 3);
+        for (col, albedo) in color.iter().zip(&albedo) {
+            let col = col * albedo;
+            buffer.push(col.x);
+            buffer.push(col.y);
+            buffer.push(col.z);
+        }
+        Image::from(image.width(), image.height(), PixelFormat::RGBU8, &buffer)
+    }
+
+    /// How much the pixel q may contribute to the pixel p, judging by their surfaces
+    fn edge_weight(&self, p: usize, q: usize, guides: &DenoiseGuides) -> f64 {
+        let (normal_p, normal_q) = (guides.normal[p], guides.normal[q]);
+        // The background has no normal
+        let normal = match (normal_p == Vec3::default(), normal_q == Vec3::default()) {
+            (true, true) => 1.0,
+            // Filtered normals shorten where surfaces meet
+            (false, false) => dot(unit_vector(normal_p), unit_vector(normal_q))
+                .max(0.0)
+                .powf(self.normal_sharpness),
+            _ => 0.0,
+        };
+
+        let (depth_p, depth_q) = (guides.depth[p], guides.depth[q]);
+        let depth = (-(depth_p - depth_q).abs()
+            / (self.depth_strength * depth_p.max(depth_q) + 1e-6))
+            .exp();
+
+        let albedo = (-(guides.albedo[p] - guides.albedo[q]).squared_length()
+            / (self.albedo_strength * self.albedo_strength))
+            .exp();
+
+        normal * depth * albedo
+    }
+}
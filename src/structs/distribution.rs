@@ -0,0 +1,162 @@
+/// Piecewise constant 1D distribution on [0,1),
+/// used to sample proportionally to a tabulated function
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: &[f64]) -> Distribution1D {
+        let n = func.len();
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for i in 0..n {
+            cdf.push(cdf[i] + func[i].abs() / n as f64);
+        }
+
+        let integral = cdf[n];
+        if integral == 0.0 {
+            // Nothing to favour, sample uniformly
+            for (i, c) in cdf.iter_mut().enumerate() {
+                *c = i as f64 / n as f64;
+            }
+        } else {
+            for c in cdf.iter_mut() {
+                *c /= integral;
+            }
+        }
+
+        Distribution1D {
+            func: func.iter().map(|f| f.abs()).collect(),
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Map u in [0,1) to a sample in [0,1).
+    /// Return the sample, its density and the index of its segment.
+    pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+        // Last cdf entry lower or equal to u
+        let index = (self.cdf.partition_point(|c| *c <= u) - 1).min(self.count() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let du = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+
+        let x = (index as f64 + du) / self.count() as f64;
+        (x, self.pdf_at(index), index)
+    }
+
+    /// Density of the segment containing x
+    pub fn pdf(&self, x: f64) -> f64 {
+        let index = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.pdf_at(index)
+    }
+
+    fn pdf_at(&self, index: usize) -> f64 {
+        if self.integral == 0.0 {
+            1.0
+        } else {
+            self.func[index] / self.integral
+        }
+    }
+}
+
+/// Piecewise constant 2D distribution on [0,1)²,
+/// sampled with a marginal distribution on v and a conditional one on u
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// func holds the width * height values, row by row
+    pub fn new(func: &[f64], width: usize, height: usize) -> Distribution2D {
+        let conditional: Vec<Distribution1D> = func
+            .chunks(width)
+            .take(height)
+            .map(Distribution1D::new)
+            .collect();
+        let marginal: Vec<f64> = conditional.iter().map(|d| d.integral()).collect();
+
+        Distribution2D {
+            conditional,
+            marginal: Distribution1D::new(&marginal),
+        }
+    }
+
+    /// Map a pair of numbers in [0,1) to a sample (u,v) and its density
+    pub fn sample(&self, u0: f64, u1: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample_continuous(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample_continuous(u0);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row = ((v * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        self.marginal.pdf(v) * self.conditional[row].pdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A multiple of every segment count, the midpoint rule is then exact
+    const STEPS: usize = 1200;
+
+    /// Midpoint rule over [0, 1)
+    fn integrate(f: impl Fn(f64) -> f64) -> f64 {
+        (0..STEPS)
+            .map(|i| f((i as f64 + 0.5) / STEPS as f64))
+            .sum::<f64>()
+            / STEPS as f64
+    }
+
+    #[test]
+    fn pdf_1d_integrates_to_one() {
+        for func in &[vec![1.0, 3.0, 0.0, 2.0, 0.5], vec![0.0; 4], vec![-2.0, 1.0]] {
+            let distribution = Distribution1D::new(func);
+            assert!((integrate(|x| distribution.pdf(x)) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn samples_1d_follow_the_pdf() {
+        let distribution = Distribution1D::new(&[1.0, 3.0, 0.0, 2.0, 0.5]);
+        for i in 0..STEPS {
+            let (x, pdf, index) = distribution.sample_continuous(i as f64 / STEPS as f64);
+            assert!((0.0..1.0).contains(&x));
+            assert_eq!(index, (x * 5.0) as usize);
+            assert_eq!(pdf, distribution.pdf(x));
+            // Never lands in the empty segment
+            assert_ne!(index, 2);
+        }
+    }
+
+    #[test]
+    fn pdf_2d_integrates_to_one() {
+        let func: Vec<f64> = (0..12).map(|i| f64::from(i * i % 7)).collect();
+        let distribution = Distribution2D::new(&func, 4, 3);
+        let integral = integrate(|v| integrate(|u| distribution.pdf(u, v)));
+        assert!((integral - 1.0).abs() < 1e-9, "{}", integral);
+        for i in 0..100 {
+            let (u0, u1) = ((i % 10) as f64 / 10.0, (i / 10) as f64 / 10.0);
+            let ((u, v), pdf) = distribution.sample(u0, u1);
+            assert!((pdf - distribution.pdf(u, v)).abs() < 1e-9);
+            assert!(pdf > 0.0);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use super::{AovImage, Filter, Image, PixelFormat, RunningStats, Vec3};
+use crate::scheduler::Tile;
+
+use std::io::{self, Read, Write};
+use std::sync::Mutex;
+
+/// Part of the image to render, as fractions of its width and height from the top left corner
+#[derive(Debug, Copy, Clone)]
+pub struct CropWindow {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+/// What the film knows of a pixel
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FilmPixel {
+    /// Samples taken through the pixel itself, unfiltered
+    pub stats: RunningStats,
+    /// Sum of the samples in reach of the filter, weighted by it
+    pub weighted_sum: Vec3,
+    pub weight_sum: f64,
+}
+
+impl FilmPixel {
+    /// Filtered estimate, the filter may have negative lobes
+    pub fn color(&self) -> Vec3 {
+        if self.weight_sum == 0.0 {
+            return Vec3::default();
+        }
+        self.weighted_sum / self.weight_sum
+    }
+
+    /// Little endian binary encoding, read back by `read_from`
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        self.stats.write_to(out)?;
+        for value in &[
+            self.weighted_sum.x,
+            self.weighted_sum.y,
+            self.weighted_sum.z,
+            self.weight_sum,
+        ] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(input: &mut R) -> io::Result<FilmPixel> {
+        let stats = RunningStats::read_from(input)?;
+        let mut values = [0.0; 4];
+        for value in values.iter_mut() {
+            let mut bytes = [0; 8];
+            input.read_exact(&mut bytes)?;
+            *value = f64::from_le_bytes(bytes);
+        }
+        Ok(FilmPixel {
+            stats,
+            weighted_sum: Vec3::new(values[0], values[1], values[2]),
+            weight_sum: values[3],
+        })
+    }
+}
+
+/// Accumulates the samples of an image, shared by the threads rendering its tiles.
+/// Each sample is splatted with the filter onto the pixels around it,
+/// with the AOV channels it carries.
+/// Coordinates are in pixels of the full image, from its top left corner.
+pub struct Film {
+    full_width: u32,
+    full_height: u32,
+    /// Pixels kept by the film, the whole image without crop window
+    crop: Tile,
+    filter: Filter,
+    aov_channels: usize,
+    buffers: Mutex<Buffers>,
+}
+
+/// Pixels of the crop, top row first
+struct Buffers {
+    pixels: Vec<FilmPixel>,
+    // weighted sums of the AOV channels, aov_channels values per pixel
+    aovs: Vec<f64>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32, filter: Filter, crop: Option<CropWindow>) -> Film {
+        let crop = match crop {
+            Some(window) => {
+                let x0 = (f64::from(width) * window.x0.clamp(0.0, 1.0)).ceil() as u32;
+                let y0 = (f64::from(height) * window.y0.clamp(0.0, 1.0)).ceil() as u32;
+                let x1 = (f64::from(width) * window.x1.clamp(0.0, 1.0)).ceil() as u32;
+                let y1 = (f64::from(height) * window.y1.clamp(0.0, 1.0)).ceil() as u32;
+                assert!(x0 < x1 && y0 < y1, "empty crop window {:?}", window);
+                Tile { x0, y0, x1, y1 }
+            }
+            None => Tile {
+                x0: 0,
+                y0: 0,
+                x1: width,
+                y1: height,
+            },
+        };
+        Film {
+            full_width: width,
+            full_height: height,
+            crop,
+            filter,
+            aov_channels: 0,
+            buffers: Mutex::new(Buffers {
+                pixels: vec![FilmPixel::default(); crop.pixel_count()],
+                aovs: vec![],
+            }),
+        }
+    }
+
+    /// Also accumulate count AOV channels carried by each sample
+    pub fn with_aov_channels(self, count: usize) -> Film {
+        let pixel_count = self.crop.pixel_count();
+        Film {
+            aov_channels: count,
+            buffers: Mutex::new(Buffers {
+                pixels: vec![FilmPixel::default(); pixel_count],
+                aovs: vec![0.0; pixel_count * count],
+            }),
+            ..self
+        }
+    }
+
+    pub fn aov_channels(&self) -> usize {
+        self.aov_channels
+    }
+
+    /// Size of the whole image, cameras map it to their field of view
+    pub fn full_width(&self) -> u32 {
+        self.full_width
+    }
+
+    pub fn full_height(&self) -> u32 {
+        self.full_height
+    }
+
+    /// Pixels of the image the film keeps
+    pub fn crop(&self) -> Tile {
+        self.crop
+    }
+
+    /// Continue from the pixels and AOV channels of an earlier render of the same crop
+    pub fn restore(&mut self, pixels: Vec<FilmPixel>, aovs: Vec<f64>) {
+        assert_eq!(pixels.len(), self.crop.pixel_count());
+        assert_eq!(aovs.len(), pixels.len() * self.aov_channels);
+        self.buffers = Mutex::new(Buffers { pixels, aovs });
+    }
+
+    /// Buffer a thread renders a tile into, samples near its edges reach the neighbour tiles.
+    /// Threads never write into the film itself, their tiles are added by `merge_tile`
+    /// under the lock so the overlapping sums don't depend on the thread timing.
+    pub fn tile(&self, tile: &Tile) -> FilmTile {
+        let reach = self.filter.radius().ceil() as u32;
+        let bounds = Tile {
+            x0: tile.x0.saturating_sub(reach).max(self.crop.x0),
+            y0: tile.y0.saturating_sub(reach).max(self.crop.y0),
+            x1: (tile.x1 + reach).min(self.crop.x1),
+            y1: (tile.y1 + reach).min(self.crop.y1),
+        };
+
+        let buffers = self.buffers.lock().unwrap();
+        let mut stats = Vec::with_capacity(tile.pixel_count());
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                stats.push(buffers.pixels[self.index(x, y)].stats);
+            }
+        }
+        FilmTile {
+            tile: *tile,
+            filter: self.filter,
+            stats,
+            bounds,
+            sums: vec![(Vec3::default(), 0.0); bounds.pixel_count()],
+            aov_channels: self.aov_channels,
+            aov_sums: vec![0.0; bounds.pixel_count() * self.aov_channels],
+        }
+    }
+
+    /// Add what a tile gathered to the film.
+    /// Splats of neighbour tiles overlap, merging tiles in the same order gives the same sums.
+    pub fn merge_tile(&self, film_tile: FilmTile) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let tile = film_tile.tile;
+        for (y, line) in (tile.y0..tile.y1).zip(film_tile.stats.chunks(tile.width() as usize)) {
+            for (x, stats) in (tile.x0..tile.x1).zip(line) {
+                let index = self.index(x, y);
+                buffers.pixels[index].stats = *stats;
+            }
+        }
+
+        let bounds = film_tile.bounds;
+        let channels = self.aov_channels;
+        for (y, line) in (bounds.y0..bounds.y1).zip(film_tile.sums.chunks(bounds.width() as usize))
+        {
+            for (x, (weighted_sum, weight_sum)) in (bounds.x0..bounds.x1).zip(line) {
+                let index = self.index(x, y);
+                let pixel = &mut buffers.pixels[index];
+                pixel.weighted_sum += *weighted_sum;
+                pixel.weight_sum += weight_sum;
+
+                let tile_index = ((y - bounds.y0) * bounds.width() + x - bounds.x0) as usize;
+                let sums = &film_tile.aov_sums[tile_index * channels..(tile_index + 1) * channels];
+                for (sum, value) in buffers.aovs[index * channels..(index + 1) * channels]
+                    .iter_mut()
+                    .zip(sums)
+                {
+                    *sum += value;
+                }
+            }
+        }
+    }
+
+    /// Copy of every pixel of the crop, top row first
+    pub fn pixels(&self) -> Vec<FilmPixel> {
+        self.buffers.lock().unwrap().pixels.clone()
+    }
+
+    /// Copy of the weighted sums of the AOV channels, pixel after pixel
+    pub fn aovs(&self) -> Vec<f64> {
+        self.buffers.lock().unwrap().aovs.clone()
+    }
+
+    /// Linear beauty as R, G and B, followed by the AOV channels with the given names
+    pub fn aov_image(&self, names: &[String]) -> AovImage {
+        assert_eq!(names.len(), self.aov_channels);
+        let buffers = self.buffers.lock().unwrap();
+        let filtered = |pixel: &FilmPixel, sum: f64| {
+            if pixel.weight_sum == 0.0 {
+                0.0
+            } else {
+                (sum / pixel.weight_sum) as f32
+            }
+        };
+
+        let mut channels = vec![];
+        for (c, name) in ["R", "G", "B"].iter().enumerate() {
+            let values = buffers
+                .pixels
+                .iter()
+                .map(|pixel| filtered(pixel, pixel.weighted_sum[c]))
+                .collect();
+            channels.push((name.to_string(), values));
+        }
+        for (c, name) in names.iter().enumerate() {
+            let values = buffers
+                .pixels
+                .iter()
+                .enumerate()
+                .map(|(index, pixel)| filtered(pixel, buffers.aovs[index * self.aov_channels + c]))
+                .collect();
+            channels.push((name.clone(), values));
+        }
+        AovImage {
+            width: self.crop.width(),
+            height: self.crop.height(),
+            channels,
+        }
+    }
+
+    /// Gamma corrected image of the crop.
+    /// Negative filter lobes can dip below black next to bright pixels, they are clamped.
+    pub fn image(&self) -> Image {
+        self.linear_image().gamma_corrected()
+    }
+
+    /// Filtered estimate of the crop, before gamma correction
+    pub fn linear_image(&self) -> Image {
+        let buffers = self.buffers.lock().unwrap();
+        let mut buffer = Vec::with_capacity(buffers.pixels.len() * 3);
+        for pixel in buffers.pixels.iter() {
+            let col = pixel.color();
+            buffer.push(col.x);
+            buffer.push(col.y);
+            buffer.push(col.z);
+        }
+        Image::from(
+            self.crop.width(),
+            self.crop.height(),
+            PixelFormat::RGBU8,
+            &buffer,
+        )
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        ((y - self.crop.y0) * self.crop.width() + x - self.crop.x0) as usize
+    }
+}
+
+/// Samples of a tile, and their splats on the pixels around it, until merged into the film
+pub struct FilmTile {
+    tile: Tile,
+    filter: Filter,
+    // pixels of the tile, top row first
+    stats: Vec<RunningStats>,
+    /// Pixels reached by the filter from the tile
+    bounds: Tile,
+    sums: Vec<(Vec3, f64)>,
+    aov_channels: usize,
+    aov_sums: Vec<f64>,
+}
+
+impl FilmTile {
+    /// Samples taken through the pixel (x, y) of the tile
+    pub fn stats(&self, x: u32, y: u32) -> &RunningStats {
+        &self.stats[((y - self.tile.y0) * self.tile.width() + x - self.tile.x0) as usize]
+    }
+
+    /// Add a sample taken through the pixel (x, y) of the tile, at (film_x, film_y) on the film,
+    /// with the values of its AOV channels
+    pub fn add_sample(
+        &mut self,
+        x: u32,
+        y: u32,
+        film_x: f64,
+        film_y: f64,
+        color: Vec3,
+        aovs: &[f64],
+    ) {
+        assert_eq!(aovs.len(), self.aov_channels);
+        let index = ((y - self.tile.y0) * self.tile.width() + x - self.tile.x0) as usize;
+        self.stats[index].push(color);
+
+        // Pixels whose center is in reach of the filter
+        let radius = self.filter.radius();
+        let bounds = self.bounds;
+        let first_x = ((film_x - 0.5 - radius).ceil().max(0.0) as u32).max(bounds.x0);
+        let last_x = ((film_x - 0.5 + radius).floor().max(0.0) as u32 + 1).min(bounds.x1);
+        let first_y = ((film_y - 0.5 - radius).ceil().max(0.0) as u32).max(bounds.y0);
+        let last_y = ((film_y - 0.5 + radius).floor().max(0.0) as u32 + 1).min(bounds.y1);
+        for py in first_y..last_y {
+            for px in first_x..last_x {
+                let weight = self.filter.evaluate(
+                    film_x - (f64::from(px) + 0.5),
+                    film_y - (f64::from(py) + 0.5),
+                );
+                let index = ((py - bounds.y0) * bounds.width() + px - bounds.x0) as usize;
+                let sum = &mut self.sums[index];
+                sum.0 += weight * color;
+                sum.1 += weight;
+                let channels = self.aov_channels;
+                for (sum, value) in self.aov_sums[index * channels..(index + 1) * channels]
+                    .iter_mut()
+                    .zip(aovs)
+                {
+                    *sum += weight * value;
+                }
+            }
+        }
+    }
+}
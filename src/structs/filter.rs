@@ -0,0 +1,116 @@
+use std::f64::consts::PI;
+
+/// Reconstruction filter weighting a sample by its offset to a pixel center, in pixels
+#[derive(Debug, Copy, Clone)]
+pub enum Filter {
+    /// Every sample in reach counts the same, radius 0.5 only keeps the pixel's own
+    Box { radius: f64 },
+    /// Linear falloff
+    Tent { radius: f64 },
+    /// Gaussian shifted down to reach zero at the radius
+    Gaussian { radius: f64, sigma: f64 },
+    /// Mitchell-Netravali cubic, b = c = 1/3 is the recommended compromise
+    /// between blur and ringing
+    Mitchell { radius: f64, b: f64, c: f64 },
+    /// Sinc windowed by a wider sinc, sharpest and most prone to ringing
+    Lanczos { radius: f64 },
+}
+
+impl Filter {
+    /// Names recognized by `from_name`
+    pub const NAMES: &'static str =
+        "box[:radius], tent[:radius], gaussian[:radius], mitchell[:radius], lanczos[:radius]";
+
+    /// Filter given on the command line, a name optionally followed by `:` and a radius.
+    /// None when the name is unknown or the radius can't be read.
+    pub fn from_name(spec: &str) -> Option<Filter> {
+        let (name, radius) = match spec.split_once(':') {
+            Some((name, radius)) => (name, Some(radius.parse::<f64>().ok()?)),
+            None => (spec, None),
+        };
+        if radius.is_some_and(|radius| radius <= 0.0) {
+            return None;
+        }
+        let filter = match name {
+            "box" => Filter::Box {
+                radius: radius.unwrap_or(0.5),
+            },
+            "tent" => Filter::Tent {
+                radius: radius.unwrap_or(1.0),
+            },
+            "gaussian" => {
+                // Keeps the falloff of the default filter over the radius
+                let radius = radius.unwrap_or(1.5);
+                Filter::Gaussian {
+                    radius,
+                    sigma: radius / 3.0,
+                }
+            }
+            "mitchell" => Filter::Mitchell {
+                radius: radius.unwrap_or(2.0),
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            "lanczos" => Filter::Lanczos {
+                radius: radius.unwrap_or(3.0),
+            },
+            _ => return None,
+        };
+        Some(filter)
+    }
+
+    /// Distance to the pixel center beyond which the weight is zero, on each axis
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Weight of a sample offset by (dx, dy) from the pixel center, may be negative
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => radius - x,
+            Filter::Gaussian { radius, sigma } => {
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => {
+                // The cubic spans [0, 2]
+                let x = 2.0 * x / radius;
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                }
+            }
+            Filter::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
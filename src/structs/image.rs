@@ -1,3 +1,5 @@
+use std::io;
+
 pub enum PixelFormat {
     RGBU8,
 }
@@ -40,6 +42,20 @@ impl Image {
         }
     }
 
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Square root gamma of a linear image, negative values clamped to black
+    pub fn gamma_corrected(&self) -> Image {
+        let pixels: Vec<f64> = self.pixels.iter().map(|p| p.max(0.0).sqrt()).collect();
+        Image::from(self.width, self.height, PixelFormat::RGBU8, &pixels)
+    }
+
     pub fn get_pixels(&self) -> &Vec<f64> {
         &self.pixels
     }
@@ -52,6 +68,15 @@ impl Image {
     }
 }
 
+impl Image {
+    /// Read an image written by `encode`, values above the maximum are clamped to it
+    pub fn decode(text: &str, format: ImageFormat) -> io::Result<Image> {
+        match format {
+            ImageFormat::PPM => decode_ppm(text),
+        }
+    }
+}
+
 pub trait Serializable {
     fn encode(&self, format: ImageFormat) -> String;
 }
@@ -59,7 +84,7 @@ pub trait Serializable {
 impl Serializable for Image {
     fn encode(&self, format: ImageFormat) -> String {
         match format {
-            ImageFormat::PPM => encode_ppm(&self),
+            ImageFormat::PPM => encode_ppm(self),
         }
     }
 }
@@ -97,3 +122,34 @@ fn encode_ppm(image: &Image) -> String {
 
     img
 }
+
+fn decode_ppm(text: &str) -> io::Result<Image> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    // Comments run from # to the end of the line
+    let mut tokens = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(str::split_whitespace);
+    if tokens.next() != Some("P3") {
+        return Err(invalid("not a plain PPM image"));
+    }
+    let mut number = || -> io::Result<u32> {
+        tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| invalid("truncated or malformed PPM image"))
+    };
+    let width = number()?;
+    let height = number()?;
+    let max_value = number()?;
+    if max_value == 0 {
+        return Err(invalid("PPM maximum value is 0"));
+    }
+
+    let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+    for _ in 0..width * height * 3 {
+        let value = number()?.min(max_value);
+        pixels.push(f64::from(value) / f64::from(max_value));
+    }
+    Ok(Image::from(width, height, PixelFormat::RGBU8, &pixels))
+}
@@ -12,16 +12,45 @@ mod resources_manager;
 pub use resources_manager::*;
 mod texture;
 pub use texture::*;
+mod spectrum;
+pub use spectrum::*;
+mod distribution;
+pub use distribution::*;
+mod animation;
+pub use animation::*;
+mod running_stats;
+pub use running_stats::*;
+mod checkpoint;
+pub use checkpoint::*;
+mod filter;
+pub use filter::*;
+mod film;
+pub use film::*;
+mod aov;
+pub use aov::*;
+mod denoiser;
+pub use denoiser::*;
+mod comparison;
+pub use comparison::*;
+mod render_stats;
+pub use render_stats::*;
 
-use rand::Rng;
+use crate::samplers::Sampler;
 
-pub fn random_in_unit_disk() -> Vec3 {
-    let mut rng = rand::thread_rng();
-    loop {
-        let p = Vec3::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), 0.0);
-        if p.squared_length() >= 1.0 {
-            continue;
-        }
-        return p;
+use std::f64::consts::PI;
+
+/// Uniform point in the unit disk, from a single 2D sample.
+/// The concentric mapping keeps the sample's stratification.
+pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return Vec3::default();
     }
+    let (r, theta) = if x.abs() > y.abs() {
+        (x, PI / 4.0 * (y / x))
+    } else {
+        (y, PI / 2.0 - PI / 4.0 * (x / y))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
@@ -1,6 +1,10 @@
 use super::{dot, unit_vector, Vec3};
+use crate::samplers::Pcg32;
 use rand::Rng;
 
+/// Noise is the same from one run to the other
+const PERLIN_SEED: u64 = 0x5eed;
+
 pub struct Perlin {
     random_vectors: Vec<Vec3>,
     perm_x: Vec<usize>,
@@ -10,19 +14,21 @@ pub struct Perlin {
 
 impl Perlin {
     pub fn new(point_count: usize) -> Perlin {
+        let mut rng = Pcg32::new(PERLIN_SEED);
         let mut ranvecs = Vec::with_capacity(point_count);
         for _i in 0..point_count {
-            ranvecs.push(unit_vector(Vec3::random_range(-1.0, 1.0)));
+            ranvecs.push(unit_vector(Vec3::random_range(&mut rng, -1.0, 1.0)));
         }
 
         Perlin {
             random_vectors: ranvecs,
-            perm_x: Perlin::generate_permutations(point_count, point_count),
-            perm_y: Perlin::generate_permutations(point_count, point_count),
-            perm_z: Perlin::generate_permutations(point_count, point_count),
+            perm_x: Perlin::generate_permutations(point_count, point_count, &mut rng),
+            perm_y: Perlin::generate_permutations(point_count, point_count, &mut rng),
+            perm_z: Perlin::generate_permutations(point_count, point_count, &mut rng),
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn noise(&self, point: &Vec3) -> f64 {
         let i = point.x.floor();
         let j = point.y.floor();
@@ -61,24 +67,25 @@ impl Perlin {
         acc.abs()
     }
 
-    fn generate_permutations(capacity: usize, point_count: usize) -> Vec<usize> {
+    fn generate_permutations(capacity: usize, point_count: usize, rng: &mut Pcg32) -> Vec<usize> {
         let mut values = Vec::with_capacity(capacity);
         for i in 0..point_count {
             values.push(i);
         }
-        Perlin::permute(&mut values[..], point_count);
+        Perlin::permute(&mut values[..], point_count, rng);
         values
     }
 
     /// Executes `count` permutations on `ptr` randomly
-    fn permute(ptr: &mut [usize], count: usize) {
+    fn permute(ptr: &mut [usize], count: usize, rng: &mut Pcg32) {
         for i in (1..count - 1).rev() {
-            let target: usize = rand::thread_rng().gen_range(0, i);
+            let target: usize = rng.gen_range(0, i);
             ptr.swap(i, target);
         }
     }
 }
 
+#[allow(clippy::needless_range_loop)]
 fn trilinear_interpolation(u: f64, v: f64, w: f64, c: &[[[Vec3; 2]; 2]; 2]) -> f64 {
     // Smooth
     let uu = u * u * (3.0 - 2.0 * u);
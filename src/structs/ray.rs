@@ -5,6 +5,7 @@ pub struct Ray {
     orig: Vec3,
     dir: Vec3,
     time: f64,
+    wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -13,9 +14,16 @@ impl Ray {
             orig: origin,
             dir: direction,
             time,
+            wavelength: None,
         }
     }
 
+    /// Return the same ray, carrying a single wavelength (nm) for spectral rendering
+    pub fn with_wavelength(mut self, wavelength: f64) -> Ray {
+        self.wavelength = Some(wavelength);
+        self
+    }
+
     pub fn origin(&self) -> Vec3 {
         Vec3 {
             x: self.orig.x,
@@ -36,6 +44,11 @@ impl Ray {
         self.time
     }
 
+    /// Wavelength carried by the ray, None when rendering in RGB
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
     pub fn point_at(&self, t: f64) -> Vec3 {
         self.orig + (self.dir * t)
     }
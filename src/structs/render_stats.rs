@@ -0,0 +1,211 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Events counted while rendering
+#[derive(Debug, Copy, Clone)]
+pub enum Counter {
+    /// Rays leaving the camera, one per path
+    CameraRays,
+    /// Camera and scattered rays traced along the paths
+    PathRays,
+    /// Rays traced toward lights to check they're not occluded
+    ShadowRays,
+    /// BVH nodes whose bounding box was tested
+    BvhNodes,
+    /// Objects stored in the leaves of the BVH tested against a ray
+    PrimitiveTests,
+    /// Paths ended by Russian roulette before reaching the maximum depth
+    RouletteTerminations,
+}
+
+const COUNTER_COUNT: usize = 6;
+
+thread_local! {
+    /// Counters of the current thread, cheap to update from anywhere
+    static COUNTERS: [Cell<u64>; COUNTER_COUNT] = Default::default();
+}
+
+/// Count one event on the current thread
+pub fn count(counter: Counter) {
+    count_many(counter, 1);
+}
+
+/// Count amount events on the current thread
+pub fn count_many(counter: Counter, amount: u64) {
+    COUNTERS.with(|counters| {
+        let cell = &counters[counter as usize];
+        cell.set(cell.get() + amount);
+    });
+}
+
+/// Counters of the current thread since the last call, which resets them
+pub fn take_counters() -> RayCounters {
+    COUNTERS.with(|counters| {
+        let mut taken = [0; COUNTER_COUNT];
+        for (value, cell) in taken.iter_mut().zip(counters.iter()) {
+            *value = cell.replace(0);
+        }
+        RayCounters {
+            camera_rays: taken[Counter::CameraRays as usize],
+            path_rays: taken[Counter::PathRays as usize],
+            shadow_rays: taken[Counter::ShadowRays as usize],
+            bvh_nodes: taken[Counter::BvhNodes as usize],
+            primitive_tests: taken[Counter::PrimitiveTests as usize],
+            roulette_terminations: taken[Counter::RouletteTerminations as usize],
+        }
+    })
+}
+
+/// Events counted by a thread, see `Counter`
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct RayCounters {
+    pub camera_rays: u64,
+    pub path_rays: u64,
+    pub shadow_rays: u64,
+    pub bvh_nodes: u64,
+    pub primitive_tests: u64,
+    pub roulette_terminations: u64,
+}
+
+impl RayCounters {
+    /// Every ray traced, shadow rays included
+    pub fn total_rays(&self) -> u64 {
+        self.path_rays + self.shadow_rays
+    }
+
+    /// Rays traced per path, shadow rays excluded
+    pub fn average_path_length(&self) -> f64 {
+        ratio(self.path_rays, self.camera_rays)
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"camera_rays\": {}, \"path_rays\": {}, \"shadow_rays\": {}, \"total_rays\": {}, \
+             \"bvh_nodes_visited\": {}, \"primitive_tests\": {}, \"average_path_length\": {}, \
+             \"roulette_terminations\": {}}}",
+            self.camera_rays,
+            self.path_rays,
+            self.shadow_rays,
+            self.total_rays(),
+            self.bvh_nodes,
+            self.primitive_tests,
+            self.average_path_length(),
+            self.roulette_terminations
+        )
+    }
+}
+
+impl AddAssign for RayCounters {
+    fn add_assign(&mut self, other: RayCounters) {
+        self.camera_rays += other.camera_rays;
+        self.path_rays += other.path_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.bvh_nodes += other.bvh_nodes;
+        self.primitive_tests += other.primitive_tests;
+        self.roulette_terminations += other.roulette_terminations;
+    }
+}
+
+/// 0 when there's nothing to divide
+fn ratio(a: u64, b: u64) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+/// What a render did and how long it took
+#[derive(Debug, Default, Clone)]
+pub struct RenderStats {
+    /// Counters of each rendering thread
+    pub threads: Vec<RayCounters>,
+    /// Time spent building the BVH
+    pub build_time: Duration,
+    /// Time spent tracing the passes, and finishing the image
+    pub render_time: Duration,
+}
+
+impl RenderStats {
+    /// Counters of all the threads
+    pub fn total(&self) -> RayCounters {
+        let mut total = RayCounters::default();
+        for counters in &self.threads {
+            total += *counters;
+        }
+        total
+    }
+
+    /// Rays traced per second of rendering, shadow rays included
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.render_time.as_secs_f64();
+        if seconds > 0.0 {
+            self.total().total_rays() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// The report as a JSON object
+    pub fn to_json(&self) -> String {
+        let threads: Vec<String> = self.threads.iter().map(|c| c.to_json()).collect();
+        format!(
+            "{{\n  \"build_seconds\": {},\n  \"render_seconds\": {},\n  \"rays_per_second\": {},\n  \
+             \"total\": {},\n  \"threads\": [\n    {}\n  ]\n}}",
+            self.build_time.as_secs_f64(),
+            self.render_time.as_secs_f64(),
+            self.rays_per_second(),
+            self.total().to_json(),
+            threads.join(",\n    ")
+        )
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total();
+        let rays = total.total_rays();
+        writeln!(
+            f,
+            "BVH build            {:.3}s",
+            self.build_time.as_secs_f64()
+        )?;
+        writeln!(
+            f,
+            "Rendering            {:.3}s",
+            self.render_time.as_secs_f64()
+        )?;
+        writeln!(f, "Camera rays          {}", total.camera_rays)?;
+        writeln!(f, "Shadow rays          {}", total.shadow_rays)?;
+        writeln!(
+            f,
+            "Total rays           {} ({:.2} Mrays/s)",
+            rays,
+            self.rays_per_second() / 1e6
+        )?;
+        writeln!(
+            f,
+            "BVH nodes visited    {} ({:.1} per ray)",
+            total.bvh_nodes,
+            ratio(total.bvh_nodes, rays)
+        )?;
+        writeln!(
+            f,
+            "Primitive tests      {} ({:.1} per ray)",
+            total.primitive_tests,
+            ratio(total.primitive_tests, rays)
+        )?;
+        writeln!(f, "Average path length  {:.2}", total.average_path_length())?;
+        write!(
+            f,
+            "Russian roulette     {} paths ended",
+            total.roulette_terminations
+        )?;
+        for (index, counters) in self.threads.iter().enumerate() {
+            write!(f, "\nThread {:<13} {} rays", index, counters.total_rays())?;
+        }
+        Ok(())
+    }
+}
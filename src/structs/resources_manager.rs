@@ -18,7 +18,7 @@ pub struct ResourceManager<K: ResourceConfig> {
     pub resources: HashMap<K, Weak<<K as ResourceConfig>::AssociatedResource>>,
 }
 
-impl<'a, K: ResourceConfig> ResourceManager<K> {
+impl<K: ResourceConfig> ResourceManager<K> {
     pub fn new() -> Self {
         ResourceManager {
             resources: HashMap::new(),
@@ -29,12 +29,12 @@ impl<'a, K: ResourceConfig> ResourceManager<K> {
     /// instanciation is a success or a similar resource already exist.
     /// Can panic if no resource can be created with specified configuration.
     pub fn get_resource(&mut self, config: &K) -> Arc<<K as ResourceConfig>::AssociatedResource> {
-        match self.resources.get(&config) {
+        match self.resources.get(config) {
             Some(t) => t
                 .upgrade()
-                .or_else(|| self.load_from_config(&config))
+                .or_else(|| self.load_from_config(config))
                 .unwrap(),
-            None => self.load_from_config(&config).unwrap(),
+            None => self.load_from_config(config).unwrap(),
         }
     }
 
@@ -52,3 +52,9 @@ impl<'a, K: ResourceConfig> ResourceManager<K> {
         Some(res)
     }
 }
+
+impl<K: ResourceConfig> Default for ResourceManager<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
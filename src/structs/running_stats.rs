@@ -0,0 +1,97 @@
+use super::{luminance, Vec3};
+
+use std::io::{self, Read, Write};
+
+/// Running mean and variance of the samples of a pixel,
+/// updated one sample at a time with Welford's algorithm
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RunningStats {
+    count: u32,
+    mean: Vec3,
+    // sum of squared differences to the mean of the luminance
+    m2: f64,
+    mean_luminance: f64,
+}
+
+impl RunningStats {
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+
+    pub fn push(&mut self, sample: Vec3) {
+        self.count += 1;
+        let n = f64::from(self.count);
+        self.mean += (sample - self.mean) / n;
+
+        let y = luminance(&sample);
+        let delta = y - self.mean_luminance;
+        self.mean_luminance += delta / n;
+        self.m2 += delta * (y - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Vec3 {
+        self.mean
+    }
+
+    /// Unbiased sample variance of the luminance
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / f64::from(self.count - 1)
+    }
+
+    /// Variance of the mean luminance as an estimate, shrinks as samples are added
+    pub fn variance_of_mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.variance() / f64::from(self.count)
+    }
+
+    /// Standard error of the mean luminance relative to the mean itself.
+    /// The mean is floored so black pixels don't divide by zero.
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let standard_error = self.variance_of_mean().sqrt();
+        standard_error / self.mean_luminance.max(1e-3)
+    }
+
+    /// Little endian binary encoding, read back by `read_from`
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&self.count.to_le_bytes())?;
+        for value in &[
+            self.mean.x,
+            self.mean.y,
+            self.mean.z,
+            self.m2,
+            self.mean_luminance,
+        ] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(input: &mut R) -> io::Result<RunningStats> {
+        let mut count = [0; 4];
+        input.read_exact(&mut count)?;
+        let mut values = [0.0; 5];
+        for value in values.iter_mut() {
+            let mut bytes = [0; 8];
+            input.read_exact(&mut bytes)?;
+            *value = f64::from_le_bytes(bytes);
+        }
+        Ok(RunningStats {
+            count: u32::from_le_bytes(count),
+            mean: Vec3::new(values[0], values[1], values[2]),
+            m2: values[3],
+            mean_luminance: values[4],
+        })
+    }
+}
@@ -0,0 +1,210 @@
+use super::Vec3;
+
+use std::ops::{Add, Mul};
+use std::sync::OnceLock;
+
+/// Shortest wavelength (nm) sampled by spectral paths
+pub const LAMBDA_MIN: f64 = 380.0;
+/// Longest wavelength (nm) sampled by spectral paths
+pub const LAMBDA_MAX: f64 = 720.0;
+/// Number of wavelengths carried by a path
+pub const WAVELENGTH_COUNT: usize = 4;
+
+/// Wavelengths carried by a spectral path, using hero wavelength sampling:
+/// the first one (the hero) is sampled uniformly, the other ones are
+/// evenly rotated across the visible range.
+#[derive(Debug, Copy, Clone)]
+pub struct SampledWavelengths {
+    lambda: [f64; WAVELENGTH_COUNT],
+    secondary_terminated: bool,
+}
+
+impl SampledWavelengths {
+    /// Sample wavelengths from a random number in [0,1)
+    pub fn sample(u: f64) -> SampledWavelengths {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let mut lambda = [0.0; WAVELENGTH_COUNT];
+        for (i, l) in lambda.iter_mut().enumerate() {
+            let offset = u * range + i as f64 * range / WAVELENGTH_COUNT as f64;
+            *l = LAMBDA_MIN + offset % range;
+        }
+        SampledWavelengths {
+            lambda,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    pub fn get(&self, i: usize) -> f64 {
+        self.lambda[i]
+    }
+
+    /// Only keep the hero wavelength, needed after a wavelength dependent
+    /// direction was chosen (e.g. dispersion).
+    /// Return the weight to apply on the spectrum gathered after the event.
+    pub fn terminate_secondary(&mut self) -> SampledSpectrum {
+        if self.secondary_terminated {
+            return SampledSpectrum::splat(1.0);
+        }
+        self.secondary_terminated = true;
+        let mut weight = SampledSpectrum::default();
+        weight.values[0] = WAVELENGTH_COUNT as f64;
+        weight
+    }
+
+    /// Convert the radiance carried by the path to linear RGB.
+    /// The result is white balanced so an uplifted RGB color comes back unchanged on average.
+    pub fn to_rgb(&self, radiance: &SampledSpectrum) -> Vec3 {
+        let mut xyz = Vec3::default();
+        for i in 0..WAVELENGTH_COUNT {
+            xyz += radiance.values[i] * cie_xyz(self.lambda[i]);
+        }
+        // Uniform pdf on the range, averaged on the wavelengths
+        xyz *= (LAMBDA_MAX - LAMBDA_MIN) / WAVELENGTH_COUNT as f64;
+        xyz_to_linear_srgb(&xyz) / *white_balance()
+    }
+}
+
+/// Values of a spectrum at the wavelengths of a path
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SampledSpectrum {
+    pub values: [f64; WAVELENGTH_COUNT],
+}
+
+impl SampledSpectrum {
+    pub fn splat(value: f64) -> SampledSpectrum {
+        SampledSpectrum {
+            values: [value; WAVELENGTH_COUNT],
+        }
+    }
+
+    /// Uplift a RGB color to a spectrum and evaluate it at the path wavelengths
+    pub fn from_rgb(rgb: &Vec3, wavelengths: &SampledWavelengths) -> SampledSpectrum {
+        let mut values = [0.0; WAVELENGTH_COUNT];
+        for (i, v) in values.iter_mut().enumerate() {
+            *v = rgb_to_spectrum(rgb, wavelengths.get(i));
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(mut self, other: SampledSpectrum) -> SampledSpectrum {
+        for i in 0..WAVELENGTH_COUNT {
+            self.values[i] += other.values[i];
+        }
+        self
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(mut self, other: SampledSpectrum) -> SampledSpectrum {
+        for i in 0..WAVELENGTH_COUNT {
+            self.values[i] *= other.values[i];
+        }
+        self
+    }
+}
+
+// RGB to spectrum conversion, from Brian Smits,
+// "An RGB to Spectrum Conversion for Reflectances" (1999).
+// The basis spectra are sampled on 10 bins covering LAMBDA_MIN..LAMBDA_MAX.
+
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Evaluate the spectrum uplifted from a RGB color at the given wavelength (nm)
+pub fn rgb_to_spectrum(rgb: &Vec3, lambda: f64) -> f64 {
+    let bin = ((lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN) * 10.0) as isize;
+    let bin = bin.clamp(0, 9) as usize;
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+
+    if r <= g && r <= b {
+        let base = r * SMITS_WHITE[bin];
+        if g <= b {
+            base + (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            base + (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        let base = g * SMITS_WHITE[bin];
+        if r <= b {
+            base + (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            base + (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        }
+    } else {
+        let base = b * SMITS_WHITE[bin];
+        if r <= g {
+            base + (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+        } else {
+            base + (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+        }
+    }
+}
+
+/// Piecewise gaussian used by the CIE fit
+fn gaussian(x: f64, mu: f64, sigma1: f64, sigma2: f64) -> f64 {
+    let t = (x - mu) / if x < mu { sigma1 } else { sigma2 };
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 color matching functions at the given wavelength (nm),
+/// analytic fit from Wyman, Sloan and Shirley (2013)
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+        0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+        1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: &Vec3) -> Vec3 {
+    Vec3::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+/// Linear RGB of the spectrum uplifted from white, integrated over the sampled range
+fn white_balance() -> &'static Vec3 {
+    static WHITE: OnceLock<Vec3> = OnceLock::new();
+    WHITE.get_or_init(|| {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let mut xyz = Vec3::default();
+        let mut lambda = LAMBDA_MIN + 0.5;
+        while lambda < LAMBDA_MAX {
+            xyz += rgb_to_spectrum(&white, lambda) * cie_xyz(lambda);
+            lambda += 1.0;
+        }
+        xyz_to_linear_srgb(&xyz)
+    })
+}
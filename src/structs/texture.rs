@@ -17,6 +17,7 @@ pub enum TextureKind<'a> {
     Constant(Color),
     Checker(&'a TextureConfig<'a>, &'a TextureConfig<'a>),
     FromFile(&'a Path),
+    AlphaFromFile(&'a Path),
     Perlin(usize, usize),
 }
 
@@ -47,11 +48,13 @@ impl<'a> ResourceConfig for TextureConfig<'a> {
                 Arc::new(CheckerTexture::new(odd_texture, even_texture))
             }
             TextureKind::FromFile(p) => Arc::new(ImageTexture::new(p)),
+            TextureKind::AlphaFromFile(p) => Arc::new(ImageAlphaTexture {
+                image: ImageTexture::new(p),
+            }),
             TextureKind::Perlin(n, s) => Arc::new(PerlinTexture {
                 noise: Perlin::new(n),
                 scale: s as f64,
             }),
-            _ => unimplemented!(),
         }
     }
 }
@@ -91,9 +94,9 @@ impl Texture for CheckerTexture {
     fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
         let sines = f64::sin(10.0 * p.x) * f64::sin(10.0 * p.y) * f64::sin(10.0 * p.z);
         if sines < 0.0 {
-            self.odd.value(u, v, &p)
+            self.odd.value(u, v, p)
         } else {
-            self.even.value(u, v, &p)
+            self.even.value(u, v, p)
         }
     }
 }
@@ -126,27 +129,14 @@ impl ImageTexture {
         }
     }
 
-    fn get_pixel(&self, row: u32, col: u32) -> Vec3 {
-        let color_scale = 1.0 / 255.0;
+    fn get_pixel(&self, row: u32, col: u32) -> &[u8] {
         let values_per_row = self.channel_count * self.width;
         let pixel_index = (col * values_per_row + row * self.channel_count) as usize;
-        let pixel = &self.pixels[pixel_index..pixel_index + 4];
-
-        Vec3::new(
-            pixel[0] as f64 * color_scale,
-            pixel[1] as f64 * color_scale,
-            pixel[2] as f64 * color_scale,
-        )
+        &self.pixels[pixel_index..pixel_index + 4]
     }
-}
-
-impl Texture for ImageTexture {
-    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
-        // If we have no texture data, then return solid cyan as a debugging aid.
-        if self.pixels.is_empty() {
-            return Vec3::new(0.0, 1.0, 1.0);
-        }
 
+    /// Return the pixel under the u,v texture coordinates
+    fn lookup(&self, u: f64, v: f64) -> &[u8] {
         let width = self.width as f64;
         let height = self.height as f64;
 
@@ -163,6 +153,43 @@ impl Texture for ImageTexture {
     }
 }
 
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        // If we have no texture data, then return solid cyan as a debugging aid.
+        if self.pixels.is_empty() {
+            return Vec3::new(0.0, 1.0, 1.0);
+        }
+
+        let color_scale = 1.0 / 255.0;
+        let pixel = self.lookup(u, v);
+        Vec3::new(
+            pixel[0] as f64 * color_scale,
+            pixel[1] as f64 * color_scale,
+            pixel[2] as f64 * color_scale,
+        )
+    }
+}
+
+// Image Alpha Texture
+
+/// Alpha channel of an image, repeated on the three channels.
+/// Mostly useful as an opacity mask.
+pub struct ImageAlphaTexture {
+    image: ImageTexture,
+}
+
+impl Texture for ImageAlphaTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        // No texture data means fully opaque
+        if self.image.pixels.is_empty() {
+            return Vec3::new(1.0, 1.0, 1.0);
+        }
+
+        let alpha = self.image.lookup(u, v)[3] as f64 / 255.0;
+        Vec3::new(alpha, alpha, alpha)
+    }
+}
+
 // Perlin Texture
 
 pub struct PerlinTexture {
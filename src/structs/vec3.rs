@@ -42,19 +42,15 @@ impl Vec3 {
         Vec3 { x, y, z }
     }
 
-    pub fn random() -> Vec3 {
-        Vec3 {
-            x: rand::thread_rng().gen(),
-            y: rand::thread_rng().gen(),
-            z: rand::thread_rng().gen(),
-        }
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+        rng.gen()
     }
 
-    pub fn random_range(min: f64, max: f64) -> Vec3 {
+    pub fn random_range<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> Vec3 {
         Vec3 {
-            x: rand::thread_rng().gen_range(min, max),
-            y: rand::thread_rng().gen_range(min, max),
-            z: rand::thread_rng().gen_range(min, max),
+            x: rng.gen_range(min, max),
+            y: rng.gen_range(min, max),
+            z: rng.gen_range(min, max),
         }
     }
 
@@ -108,6 +104,11 @@ pub fn unit_vector(vec: Vec3) -> Vec3 {
     vec / vec.length()
 }
 
+/// Perceived brightness of a linear Rec. 709 colour
+pub fn luminance(color: &Vec3) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
 // v1 + v2
 impl Add for &Vec3 {
     type Output = Vec3;
@@ -0,0 +1,689 @@
+//! Checks that the materials conserve energy and sample the directions they claim.
+//!
+//! - White furnace: a sphere of a white material inside a uniformly white background must
+//!   look as bright as the background, it can't create light and shouldn't lose any.
+//! - Reflectance: the BSDF integrated numerically over the sphere of directions must match
+//!   the albedo, and the weights returned by `scatter`.
+//! - Chi-square: directions drawn by `scatter` are counted in bins over the sphere and
+//!   compared with the density integrated over each bin.
+//!   A failure prints both histograms.
+
+use rust_tracing::hitables::{HitRecord, HitableList, Sphere};
+use rust_tracing::integrators::{
+    AmbientOcclusion, DirectLighting, Integrator, PathTracer, SimplePathTracer,
+};
+use rust_tracing::materials::{Dielectric, Emissive, Lambertian, Material, Metal, Subsurface};
+use rust_tracing::render::{PathSettings, Scene};
+use rust_tracing::samplers::{IndependentSampler, Sampler, CAMERA_DIMENSION};
+use rust_tracing::structs::*;
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+const SEED: u64 = 7;
+/// Paths traced through each furnace
+const FURNACE_SAMPLES: u32 = 20_000;
+/// Bounces allowed in the furnace, paths through glass bounce inside for a while
+const FURNACE_DEPTH: u32 = 64;
+/// Energy a furnace may lose to paths cut at FURNACE_DEPTH
+const ENERGY_TOLERANCE: f64 = 1e-3;
+/// Largest difference allowed between estimates, in standard errors
+const MAX_DEVIATION: f64 = 4.0;
+/// Directions drawn for each histogram
+const SAMPLES: u32 = 100_000;
+/// Bins over cos θ, from the normal down to its opposite, and over φ
+const THETA_BINS: usize = 16;
+const PHI_BINS: usize = 32;
+/// Points per side of the grid integrating the density over a bin
+const BIN_RESOLUTION: usize = 32;
+/// Bins expecting fewer samples are pooled, the chi-square law doesn't hold for them
+const MIN_EXPECTED: f64 = 5.0;
+/// Probability of failing a correct material
+const SIGNIFICANCE: f64 = 0.01;
+/// Cosines between the incoming direction and the normal
+const INCIDENCES: [f64; 3] = [1.0, 0.7, 0.2];
+
+fn white() -> Vec3 {
+    Vec3::new(1.0, 1.0, 1.0)
+}
+
+fn lambertian(albedo: Vec3) -> Arc<dyn Material> {
+    Arc::new(Lambertian {
+        albedo: Arc::new(SolidColor::new(albedo)),
+    })
+}
+
+/// Direction of the given cosine with the z axis, in the xz plane
+fn direction(cos_theta: f64) -> Vec3 {
+    Vec3::new(
+        (1.0 - cos_theta * cos_theta).max(0.0).sqrt(),
+        0.0,
+        cos_theta,
+    )
+}
+
+/// Mean luminance of the paths entering a unit sphere of the material, lit by a white
+/// background and traced by integrator, with its standard error
+fn furnace(integrator: &dyn Integrator, material: Arc<dyn Material>) -> (f64, f64) {
+    let mut world = HitableList::new(Arc::new(Emissive {
+        emit: Arc::new(SolidColor::new(white())),
+        multiplier: 1.0,
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::default(),
+        radius: 1.0,
+        material,
+    }));
+    let scene = Scene::new(&world, (0.0, 1.0), PathSettings::new(FURNACE_DEPTH));
+
+    let mut sampler = IndependentSampler::new(SEED);
+    let mut stats = RunningStats::new();
+    for index in 0..FURNACE_SAMPLES {
+        // Aim at a uniform point of the disk the sphere covers
+        sampler.start_pixel_sample(0, 0, index);
+        sampler.set_dimension(CAMERA_DIMENSION);
+        let (u, v) = sampler.get_2d();
+        let (r, a) = (u.sqrt(), 2.0 * PI * v);
+        let origin = Vec3::new(0.0, 0.0, -3.0);
+        let target = Vec3::new(r * a.cos(), r * a.sin(), 0.0);
+        let ray = Ray::new(origin, target - origin, 0.0);
+        stats.push(integrator.li(&ray, &scene, &mut sampler, None));
+    }
+    (luminance(&stats.mean()), stats.variance_of_mean().sqrt())
+}
+
+fn assert_furnace(name: &str, material: Arc<dyn Material>) {
+    let (mean, standard_error) = furnace(&PathTracer, material);
+    let tolerance = MAX_DEVIATION * standard_error + ENERGY_TOLERANCE;
+    assert!(
+        (mean - 1.0).abs() <= tolerance,
+        "{} reflects {:.5} of the furnace's light (± {:.5})",
+        name,
+        mean,
+        standard_error
+    );
+}
+
+/// Ray arriving at the origin from the direction incoming (unit vector) and its hit
+/// on a surface of normal z, seen from below when incoming points below the surface
+fn hit(material: Arc<dyn Material>, incoming: Vec3) -> (Ray, HitRecord) {
+    let ray = Ray::new(incoming, -incoming, 0.0);
+    let rec = HitRecord::new(
+        1.0,
+        0.0,
+        0.0,
+        Vec3::default(),
+        &ray,
+        Vec3::new(0.0, 0.0, 1.0),
+        material,
+    );
+    (ray, rec)
+}
+
+/// Attenuation and unit direction of SAMPLES scattered rays, None when absorbed
+fn scatter_samples(ray: &Ray, rec: &HitRecord) -> Vec<Option<(Vec3, Vec3)>> {
+    let mut sampler = IndependentSampler::new(SEED);
+    (0..SAMPLES)
+        .map(|index| {
+            sampler.start_pixel_sample(0, 0, index);
+            rec.material
+                .scatter(ray, rec, &mut sampler)
+                .map(|(attenuation, scattered)| (attenuation, unit_vector(scattered.direction())))
+        })
+        .collect()
+}
+
+/// Bin of a unit direction, rows go from the normal (z) to its opposite
+fn bin_of(direction: &Vec3) -> usize {
+    let row = ((1.0 - direction.z) / 2.0 * THETA_BINS as f64) as usize;
+    let phi = direction.y.atan2(direction.x) + PI;
+    let column = (phi / (2.0 * PI) * PHI_BINS as f64) as usize;
+    row.min(THETA_BINS - 1) * PHI_BINS + column.min(PHI_BINS - 1)
+}
+
+/// Integral of a density over each bin, with a midpoint rule in cos θ and φ
+fn integrate_bins<F: Fn(&Vec3) -> f64>(density: F) -> Vec<f64> {
+    let steps = (THETA_BINS * BIN_RESOLUTION, PHI_BINS * BIN_RESOLUTION);
+    let (cos_step, phi_step) = (2.0 / steps.0 as f64, 2.0 * PI / steps.1 as f64);
+    let mut bins = vec![0.0; THETA_BINS * PHI_BINS];
+    for i in 0..steps.0 {
+        let cos_theta = 1.0 - (i as f64 + 0.5) * cos_step;
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        for j in 0..steps.1 {
+            let phi = (j as f64 + 0.5) * phi_step - PI;
+            let direction = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+            let bin = (i / BIN_RESOLUTION) * PHI_BINS + j / BIN_RESOLUTION;
+            // cos θ is uniform over the sphere, the solid angle of a cell is its area
+            bins[bin] += density(&direction) * cos_step * phi_step;
+        }
+    }
+    bins
+}
+
+/// ln Γ(x) for x > 0, Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.120_865_097_386_617_9e-2,
+        -0.539_523_938_495_3e-5,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let mut series = 1.000_000_000_190_015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Probability that a chi-square variable with the given degrees of freedom
+/// exceeds the statistic, the regularized upper incomplete gamma function Q(k/2, x/2)
+fn chi_square_tail(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    let (a, x) = (degrees_of_freedom as f64 / 2.0, statistic / 2.0);
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // Series of the lower function
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        while term.abs() > sum.abs() * 1e-15 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+        }
+        1.0 - sum * prefactor
+    } else {
+        // Continued fraction of the upper function, modified Lentz's method
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut fraction = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            d = if d.abs() < tiny { tiny } else { d };
+            c = b + an / c;
+            c = if c.abs() < tiny { tiny } else { c };
+            d = 1.0 / d;
+            let delta = d * c;
+            fraction *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        fraction * prefactor
+    }
+}
+
+/// Density map of a histogram, darker characters for fuller bins
+fn histogram_map(counts: &[f64]) -> Vec<String> {
+    const SHADES: &[u8] = b" .:-=+*#%@";
+    let max = counts.iter().cloned().fold(0.0, f64::max).max(1.0);
+    counts
+        .chunks(PHI_BINS)
+        .map(|row| {
+            row.iter()
+                .map(|count| {
+                    let shade = (count / max * (SHADES.len() - 1) as f64).ceil() as usize;
+                    SHADES[shade.min(SHADES.len() - 1)] as char
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Pearson's test of the observed counts of the bins against the expected ones.
+/// The observed counts may have more bins than the histogram over the sphere,
+/// for the samples that didn't produce a direction.
+fn chi_square_test(name: &str, observed: &[f64], expected: &[f64]) {
+    assert_eq!(observed.len(), expected.len());
+    let mut statistic = 0.0;
+    let mut bins = 0;
+    let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+    for (bin, (o, e)) in observed.iter().zip(expected).enumerate() {
+        if *e == 0.0 && *o > 0.0 {
+            panic!(
+                "{}: {} samples in bin {} where the density is zero",
+                name, o, bin
+            );
+        }
+        if *e < MIN_EXPECTED {
+            pooled_observed += o;
+            pooled_expected += e;
+        } else {
+            statistic += (o - e).powi(2) / e;
+            bins += 1;
+        }
+    }
+    if pooled_expected >= MIN_EXPECTED {
+        statistic += (pooled_observed - pooled_expected).powi(2) / pooled_expected;
+        bins += 1;
+    } else if pooled_observed > 0.0 && pooled_expected == 0.0 {
+        panic!("{}: samples where the density is zero", name);
+    }
+    assert!(bins > 1, "{}: too few bins for a chi-square test", name);
+
+    let p_value = chi_square_tail(statistic, bins - 1);
+    if p_value >= SIGNIFICANCE {
+        return;
+    }
+
+    let sphere = THETA_BINS * PHI_BINS;
+    let maps = histogram_map(&observed[..sphere.min(observed.len())])
+        .into_iter()
+        .zip(histogram_map(&expected[..sphere.min(expected.len())]))
+        .map(|(o, e)| format!("  |{}|  |{}|", o, e))
+        .collect::<Vec<String>>();
+    let mut worst: Vec<(usize, f64)> = observed
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|(_, (_, e))| **e > 0.0)
+        .map(|(bin, (o, e))| (bin, (o - e).powi(2) / e))
+        .collect();
+    worst.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    let worst: Vec<String> = worst
+        .iter()
+        .take(5)
+        .map(|(bin, _)| {
+            format!(
+                "  bin {} (row {}, column {}): {} observed, {:.1} expected",
+                bin,
+                bin / PHI_BINS,
+                bin % PHI_BINS,
+                observed[*bin],
+                expected[*bin]
+            )
+        })
+        .collect();
+    panic!(
+        "{}: chi-square {:.1} with {} degrees of freedom, p-value {:.2e}\n\
+         observed (left) and expected (right), normal at the top:\n{}\n{}",
+        name,
+        statistic,
+        bins - 1,
+        p_value,
+        maps.join("\n"),
+        worst.join("\n")
+    );
+}
+
+/// Density of the directions picked by fuzzy metal before the ones below the surface are
+/// absorbed: a point uniform in a ball of radius fuzziness around the reflected direction.
+/// The reference the `pdf` of Metal is checked against.
+fn fuzzy_reflection_pdf(reflected: &Vec3, fuzziness: f64, direction: &Vec3) -> f64 {
+    // The segment of the line along direction inside the ball
+    let b = dot(*direction, *reflected);
+    let discriminant = b * b - (1.0 - fuzziness * fuzziness);
+    if discriminant < 0.0 {
+        return 0.0;
+    }
+    let far = b + discriminant.sqrt();
+    if far <= 0.0 {
+        return 0.0;
+    }
+    let near = (b - discriminant.sqrt()).max(0.0);
+    // Volume of the cone of directions over the volume of the ball
+    (far.powi(3) - near.powi(3)) / (4.0 * PI * fuzziness.powi(3))
+}
+
+/// Fraction of the fuzzy reflection lobe above the surface, integrated over a polar grid
+/// around the reflected direction, which the grid of bins resolves poorly for small lobes
+fn fuzzy_reflection_above(reflected: &Vec3, fuzziness: f64) -> f64 {
+    const STEPS: usize = 1024;
+    // Directions of the lobe are within asin(fuzziness) of the reflected one
+    let cos_max = (1.0 - fuzziness * fuzziness).sqrt();
+    let (cos_step, phi_step) = ((1.0 - cos_max) / STEPS as f64, 2.0 * PI / STEPS as f64);
+    let tangent = unit_vector(cross(*reflected, Vec3::new(0.0, 1.0, 0.0)));
+    let bitangent = cross(*reflected, tangent);
+    let mut total = 0.0;
+    for i in 0..STEPS {
+        let cos_alpha = 1.0 - (i as f64 + 0.5) * cos_step;
+        let sin_alpha = (1.0 - cos_alpha * cos_alpha).sqrt();
+        for j in 0..STEPS {
+            let phi = (j as f64 + 0.5) * phi_step;
+            let direction =
+                cos_alpha * reflected + sin_alpha * (phi.cos() * tangent + phi.sin() * bitangent);
+            if direction.z > 0.0 {
+                total +=
+                    fuzzy_reflection_pdf(reflected, fuzziness, &direction) * cos_step * phi_step;
+            }
+        }
+    }
+    total
+}
+
+fn mirror(incoming: &Vec3) -> Vec3 {
+    Vec3::new(-incoming.x, -incoming.y, incoming.z)
+}
+
+/// Fresnel reflectance the glass claims, Schlick's approximation
+fn schlick(cosine: f64, etai_over_etat: f64) -> f64 {
+    let r0 = ((1.0 - etai_over_etat) / (1.0 + etai_over_etat)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+}
+
+#[test]
+fn furnace_lambertian() {
+    assert_furnace("Lambertian", lambertian(white()));
+}
+
+#[test]
+fn furnace_metal() {
+    assert_furnace("smooth metal", Arc::new(Metal::new(white(), 0.0)));
+}
+
+#[test]
+fn furnace_fuzzy_metal() {
+    // Directions drawn below the surface are absorbed, fuzzy metal may only lose light
+    for fuzziness in &[0.3, 1.0] {
+        let (mean, standard_error) =
+            furnace(&PathTracer, Arc::new(Metal::new(white(), *fuzziness)));
+        assert!(
+            mean <= 1.0 + MAX_DEVIATION * standard_error,
+            "metal of fuzziness {} reflects {:.5} of the furnace's light",
+            fuzziness,
+            mean
+        );
+    }
+}
+
+#[test]
+fn furnace_dielectric() {
+    assert_furnace("glass", Arc::new(Dielectric::new(1.5)));
+    assert_furnace("diamond", Arc::new(Dielectric::diamond()));
+}
+
+#[test]
+fn furnace_integrators() {
+    // Light leaving a convex object never meets it again, so a single bounce
+    // already sees the whole furnace
+    let integrators: [(&str, &dyn Integrator); 3] = [
+        ("simple path tracer", &SimplePathTracer),
+        ("direct lighting", &DirectLighting),
+        ("ambient occlusion", &AmbientOcclusion { distance: 10.0 }),
+    ];
+    for (name, integrator) in &integrators {
+        let (mean, standard_error) = furnace(*integrator, lambertian(white()));
+        assert!(
+            (mean - 1.0).abs() <= MAX_DEVIATION * standard_error + ENERGY_TOLERANCE,
+            "the {} sees {:.5} of the furnace's light (± {:.5})",
+            name,
+            mean,
+            standard_error
+        );
+    }
+}
+
+#[test]
+fn reflectance_lambertian() {
+    let albedo = Vec3::new(0.8, 0.5, 0.2);
+    for cos_theta in &INCIDENCES {
+        let (ray, rec) = hit(lambertian(albedo), direction(*cos_theta));
+        let eval = integrate_bins(|d| luminance(&rec.material.eval(&ray, &rec, d)));
+        let pdf = integrate_bins(|d| rec.material.pdf(&ray, &rec, d));
+        let reflectance: f64 = eval.iter().sum();
+        let total: f64 = pdf.iter().sum();
+        assert!(
+            (reflectance - luminance(&albedo)).abs() < 1e-3,
+            "Lambertian at cos θ = {}: eval integrates to {:.5}, albedo is {:.5}",
+            cos_theta,
+            reflectance,
+            luminance(&albedo)
+        );
+        assert!(
+            (total - 1.0).abs() < 1e-3,
+            "Lambertian at cos θ = {}: pdf integrates to {:.5}",
+            cos_theta,
+            total
+        );
+
+        // eval / pdf is the weight scatter returns
+        let mut stats = RunningStats::new();
+        for (attenuation, _) in scatter_samples(&ray, &rec).into_iter().flatten() {
+            stats.push(attenuation);
+        }
+        let weight = luminance(&stats.mean());
+        assert!(
+            (weight - luminance(&albedo)).abs() < 1e-9,
+            "Lambertian at cos θ = {}: scatter weighs {:.5}, albedo is {:.5}",
+            cos_theta,
+            weight,
+            luminance(&albedo)
+        );
+    }
+}
+
+#[test]
+fn reflectance_fuzzy_metal() {
+    let albedo = Vec3::new(0.9, 0.6, 0.3);
+    for fuzziness in &[0.3, 1.0] {
+        for cos_theta in &INCIDENCES {
+            let incoming = direction(*cos_theta);
+            let (ray, rec) = hit(Arc::new(Metal::new(albedo, *fuzziness)), incoming);
+            let above = fuzzy_reflection_above(&mirror(&incoming), *fuzziness);
+            let expected = luminance(&albedo) * above;
+
+            let mut stats = RunningStats::new();
+            for sample in scatter_samples(&ray, &rec) {
+                stats.push(sample.map_or(Vec3::default(), |(attenuation, _)| attenuation));
+            }
+            let (reflectance, standard_error) =
+                (luminance(&stats.mean()), stats.variance_of_mean().sqrt());
+            assert!(
+                (reflectance - expected).abs() <= MAX_DEVIATION * standard_error + 1e-3,
+                "metal of fuzziness {} at cos θ = {}: reflects {:.5} (± {:.5}), expected {:.5}",
+                fuzziness,
+                cos_theta,
+                reflectance,
+                standard_error,
+                expected
+            );
+
+            // Lights see the same lobe
+            let eval = integrate_bins(|d| luminance(&rec.material.eval(&ray, &rec, d)));
+            let pdf = integrate_bins(|d| rec.material.pdf(&ray, &rec, d));
+            let (eval, pdf): (f64, f64) = (eval.iter().sum(), pdf.iter().sum());
+            assert!(
+                (eval - expected).abs() < 1e-2 && (pdf - above).abs() < 1e-2,
+                "metal of fuzziness {} at cos θ = {}: eval integrates to {:.5}, pdf to {:.5}, \
+                 expected {:.5} and {:.5}",
+                fuzziness,
+                cos_theta,
+                eval,
+                pdf,
+                expected,
+                above
+            );
+        }
+    }
+}
+
+#[test]
+fn reflectance_subsurface_exit() {
+    let material: Arc<dyn Material> = Arc::new(Subsurface::from_albedo(
+        1.45,
+        Vec3::new(0.9, 0.8, 0.7),
+        Vec3::new(2.0, 1.0, 0.5),
+    ));
+    for cos_theta in &INCIDENCES {
+        // Walking inside the medium up to the boundary
+        let (ray, rec) = hit(material.clone(), -direction(*cos_theta));
+        let exit = integrate_bins(|d| luminance(&rec.material.eval(&ray, &rec, d)));
+        let exit: f64 = exit.iter().sum();
+
+        // Rays leaving the medium at the hit, not the ones scattered inside or reflected back
+        let mut sampler = IndependentSampler::new(SEED);
+        let mut stats = RunningStats::new();
+        for index in 0..SAMPLES {
+            sampler.start_pixel_sample(0, 0, index);
+            stats.push(match rec.material.scatter(&ray, &rec, &mut sampler) {
+                Some((attenuation, scattered))
+                    if scattered.origin() == rec.p && scattered.direction().z > 0.0 =>
+                {
+                    attenuation
+                }
+                _ => Vec3::default(),
+            });
+        }
+        let (scattered, standard_error) =
+            (luminance(&stats.mean()), stats.variance_of_mean().sqrt());
+        assert!(
+            (exit - scattered).abs() <= MAX_DEVIATION * standard_error + 1e-3,
+            "subsurface at cos θ = {}: eval integrates to {:.5}, scatter leaves with {:.5} (± {:.5})",
+            cos_theta,
+            exit,
+            scattered,
+            standard_error
+        );
+    }
+}
+
+#[test]
+fn chi_square_lambertian() {
+    for cos_theta in &INCIDENCES {
+        let (ray, rec) = hit(lambertian(white()), direction(*cos_theta));
+        let mut observed = vec![0.0; THETA_BINS * PHI_BINS];
+        for (_, direction) in scatter_samples(&ray, &rec).into_iter().flatten() {
+            observed[bin_of(&direction)] += 1.0;
+        }
+        let expected: Vec<f64> = integrate_bins(|d| rec.material.pdf(&ray, &rec, d))
+            .iter()
+            .map(|p| p * f64::from(SAMPLES))
+            .collect();
+        chi_square_test(
+            &format!("Lambertian at cos θ = {}", cos_theta),
+            &observed,
+            &expected,
+        );
+    }
+}
+
+#[test]
+fn chi_square_fuzzy_metal() {
+    for fuzziness in &[0.3, 1.0] {
+        for cos_theta in &INCIDENCES {
+            let incoming = direction(*cos_theta);
+            let (ray, rec) = hit(Arc::new(Metal::new(white(), *fuzziness)), incoming);
+            let reflected = mirror(&incoming);
+
+            // The last bin counts the absorbed samples
+            let mut observed = vec![0.0; THETA_BINS * PHI_BINS + 1];
+            for sample in scatter_samples(&ray, &rec) {
+                match sample {
+                    Some((_, direction)) => observed[bin_of(&direction)] += 1.0,
+                    None => observed[THETA_BINS * PHI_BINS] += 1.0,
+                }
+            }
+            let mut expected: Vec<f64> = integrate_bins(|d| {
+                let pdf = rec.material.pdf(&ray, &rec, d);
+                let reference = if d.z > 0.0 {
+                    fuzzy_reflection_pdf(&reflected, *fuzziness, d)
+                } else {
+                    0.0
+                };
+                assert!(
+                    (pdf - reference).abs() <= 1e-9 * reference.max(1.0),
+                    "metal of fuzziness {} at cos θ = {}: pdf {} toward {:?}, expected {}",
+                    fuzziness,
+                    cos_theta,
+                    pdf,
+                    d,
+                    reference
+                );
+                pdf
+            })
+            .iter()
+            .map(|p| p * f64::from(SAMPLES))
+            .collect();
+            let absorbed = f64::from(SAMPLES) - expected.iter().sum::<f64>();
+            expected.push(absorbed.max(0.0));
+            chi_square_test(
+                &format!("metal of fuzziness {} at cos θ = {}", fuzziness, cos_theta),
+                &observed,
+                &expected,
+            );
+        }
+    }
+}
+
+#[test]
+fn smooth_metal_reflects_mirror_direction() {
+    for cos_theta in &INCIDENCES {
+        let incoming = direction(*cos_theta);
+        let (ray, rec) = hit(Arc::new(Metal::new(white(), 0.0)), incoming);
+        for sample in scatter_samples(&ray, &rec).iter().take(100) {
+            let (_, direction) = sample.expect("smooth metal absorbed a ray");
+            assert!(
+                (direction - mirror(&incoming)).length() < 1e-9,
+                "smooth metal at cos θ = {} scattered toward {:?}",
+                cos_theta,
+                direction
+            );
+        }
+    }
+}
+
+#[test]
+fn chi_square_dielectric() {
+    let ref_idx = 1.5;
+    // Entering then leaving the glass, the last incidence from inside is totally reflected
+    for (inside, cos_theta) in INCIDENCES
+        .iter()
+        .map(|c| (false, *c))
+        .chain(INCIDENCES.iter().map(|c| (true, *c)))
+    {
+        let incoming = direction(cos_theta);
+        let incoming = if inside { -incoming } else { incoming };
+        let name = format!(
+            "glass at cos θ = {} from {}",
+            cos_theta,
+            if inside { "inside" } else { "outside" }
+        );
+        let (ray, rec) = hit(Arc::new(Dielectric::new(ref_idx)), incoming);
+        let etai_over_etat = if inside { ref_idx } else { 1.0 / ref_idx };
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let reflectance = if etai_over_etat * sin_theta > 1.0 {
+            1.0
+        } else {
+            schlick(cos_theta, etai_over_etat)
+        };
+
+        // Two bins, reflected and refracted, the directions themselves are deterministic
+        let mut observed = vec![0.0; 2];
+        for sample in scatter_samples(&ray, &rec) {
+            let (attenuation, direction) = sample.expect("glass absorbed a ray");
+            assert_eq!(attenuation, white(), "{}: glass is tinted", name);
+            if (direction - mirror(&incoming)).length() < 1e-9 {
+                observed[0] += 1.0;
+                continue;
+            }
+            // Snell's law: the tangent component scales by the ratio of the indices
+            let expected_tangent = -etai_over_etat * Vec3::new(incoming.x, incoming.y, 0.0);
+            let tangent = Vec3::new(direction.x, direction.y, 0.0);
+            assert!(
+                (tangent - expected_tangent).length() < 1e-9 && direction.z * incoming.z < 0.0,
+                "{}: scattered toward {:?}, neither reflected nor refracted",
+                name,
+                direction
+            );
+            observed[1] += 1.0;
+        }
+        let samples = f64::from(SAMPLES);
+        let expected = [reflectance * samples, (1.0 - reflectance) * samples];
+        if reflectance == 1.0 {
+            assert_eq!(
+                observed[1], 0.0,
+                "{}: refracted beyond the critical angle",
+                name
+            );
+            continue;
+        }
+        chi_square_test(&name, &observed, &expected);
+    }
+}
@@ -0,0 +1,112 @@
+//! Denoises a low sample count render of the Cornell box and checks it gets closer to the
+//! high sample count reference of the golden tests than the noisy render.
+
+use rust_tracing::cameras::ThinLensCamera;
+use rust_tracing::integrators::PathTracer;
+use rust_tracing::render::*;
+use rust_tracing::samplers::SamplerKind;
+use rust_tracing::scenes::{preset, Preset};
+use rust_tracing::scheduler::TileOrder;
+use rust_tracing::structs::*;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Size of the golden references
+const WIDTH: u32 = 48;
+const HEIGHT: u32 = 27;
+const SAMPLES: u32 = 8;
+const CORNELL_BOX: u32 = 5;
+/// Seed of the random choices made by the scenes, shared with the golden references
+const SCENE_SEED: u64 = 0;
+/// Unrelated to the numbers of the references
+const SEED: u64 = 3;
+
+/// Gamma corrected reference, clamped to white like the renders
+fn reference() -> Image {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/cornell_box.exr");
+    let image = AovImage::read_exr(&path)
+        .unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+    assert_eq!((image.width, image.height), (WIDTH, HEIGHT));
+    let (r, g, b) = (
+        image.channel("R").unwrap(),
+        image.channel("G").unwrap(),
+        image.channel("B").unwrap(),
+    );
+    let mut buffer = Vec::with_capacity(r.len() * 3);
+    for i in 0..r.len() {
+        for channel in &[r, g, b] {
+            buffer.push(f64::from(channel[i]).min(1.0));
+        }
+    }
+    Image::from(WIDTH, HEIGHT, PixelFormat::RGBU8, &buffer).gamma_corrected()
+}
+
+/// Render denoised and noisy, both clamped to white
+fn render_denoised(denoiser: &Denoiser) -> (Image, Image) {
+    let Preset {
+        world,
+        camera,
+        max_depth,
+    } = preset(CORNELL_BOX, SCENE_SEED);
+    let camera = Arc::new(ThinLensCamera::new_look_at(
+        camera.lookfrom,
+        camera.lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        camera.vfov,
+        f64::from(WIDTH) / f64::from(HEIGHT),
+        camera.aperture,
+        camera.focus_dist,
+        0.0,
+        1.0,
+    ));
+    let aovs = AovLayout::new(&[Aov::Albedo, Aov::Normal, Aov::Depth], world.lights.len());
+    let film = Film::new(WIDTH, HEIGHT, Filter::Box { radius: 0.5 }, None)
+        .with_aov_channels(aovs.channel_count());
+    let progress = Progress {
+        time_budget: None,
+        samples_per_pass: SAMPLES,
+        thread_count: 2,
+        tile_size: 16,
+        tile_order: TileOrder::Scanline,
+        snapshot: None,
+        checkpoint: None,
+    };
+    let rendered = render(
+        &film,
+        Sampling::Uniform(SAMPLES),
+        &progress,
+        PathSettings::new(max_depth),
+        ColorMode::Rgb,
+        &PathTracer,
+        &world,
+        camera,
+        (0.0, 1.0),
+        SamplerKind::Sobol,
+        SEED,
+        &aovs,
+        Some(denoiser),
+    )
+    .expect("the render was interrupted");
+    let clamped = |image: &Image| {
+        let pixels: Vec<f64> = image.get_pixels().iter().map(|p| p.min(1.0)).collect();
+        Image::from(WIDTH, HEIGHT, PixelFormat::RGBU8, &pixels)
+    };
+    let noisy = rendered.noisy.expect("no noisy image kept");
+    (clamped(&rendered.image), clamped(&noisy))
+}
+
+#[test]
+fn denoising_gets_closer_to_the_reference() {
+    let reference = reference();
+    let (denoised, noisy) = render_denoised(&Denoiser::default());
+    let denoised_rmse = denoised.compare(&reference).rmse;
+    let noisy_rmse = noisy.compare(&reference).rmse;
+    // About a third of the error is left at 8 spp, half leaves room for other noise patterns
+    assert!(
+        denoised_rmse < noisy_rmse / 2.0,
+        "RMSE of {} denoised against {} noisy",
+        denoised_rmse,
+        noisy_rmse
+    );
+}
@@ -0,0 +1,102 @@
+//! Renders a scene with different thread counts and checks the film holds the same bits.
+//!
+//! Samples only depend on the pixel and their index, and the splats of the tiles are added
+//! to the film in a fixed order, so the threads must not change a single bit of the image.
+
+use rust_tracing::cameras::ThinLensCamera;
+use rust_tracing::integrators::PathTracer;
+use rust_tracing::render::*;
+use rust_tracing::samplers::SamplerKind;
+use rust_tracing::scenes::{preset, Preset};
+use rust_tracing::scheduler::TileOrder;
+use rust_tracing::structs::*;
+
+use std::sync::Arc;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+const SAMPLES: u32 = 8;
+const SEED: u64 = 0;
+
+/// Bits of the filtered color, the weight sum and the AOV sums of every pixel
+fn render_bits(thread_count: usize) -> Vec<u64> {
+    let Preset {
+        world,
+        camera,
+        max_depth,
+    } = preset(1, SEED);
+    let camera = Arc::new(ThinLensCamera::new_look_at(
+        camera.lookfrom,
+        camera.lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        camera.vfov,
+        f64::from(WIDTH) / f64::from(HEIGHT),
+        camera.aperture,
+        camera.focus_dist,
+        0.0,
+        1.0,
+    ));
+    let aovs = AovLayout::new(&[Aov::Albedo, Aov::Normal], 0);
+    // Wide enough for the splats to reach over the edges of the tiles
+    let filter = Filter::Gaussian {
+        radius: 1.5,
+        sigma: 0.5,
+    };
+    let film = Film::new(WIDTH, HEIGHT, filter, None).with_aov_channels(aovs.channel_count());
+    let progress = Progress {
+        time_budget: None,
+        samples_per_pass: SAMPLES / 2,
+        thread_count,
+        tile_size: 8,
+        tile_order: TileOrder::Spiral,
+        snapshot: None,
+        checkpoint: None,
+    };
+    render(
+        &film,
+        Sampling::Uniform(SAMPLES),
+        &progress,
+        PathSettings::new(max_depth),
+        ColorMode::Rgb,
+        &PathTracer,
+        &world,
+        camera,
+        (0.0, 1.0),
+        SamplerKind::Sobol,
+        SEED,
+        &aovs,
+        None,
+    )
+    .expect("the render was interrupted");
+
+    let mut bits = vec![];
+    for pixel in film.pixels() {
+        let color = pixel.color();
+        bits.extend(
+            [color.x, color.y, color.z, pixel.weight_sum]
+                .iter()
+                .map(|v| v.to_bits()),
+        );
+    }
+    bits.extend(film.aovs().iter().map(|v| v.to_bits()));
+    bits
+}
+
+#[test]
+fn same_bits_whatever_the_thread_count() {
+    let single = render_bits(1);
+    for &thread_count in &[4, 8] {
+        for _ in 0..2 {
+            let differing = render_bits(thread_count)
+                .iter()
+                .zip(&single)
+                .filter(|(a, b)| a != b)
+                .count();
+            assert_eq!(
+                differing, 0,
+                "{} values differ with {} threads",
+                differing, thread_count
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Renders the built-in scenes at a small resolution and compares them with stored references.
+//!
+//! Pixels are compared by blocks: the mean difference of each channel over a block must stay
+//! within a few standard errors, estimated from the samples of both renders.
+//! After a change meant to alter the images, render the references again with
+//! `UPDATE_GOLDEN=1 cargo test --release --test golden` and check them by eye.
+
+use rust_tracing::cameras::ThinLensCamera;
+use rust_tracing::integrators::PathTracer;
+use rust_tracing::render::*;
+use rust_tracing::samplers::SamplerKind;
+use rust_tracing::scenes::{preset, Preset};
+use rust_tracing::scheduler::TileOrder;
+use rust_tracing::structs::*;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const WIDTH: u32 = 48;
+const HEIGHT: u32 = 27;
+const SAMPLES: u32 = 32;
+const REFERENCE_SAMPLES: u32 = 2048;
+/// Seed of the random choices made by the scenes, shared by the test and the references
+const SCENE_SEED: u64 = 0;
+/// The references are rendered with other numbers than the tests, so their noise is unrelated
+const SEED: u64 = 1;
+const REFERENCE_SEED: u64 = 2;
+/// Side of the blocks of pixels compared, in pixels
+const BLOCK: u32 = 6;
+/// Largest difference allowed, in standard errors
+const MAX_DEVIATION: f64 = 6.0;
+/// Differences below this luminance are never reported, variance can't be estimated in the dark
+const NEGLIGIBLE: f64 = 2e-3;
+
+/// Linear color and variance of the mean luminance of each pixel, top row first
+struct Estimate {
+    colors: Vec<Vec3>,
+    variances: Vec<f64>,
+}
+
+fn render_scene(scene: u32, samples: u32, seed: u64) -> Estimate {
+    let Preset {
+        world,
+        camera,
+        max_depth,
+    } = preset(scene, SCENE_SEED);
+    let camera = Arc::new(ThinLensCamera::new_look_at(
+        camera.lookfrom,
+        camera.lookat,
+        Vec3::new(0.0, 1.0, 0.0),
+        camera.vfov,
+        f64::from(WIDTH) / f64::from(HEIGHT),
+        camera.aperture,
+        camera.focus_dist,
+        0.0,
+        1.0,
+    ));
+    // Each sample only lands in its own pixel, pixels stay independent
+    let film = Film::new(WIDTH, HEIGHT, Filter::Box { radius: 0.5 }, None);
+    let progress = Progress {
+        time_budget: None,
+        samples_per_pass: samples,
+        thread_count: 2,
+        tile_size: 16,
+        tile_order: TileOrder::Scanline,
+        snapshot: None,
+        checkpoint: None,
+    };
+    render(
+        &film,
+        Sampling::Uniform(samples),
+        &progress,
+        PathSettings::new(max_depth),
+        ColorMode::Rgb,
+        &PathTracer,
+        &world,
+        camera,
+        (0.0, 1.0),
+        SamplerKind::Sobol,
+        seed,
+        &AovLayout::new(&[], 0),
+        None,
+    )
+    .expect("the render was interrupted");
+
+    let pixels = film.pixels();
+    Estimate {
+        colors: pixels.iter().map(FilmPixel::color).collect(),
+        variances: pixels.iter().map(|p| p.stats.variance_of_mean()).collect(),
+    }
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.exr", name))
+}
+
+fn write_reference(name: &str, estimate: &Estimate) {
+    let channel = |values: Vec<f64>| values.iter().map(|v| *v as f32).collect();
+    let image = AovImage {
+        width: WIDTH,
+        height: HEIGHT,
+        channels: vec![
+            (
+                "R".to_string(),
+                channel(estimate.colors.iter().map(|c| c.x).collect()),
+            ),
+            (
+                "G".to_string(),
+                channel(estimate.colors.iter().map(|c| c.y).collect()),
+            ),
+            (
+                "B".to_string(),
+                channel(estimate.colors.iter().map(|c| c.z).collect()),
+            ),
+            ("variance".to_string(), channel(estimate.variances.clone())),
+        ],
+    };
+    image
+        .write_exr(&reference_path(name))
+        .expect("cannot write the reference");
+}
+
+fn read_reference(name: &str) -> Estimate {
+    let path = reference_path(name);
+    let image = AovImage::read_exr(&path)
+        .unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+    assert_eq!((image.width, image.height), (WIDTH, HEIGHT));
+    let channel = |name: &str| -> Vec<f64> {
+        image
+            .channel(name)
+            .unwrap_or_else(|| panic!("no {} channel in {}", name, path.display()))
+            .iter()
+            .map(|v| f64::from(*v))
+            .collect()
+    };
+    let (r, g, b) = (channel("R"), channel("G"), channel("B"));
+    Estimate {
+        colors: (0..r.len()).map(|i| Vec3::new(r[i], g[i], b[i])).collect(),
+        variances: channel("variance"),
+    }
+}
+
+/// Gamma corrected image of an estimate clamped to white, to look at failures
+fn image(estimate: &Estimate) -> Image {
+    let mut buffer = Vec::with_capacity(estimate.colors.len() * 3);
+    for color in &estimate.colors {
+        buffer.push(color.x.min(1.0));
+        buffer.push(color.y.min(1.0));
+        buffer.push(color.z.min(1.0));
+    }
+    Image::from(WIDTH, HEIGHT, PixelFormat::RGBU8, &buffer).gamma_corrected()
+}
+
+/// Blocks where a channel differs by more than MAX_DEVIATION standard errors,
+/// as (x, y, channel, difference, deviations)
+fn deviating_blocks(test: &Estimate, reference: &Estimate) -> Vec<(u32, u32, char, f64, f64)> {
+    let scale = f64::from(REFERENCE_SAMPLES) / f64::from(SAMPLES);
+    let mut deviating = vec![];
+    for block_y in (0..HEIGHT).step_by(BLOCK as usize) {
+        for block_x in (0..WIDTH).step_by(BLOCK as usize) {
+            for (c, channel) in ['R', 'G', 'B'].iter().enumerate() {
+                let (mut difference, mut variance, mut count) = (0.0, 0.0, 0.0);
+                for y in block_y..(block_y + BLOCK).min(HEIGHT) {
+                    for x in block_x..(block_x + BLOCK).min(WIDTH) {
+                        let i = (y * WIDTH + x) as usize;
+                        difference += test.colors[i][c] - reference.colors[i][c];
+                        // Only the variance of the luminance is tracked, a channel is taken
+                        // to vary along with it in the proportion seen in the reference
+                        let reference_luminance = luminance(&reference.colors[i]);
+                        let ratio = if reference_luminance > NEGLIGIBLE {
+                            reference.colors[i][c] / reference_luminance
+                        } else {
+                            1.0
+                        };
+                        // Few samples miss the rare bright paths and underestimate their own
+                        // variance, the reference's is scaled to the samples of the test instead
+                        let expected = reference.variances[i] * scale;
+                        variance += (test.variances[i].max(expected) + reference.variances[i])
+                            * ratio
+                            * ratio;
+                        count += 1.0;
+                    }
+                }
+                let difference = difference / count;
+                let standard_error = variance.sqrt() / count;
+                let deviations = difference.abs() / standard_error;
+                if difference.abs() > NEGLIGIBLE && deviations > MAX_DEVIATION {
+                    deviating.push((block_x, block_y, *channel, difference, deviations));
+                }
+            }
+        }
+    }
+    deviating
+}
+
+fn check_scene(name: &str, scene: u32) {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_reference(
+            name,
+            &render_scene(scene, REFERENCE_SAMPLES, REFERENCE_SEED),
+        );
+        return;
+    }
+
+    let reference = read_reference(name);
+    let test = render_scene(scene, SAMPLES, SEED);
+    let deviating = deviating_blocks(&test, &reference);
+    if deviating.is_empty() {
+        return;
+    }
+
+    // Keep the images around to see what changed
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&directory).expect("cannot create the failure directory");
+    let (test_image, reference_image) = (image(&test), image(&reference));
+    for (suffix, image) in &[
+        ("test", &test_image),
+        ("reference", &reference_image),
+        ("difference", &test_image.difference_image(&reference_image)),
+    ] {
+        let path = directory.join(format!("{}_{}.ppm", name, suffix));
+        std::fs::write(&path, format!("{}\n", image.encode(ImageFormat::PPM)))
+            .expect("cannot write the failure images");
+    }
+
+    let blocks: Vec<String> = deviating
+        .iter()
+        .map(|(x, y, channel, difference, deviations)| {
+            format!(
+                "  block at ({}, {}): {} {:+.4}, {:.1} standard errors",
+                x, y, channel, difference, deviations
+            )
+        })
+        .collect();
+    panic!(
+        "{} differs from its reference\n{}\n{:?}\nimages written to {}",
+        name,
+        blocks.join("\n"),
+        test_image.compare(&reference_image),
+        directory.display()
+    );
+}
+
+#[test]
+fn random_scene() {
+    check_scene("random_scene", 0);
+}
+
+#[test]
+fn two_spheres() {
+    check_scene("two_spheres", 1);
+}
+
+#[test]
+fn two_perlin_spheres() {
+    check_scene("two_perlin_spheres", 2);
+}
+
+#[test]
+fn earth() {
+    check_scene("earth", 3);
+}
+
+#[test]
+fn simple_light() {
+    check_scene("simple_light", 4);
+}
+
+#[test]
+fn cornell_box() {
+    check_scene("cornell_box", 5);
+}
@@ -0,0 +1,120 @@
+//! Checks the bounce limits of the path settings cut the paths of their kind only.
+//!
+//! A unit sphere sits in a white furnace, with no light to sample: all the light a path brings
+//! back was found by following its bounces out of the sphere to the background.
+
+use rust_tracing::hitables::{HitableList, Sphere};
+use rust_tracing::integrators::{Integrator, PathTracer};
+use rust_tracing::materials::{Dielectric, Emissive, Lambertian, Material, Metal};
+use rust_tracing::render::{PathSettings, Scene};
+use rust_tracing::samplers::{IndependentSampler, Sampler, CAMERA_DIMENSION};
+use rust_tracing::structs::*;
+
+use std::sync::Arc;
+
+const SEED: u64 = 11;
+const SAMPLES: u32 = 2_000;
+const MAX_DEPTH: u32 = 64;
+
+fn white() -> Vec3 {
+    Vec3::new(1.0, 1.0, 1.0)
+}
+
+/// Paths only stopped by the bounce limits, roulette would end some of them at random
+fn settings() -> PathSettings {
+    PathSettings {
+        roulette_depth: None,
+        ..PathSettings::new(MAX_DEPTH)
+    }
+}
+
+/// Mean luminance of the paths aimed at the center of a sphere of the material
+fn furnace(material: Arc<dyn Material>, paths: PathSettings) -> f64 {
+    let mut world = HitableList::new(Arc::new(Emissive {
+        emit: Arc::new(SolidColor::new(white())),
+        multiplier: 1.0,
+    }));
+    world.push(Arc::new(Sphere {
+        center: Vec3::default(),
+        radius: 1.0,
+        material,
+    }));
+    let scene = Scene::new(&world, (0.0, 1.0), paths);
+
+    let mut sampler = IndependentSampler::new(SEED);
+    let mut stats = RunningStats::new();
+    let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+    for index in 0..SAMPLES {
+        sampler.start_pixel_sample(0, 0, index);
+        sampler.set_dimension(CAMERA_DIMENSION);
+        stats.push(PathTracer.li(&ray, &scene, &mut sampler, None));
+    }
+    luminance(&stats.mean())
+}
+
+#[test]
+fn diffuse_limit() {
+    let material = || -> Arc<dyn Material> {
+        Arc::new(Lambertian {
+            albedo: Arc::new(SolidColor::new(white())),
+        })
+    };
+    let cut = PathSettings {
+        max_diffuse: 0,
+        ..settings()
+    };
+    assert_eq!(furnace(material(), cut), 0.0);
+    // Out of a convex sphere after a single bounce
+    let one = PathSettings {
+        max_diffuse: 1,
+        ..settings()
+    };
+    assert!((furnace(material(), one) - 1.0).abs() < 1e-9);
+    // The other limits leave diffuse bounces alone
+    let others = PathSettings {
+        max_specular: 0,
+        max_transmission: 0,
+        ..settings()
+    };
+    assert!((furnace(material(), others) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn specular_limit() {
+    let material = || -> Arc<dyn Material> { Arc::new(Metal::new(white(), 0.0)) };
+    let cut = PathSettings {
+        max_specular: 0,
+        ..settings()
+    };
+    assert_eq!(furnace(material(), cut), 0.0);
+    let others = PathSettings {
+        max_diffuse: 0,
+        max_transmission: 0,
+        max_specular: 1,
+        ..settings()
+    };
+    assert!((furnace(material(), others) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn transmission_limit() {
+    let material = || -> Arc<dyn Material> { Arc::new(Dielectric::new(1.5)) };
+    // Only the light reflected off the front of the sphere comes back, about 4% head on
+    let cut = PathSettings {
+        max_transmission: 0,
+        ..settings()
+    };
+    let reflected = furnace(material(), cut);
+    assert!(reflected > 0.0 && reflected < 0.1, "{}", reflected);
+    // Going through takes two transmissions, in and out
+    let one = PathSettings {
+        max_transmission: 1,
+        ..settings()
+    };
+    assert!((furnace(material(), one) - reflected).abs() < 0.02);
+    let two = PathSettings {
+        max_transmission: 2,
+        ..settings()
+    };
+    assert!(furnace(material(), two) > 0.9);
+}